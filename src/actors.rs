@@ -1,8 +1,6 @@
 
 pub mod player;
-pub mod circle;
-pub mod triangle;
-pub mod rect;
-pub mod hexagon;
+pub mod drone;
 pub mod circle_boss;
-pub mod triangle_boss;
\ No newline at end of file
+pub mod triangle_boss;
+pub mod elite;
@@ -1,12 +1,10 @@
 use std::sync::mpsc::Sender;
 
-use async_trait::async_trait;
 use macroquad::prelude::*;
 use macroquad::math::Vec2;
 use macroquad::color::Color;
-use ::rand::{thread_rng, Rng};
 
-use crate::{audio_system::audio_handler::{SoundRequest, SoundType}, collision_system::collider::{CircleCollider, Collider}, entity_handler::enemy_type::EnemyType, event_system::{event::{Event, EventType}, interface::{Drawable, Enemy, GameEntity, Moveable, Object, Publisher, Updatable}}, grid_system::grid::EntityType, renderer::{artist::DrawCall, metal::ConfigType}, utils::{machine::{StateMachine, StateType}, timer::SimpleTimer}};   
+use crate::{audio_system::audio_handler::{SoundRequest, SoundType}, collision_system::{collider::{CircleCollider, Collider}, layer::CollisionLayer}, entity_handler::{behavior::{Agent, BehaviorMemory, Node}, enemy_type::EnemyType, steering::Steering, update_context::UpdateContext}, event_system::{buffer::EventBuffer, event::{Event, EventType}, interface::{Drawable, Enemy, GameEntity, Moveable, Object, Publisher, Updatable}}, grid_system::grid::EntityType, renderer::{artist::DrawCall, metal::ConfigType}, utils::{knockback::{Knockback, DEFAULT_DAMPING}, machine::{StateMachine, StateType}, timer::SimpleTimer}};   
 
 const MAX_HEALTH: i32 = 60;
 
@@ -28,90 +26,38 @@ pub struct CircleBoss{
     machine: StateMachine,
     //State specifics
     is_alive: bool,
+    knockback: Knockback,
+    steering: Steering,
     //Emittion
     emittion_configs: Vec<(StateType, ConfigType)>,
-    //Dash specifics
-    boost_timer: SimpleTimer,
-    boost_duration: SimpleTimer,
-    is_boosting: bool,
-    boost_target: Option<Vec2>
+    //Behaviour
+    behavior: Node,
+    memory: BehaviorMemory
 }
 
 impl CircleBoss{
-    async fn select_movement(&mut self, delta: f32, overide: Option<Vec2>){
-        let now = get_time();
-
-        if self.boost_duration.expired(now){
-            self.is_boosting = false;
-            self.boost_target = None;
-        }
-
-        if !self.is_boosting{
-            self.move_to(delta, overide);
-        }
-        else{
-            self.boost(delta).await;
-        }
-    }
-
-    async fn boost(&mut self, delta: f32){
-        let speed = self.speed * 10.0;
-        
-        if self.boost_target.is_none(){
-            let to_player = self.target - self.pos;
-            let dist_to_player = to_player.length();
-            
-            let overshoot_distance = self.calculate_overshoot_distance(dist_to_player);
-            
-            let dir = if dist_to_player > 1e-4 {
-                (self.target - self.pos).normalize()
-            } else {
-                Vec2::ZERO
-            };
-
-            self.boost_target = Some(self.target + dir * overshoot_distance);
-            let request = SoundRequest::new(true, true, 0.1);
-            self.publish(Event::new((SoundType::CircleBossDash, request), EventType::PlaySound)).await;
-        }
-        
-        if let Some(target) = self.boost_target{
-            self.pos = self.pos.move_towards(target, speed * delta);
-        }
-    }
-
-    fn calculate_overshoot_distance(&self, distance: f32) -> f32{
-        let base_overshoot = 150.0;
-        let distance_factor = (distance * 0.25).min(200.0);
-        
-        // Add some randomness
-        let mut rng = thread_rng();
-        let random_variation = rng.gen_range(-50.0..=100.0); // Bias toward longer overshoots
-        
-        (base_overshoot + distance_factor + random_variation).max(50.0)
+    ///Dashes past the player every few seconds, chasing it along the flow field in between.
+    fn get_behavior() -> Node{
+        return Node::Selector(vec![
+            Node::Sequence(vec![
+                Node::WaitForCooldown{ duration: 5.0, scaled: false },
+                Node::Dash{ speed: 10.0, duration: 2.0 }
+            ]),
+            Node::Chase{ flow_field: true }
+        ])
     }
 }
 
 //========== Circle interfaces =========
-#[async_trait]
 impl Updatable for CircleBoss{
-    async fn update(&mut self, delta: f32, mut params: Vec<Box<dyn std::any::Any + Send>>) {
+    fn update(&mut self, delta: f32, context: &UpdateContext) {
         if self.is_alive{
             //Update target position
-            let mut overide = None;
             let mut play_sound = false;
-            let now = get_time();
+            let now = context.frame.time;
 
-            while let Some(param_item) = params.pop(){
-                if let Some(player_pos) = param_item.downcast_ref::<Vec2>(){
-                    if !self.is_boosting{
-                        self.target = *player_pos;
-                    }
-                }
-                //REVIEW: No override is ever given since Bosses don't register enemy collitions.
-                if let Some(overide_pos) = param_item.downcast_ref::<Option<Vec2>>(){
-                    overide = *overide_pos;
-                }
-            }
+            self.target = context.frame.player_pos;
+            self.steering = context.steering;
 
             if self.hit_timer.expired(now){
                 self.was_hit = false;
@@ -126,11 +72,6 @@ impl Updatable for CircleBoss{
                         self.machine.transition(StateType::Moving);
                     },
                     StateType::Moving => {
-                        if self.boost_timer.expired(now){
-                            self.is_boosting = true;
-                            self.boost_timer.set(now, 5.0);
-                            self.boost_duration.set(now, 2.0);
-                        }
                         can_move = true;
                     },
                     StateType::Hit => {
@@ -154,21 +95,29 @@ impl Updatable for CircleBoss{
             }
 
             if can_move{
-                self.select_movement(delta, overide).await;
+                let mut agent = Agent::new(self.id, self.pos, self.size, self.speed, delta, context);
+                self.behavior.run(&mut agent, &mut self.memory);
+
+                for event in agent.events{
+                    self.publish(event);
+                }
+
+                self.move_to(delta * context.frame.difficulty.speed, context.overide);
             }
 
+            self.pos += self.knockback.step(delta);
             self.collider.update(self.pos);
-            self.publish(Event::new((self.id, EntityType::Enemy, self.pos, self.size), EventType::InsertOrUpdateToGrid)).await;
+            self.publish(Event::new((self.id, EntityType::Enemy, self.collider.get_layer(), self.pos, self.size), EventType::InsertOrUpdateToGrid));
         
             if play_sound{
                     // Emit sound request
                     if self.health > 0{
                         let srequest = SoundRequest::new(true, false, 0.1);
-                        self.publish(Event::new((SoundType::RectHit, srequest), EventType::PlaySound)).await;
+                        self.publish(Event::new((SoundType::RectHit, srequest), EventType::PlaySound));
                     }
                     else{
                         let srequest = SoundRequest::new(true, false, 0.1);
-                        self.publish(Event::new((SoundType::EnemyDeath, srequest), EventType::PlaySound)).await;
+                        self.publish(Event::new((SoundType::EnemyDeath, srequest), EventType::PlaySound));
                     }
                 }
         }
@@ -193,10 +142,11 @@ impl Object for CircleBoss{
 impl Moveable for CircleBoss{
     #[inline(always)]
     fn move_to(&mut self, delta: f32, overide: Option<Vec2>) -> (f32, f32){
-        let mut new_pos = overide.unwrap_or(self.target);
-        
-        new_pos = self.pos.move_towards(new_pos, self.speed * delta);
-        self.pos = new_pos;
+        if let Some(destination) = overide{
+            self.memory.set_destination(destination);
+        }
+
+        self.pos = self.memory.step(self.pos, &self.steering, self.speed, delta);
 
         return self.pos.into()
     }
@@ -238,9 +188,10 @@ impl GameEntity for CircleBoss{
     }
 }
 
-#[async_trait]
 impl Enemy for CircleBoss{
     fn new(id: u64, pos: Vec2, size: f32, color: Color, player_pos: Vec2, sender:Sender<Event>) -> Self where Self: Sized {
+        let behavior = CircleBoss::get_behavior();
+
         let enemy =  CircleBoss {
             id: id,
             pos: pos, 
@@ -254,17 +205,17 @@ impl Enemy for CircleBoss{
             hit_timer: SimpleTimer::blank(),
 
             sender: sender,
-            collider: CircleCollider::new(pos.x, pos.y, size),
+            collider: CircleCollider::new(pos.x, pos.y, size, CollisionLayer::Boss),
             machine: StateMachine::new(),
 
             is_alive: true,
+            knockback: Knockback::new(DEFAULT_DAMPING),
+            steering: Steering::default(),
             
             emittion_configs: vec![(StateType::Hit, ConfigType::EnemyDeath)],
             
-            boost_timer: SimpleTimer::blank(),
-            boost_duration: SimpleTimer::blank(),
-            is_boosting: false,
-            boost_target: None
+            memory: BehaviorMemory::new(&behavior),
+            behavior: behavior
         };
 
         return enemy
@@ -274,8 +225,8 @@ impl Enemy for CircleBoss{
         self.id = id;
     }
 
-    async fn register_configs(&self){
-        self.publish(Event::new((self.get_id(), self.emittion_configs.clone()), EventType::RegisterEmitterConf)).await;
+    fn register_configs(&self){
+        self.publish(Event::new((self.get_id(), self.emittion_configs.clone()), EventType::RegisterEmitterConf));
     }
 
     fn set_pos(&mut self, new_pos: Vec2){
@@ -286,6 +237,10 @@ impl Enemy for CircleBoss{
         self.color = new_color;
     }
 
+    fn get_color(&self) -> Color{
+        return self.color
+    }
+
     fn set_size(&mut self, new_size: f32){
         self.size = new_size;
     }
@@ -306,6 +261,10 @@ impl Enemy for CircleBoss{
         self.machine.transition(state);
     }
 
+    fn apply_impulse(&mut self, impulse: Vec2){
+        self.knockback.apply(impulse);
+    }
+
     fn get_state(&self) -> Option<StateType>{
         if let Ok(entry) = self.machine.get_state().try_lock(){
             return Some(*entry)
@@ -324,6 +283,10 @@ impl Enemy for CircleBoss{
         return EnemyType::CircleBoss
     }
 
+    fn into_base(self: Box<Self>) -> Box<dyn Enemy>{
+        return self
+    }
+
     fn reset(&mut self, id: u64, pos: Vec2, color: Color, size: f32, target: Vec2, is_alive: bool){
         self.id = id;
         self.pos = pos;
@@ -331,23 +294,22 @@ impl Enemy for CircleBoss{
         self.size = size;
         self.target = target;
         self.is_alive = is_alive;
-        self.collider = CircleCollider::new(pos.x, pos.y, size);
+        self.knockback.reset();
+        self.steering = Steering::default();
+        self.collider = CircleCollider::new(pos.x, pos.y, size, CollisionLayer::Boss);
         self.machine.transition(StateType::Idle);
 
         self.health = MAX_HEALTH;
         self.was_hit = false;
         self.hit_timer = SimpleTimer::blank();
 
-        self.boost_timer = SimpleTimer::blank();
-        self.boost_duration = SimpleTimer::blank();
-        self.is_boosting = false;
+        self.memory = BehaviorMemory::new(&self.behavior);
     }
 }
 
-#[async_trait]
 impl Publisher for CircleBoss{
-    async fn publish(&self, event: Event){
-        let _ = self.sender.send(event);
+    fn publish(&self, event: Event){
+        EventBuffer::send(&self.sender, event);
     }
 }
 
@@ -0,0 +1,373 @@
+use std::sync::mpsc::Sender;
+
+use macroquad::prelude::*;
+use macroquad::math::Vec2;
+use macroquad::color::Color;
+
+use crate::{audio_system::audio_handler::{SoundRequest, SoundType}, collision_system::collider::Collider, entity_handler::{archetype::{Archetypes, EnemyArchetype, ShapeCollider}, behavior::{Agent, BehaviorMemory}, enemy_type::EnemyType, steering::Steering, update_context::UpdateContext}, event_system::{buffer::EventBuffer, event::{Event, EventType}, interface::{Drawable, Enemy, GameEntity, Moveable, Object, Publisher, Updatable}}, grid_system::grid::EntityType, objects::bubble::Bubble, renderer::{artist::DrawCall, metal::ConfigType}, utils::{clock::Clock, knockback::{Knockback, DEFAULT_DAMPING}, machine::{StateMachine, StateType}}};
+
+//Seconds between outline flashes, while a fuse burns
+const FLASH_INTERVAL: f64 = 0.1;
+
+/*
+    Data driven enemy. Size, speed, health, looks, sounds and behaviour tree all come from its `EnemyArchetype`,
+    so new enemies are added as archetype files in `assets\enemies`.
+
+    Drones with a `bubble` project it around themselves, shielding the enemies inside.
+    Drones with an `Explode` node blow up when they die, or when their fuse runs out.
+    While the fuse burns they turn red, and flash their outline and blast radius.
+*/
+pub struct Drone{
+    //Attributes
+    id: u64,
+    kind: EnemyType,
+    archetype: &'static EnemyArchetype,
+    pos: Vec2,
+    size: f32,
+    color: Color,
+    //Health variables
+    health: i32,
+    //Splits left down the chain of children, drawn as rings
+    splits_left: u8,
+    //Components
+    sender: Sender<Event>,
+    collider: ShapeCollider,
+    machine: StateMachine,
+    bubble: Option<Bubble>,
+    //State specifics
+    is_alive: bool,
+    knockback: Knockback,
+    steering: Steering,
+    //Emittion
+    emittion_configs: Vec<(StateType, ConfigType)>,
+    //Behaviour
+    memory: BehaviorMemory,
+}
+
+impl Drone{
+    pub fn with_archetype(id: u64, kind: EnemyType, pos: Vec2, size: f32, color: Color, sender: Sender<Event>) -> Self{
+        let archetype = match kind{
+            EnemyType::Drone(archetype) => Archetypes::get(archetype),
+            _ => panic!("Drones are built from an archetype, got {:?}", kind),
+        };
+
+        return Drone {
+            id: id,
+            kind: kind,
+            archetype: archetype,
+            pos: pos,
+            size: size,
+            color: color,
+
+            health: archetype.health,
+            splits_left: Archetypes::get_splits_left(archetype),
+
+            sender: sender,
+            collider: archetype.get_collider(pos.x, pos.y, size),
+            machine: StateMachine::new(),
+            bubble: archetype.bubble.map(|config| Bubble::new(id, pos, config)),
+
+            is_alive: true,
+            knockback: Knockback::new(DEFAULT_DAMPING),
+            steering: Steering::default(),
+
+            emittion_configs: vec![(StateType::Hit, archetype.emitter.clone())],
+
+            memory: BehaviorMemory::new(&archetype.behavior),
+        }
+    }
+
+    ///Runs the archetypes behaviour, then moves towards the destination it picked.
+    fn behave(&mut self, delta: f32, context: &UpdateContext){
+        let mut agent = Agent::new(self.id, self.pos, self.size, self.archetype.speed, delta, context);
+        self.archetype.behavior.run(&mut agent, &mut self.memory);
+
+        for event in agent.events{
+            self.publish(event);
+        }
+
+        self.move_to(delta * context.frame.difficulty.speed, context.overide);
+    }
+
+    ///Blows up in the radius of the archetypes `Explode` node. The blast is resolved by the `Handler`, through the `Grid`.
+    fn detonate(&mut self){
+        let radius = self.archetype.behavior.get_blast().unwrap_or_default();
+
+        self.set_alive(false);
+        self.machine.transition(StateType::Hit);
+
+        self.publish(Event::new((self.id, self.pos, radius), EventType::EnemyExplosion));
+    }
+
+    ///Alternates the outline every `FLASH_INTERVAL`, while the fuse burns.
+    #[inline(always)]
+    fn get_flash_color(&self, now: f64) -> Color{
+        let remaining = self.memory.get_fuse().map_or(0.0, |at| (at - now).max(0.0));
+
+        match (remaining / FLASH_INTERVAL) as i32 % 2 == 0{
+            true => WHITE,
+            false => BLACK,
+        }
+    }
+
+    fn play_sound(&self, sound: SoundType){
+        let srequest = SoundRequest::new(true, false, self.archetype.volume);
+        self.publish(Event::new((sound, srequest), EventType::PlaySound));
+    }
+}
+
+//========== Drone interfaces =========
+impl Updatable for Drone{
+    fn update(&mut self, delta: f32, context: &UpdateContext) {
+        if self.is_alive{
+            self.steering = context.steering;
+
+            let mut sound = None;
+            let mut detonate = false;
+
+            //Update based on state machine
+            if let Ok(state) = self.machine.get_state().try_lock(){
+                match *state{
+                    StateType::Idle => {
+                        self.machine.transition(StateType::Moving);
+                    },
+                    StateType::Moving => {
+                        self.behave(delta, context);
+                        detonate = self.memory.get_fuse().is_some_and(|at| context.frame.time >= at);
+                    },
+                    StateType::Hit => {
+                        self.health -= 1;
+
+                        if self.health <= 0 {
+                            self.set_alive(false);
+                            detonate = self.archetype.behavior.get_blast().is_some();
+                            sound = Some(self.archetype.death_sound.clone());
+                        }
+                        else{
+                            self.machine.transition(StateType::Moving);
+                            sound = self.archetype.hit_sound.clone();
+                        }
+                    },
+                    _ => (), //Unreachable
+                }
+            }
+
+            if detonate{
+                self.detonate();
+                sound = Some(self.archetype.death_sound.clone());
+            }
+
+            self.pos += self.knockback.step(delta);
+            self.collider.update(self.pos);
+            if let Some(bubble) = &mut self.bubble{
+                bubble.follow(self.pos, context.frame.time);
+            }
+            self.publish(Event::new((self.id, EntityType::Enemy, self.collider.get().get_layer(), self.pos, self.size), EventType::InsertOrUpdateToGrid));
+
+            if let Some(sound) = sound{
+                self.play_sound(sound);
+            }
+        }
+    }
+}
+
+impl Object for Drone{
+    #[inline(always)]
+    fn get_pos(&self) -> Vec2{
+        return self.pos
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        return self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any{
+        return self
+    }
+}
+
+impl Moveable for Drone{
+    #[inline(always)]
+    fn move_to(&mut self, delta: f32, overide: Option<Vec2>) -> (f32, f32){
+        if let Some(destination) = overide{
+            self.memory.set_destination(destination);
+        }
+
+        self.pos = self.memory.step(self.pos, &self.steering, self.archetype.speed, delta);
+
+        return self.pos.into()
+    }
+}
+
+impl Drawable for Drone{
+    #[inline(always)]
+    fn get_draw_call(&self) -> DrawCall {
+        let color = match self.memory.get_fuse(){
+            Some(_) => RED,
+            None => self.archetype.get_color(self.color, self.health),
+        };
+
+        return self.archetype.get_draw_call(self.pos.x, self.pos.y, self.size, color)
+    }
+
+    fn should_emit(&self) -> bool{
+        if let Ok(state) = self.machine.get_state().try_lock(){
+            if state.eq(&StateType::Hit) && !self.is_alive{
+                return true
+            }
+        }
+
+        return false
+    }
+}
+
+impl GameEntity for Drone{
+    #[inline(always)]
+    fn get_id(&self) -> u64 {
+        return self.id
+    }
+
+    fn get_size(&self) -> f32 {
+        return self.size
+    }
+
+    fn collides(&self, other: &dyn Collider) -> bool {
+        return self.collider.get().collides_with(other)
+    }
+
+    fn get_collider(&self) -> &dyn Collider {
+        return self.collider.get()
+    }
+}
+
+impl Enemy for Drone{
+    ///Builds a drone of the first archetype, use `Drone::with_archetype` for the others.
+    fn new(id: u64, pos: Vec2, size: f32, color: Color, _player_pos: Vec2, sender:Sender<Event>) -> Self where Self: Sized {
+        return Drone::with_archetype(id, EnemyType::Drone(0), pos, size, color, sender)
+    }
+
+    fn set_id(&mut self, id: u64){
+        self.id = id;
+
+        if let Some(bubble) = &mut self.bubble{
+            bubble.owner = id;
+        }
+    }
+
+    fn register_configs(&self){
+        self.publish(Event::new((self.get_id(), self.emittion_configs.clone()), EventType::RegisterEmitterConf));
+    }
+
+    fn set_pos(&mut self, new_pos: Vec2){
+        self.pos = new_pos
+    }
+
+    fn set_color(&mut self, new_color: Color){
+        self.color = new_color;
+    }
+
+    fn get_color(&self) -> Color{
+        return self.color
+    }
+
+    fn set_size(&mut self, new_size: f32){
+        self.size = new_size;
+    }
+
+    ///Drones read the player from the `UpdateContext`, there is no target to keep.
+    fn set_target(&mut self, _new_target: Vec2){}
+
+    fn is_alive(&self) -> bool{
+        return self.is_alive
+    }
+
+    fn set_alive(&mut self, alive: bool) {
+        self.is_alive = alive;
+    }
+
+    fn force_state(&mut self, state: StateType){
+        self.machine.transition(state);
+    }
+
+    fn apply_impulse(&mut self, impulse: Vec2){
+        self.knockback.apply(impulse);
+    }
+
+    fn get_state(&self) -> Option<StateType>{
+        if let Ok(entry) = self.machine.get_state().try_lock(){
+            return Some(*entry)
+        }
+        return None
+    }
+
+    ///Body, a ring per split left, the bubble while it holds, and the flashing outline and blast radius while a fuse burns.
+    fn get_all_draw_calls(&self) -> Vec<DrawCall>{
+        let mut calls = vec![self.get_draw_call()];
+
+        if let Some(bubble) = self.bubble.as_ref().filter(|bubble| bubble.is_active()){
+            calls.push(bubble.get_draw_call());
+        }
+
+        for ring in 1..=self.splits_left{
+            let radius = self.size * (1.0 - ring as f32 / (self.splits_left as f32 + 1.0));
+            calls.push(DrawCall::CircleLines(self.pos.x, self.pos.y, radius, 4.0, BLACK));
+        }
+
+        if let (Some(_), Some(radius)) = (self.memory.get_fuse(), self.archetype.behavior.get_blast()){
+            let outline_color = self.get_flash_color(Clock::now());
+
+            calls.push(self.archetype.get_outline_call(self.pos.x, self.pos.y, self.size, 8.0, outline_color));
+            calls.push(DrawCall::CircleLines(self.pos.x, self.pos.y, radius, 4.0, outline_color.with_alpha(0.4)));
+        }
+
+        return calls
+    }
+
+    fn get_type(&self) -> EnemyType{
+        return self.kind
+    }
+
+    fn get_bubble(&self) -> Option<&Bubble>{
+        return self.bubble.as_ref()
+    }
+
+    fn absorb(&mut self, now: f64) -> bool{
+        return self.bubble.as_mut().is_some_and(|bubble| bubble.absorb(now))
+    }
+
+    fn into_base(self: Box<Self>) -> Box<dyn Enemy>{
+        return self
+    }
+
+    fn reset(&mut self, id: u64, pos: Vec2, color: Color, size: f32, _target: Vec2, is_alive: bool){
+        self.id = id;
+        self.pos = pos;
+        self.color = color;
+        self.size = size;
+        self.is_alive = is_alive;
+        self.knockback.reset();
+        self.steering = Steering::default();
+        self.collider = self.archetype.get_collider(pos.x, pos.y, size);
+        self.machine.transition(StateType::Idle);
+
+        self.health = self.archetype.health;
+        self.bubble = self.archetype.bubble.map(|config| Bubble::new(id, pos, config));
+        self.memory = BehaviorMemory::new(&self.archetype.behavior);
+    }
+}
+
+impl Publisher for Drone{
+    fn publish(&self, event: Event){
+        EventBuffer::send(&self.sender, event);
+    }
+}
+
+impl std::fmt::Debug for Drone{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Drone")
+            .field("id", &self.id)
+            .field("pos", &self.pos)
+            .field("type", &self.archetype.name)
+            .finish()
+    }
+}
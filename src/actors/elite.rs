@@ -0,0 +1,342 @@
+use std::sync::mpsc::Sender;
+
+use macroquad::prelude::*;
+use macroquad::math::Vec2;
+use macroquad::color::Color;
+use ::rand::{seq::SliceRandom, thread_rng, Rng};
+
+use crate::{audio_system::audio_handler::{SoundRequest, SoundType}, collision_system::collider::Collider, entity_handler::{enemy_type::EnemyType, update_context::UpdateContext}, event_system::{buffer::EventBuffer, event::{Event, EventType}, interface::{Drawable, Enemy, GameEntity, Object, Publisher, Updatable}}, objects::bubble::Bubble, renderer::artist::DrawCall, utils::machine::StateType};
+
+use super::drone::Drone;
+
+pub const MAX_LEVEL: u8 = 3;
+//Speed gained per level by fast elites
+const FAST_SPEED: f32 = 0.25;
+//Hits absorbed per level by armored elites
+const ARMOR_PER_LEVEL: i32 = 2;
+const EXPLOSION_RADIUS: f32 = 150.0;
+const EXPLOSION_RADIUS_PER_LEVEL: f32 = 50.0;
+//Seconds between trail drops, and how long each stays
+const TRAIL_INTERVAL: f64 = 0.2;
+const TRAIL_DURATION: f64 = 2.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Modifier{
+    ///Moves faster.
+    Fast,
+    ///Takes extra hits.
+    Armored,
+    ///Heals extra hits back when striking the player.
+    Vampiric,
+    ///Explodes on death.
+    Volatile,
+    ///Leaves a trail that hurts the player.
+    Trail
+}
+
+impl Modifier{
+    const ALL: [Modifier; 5] = [Modifier::Fast, Modifier::Armored, Modifier::Vampiric, Modifier::Volatile, Modifier::Trail];
+
+    #[inline(always)]
+    fn get_color(&self) -> Color{
+        match self{
+            Modifier::Fast => YELLOW,
+            Modifier::Armored => LIGHTGRAY,
+            Modifier::Vampiric => MAROON,
+            Modifier::Volatile => ORANGE,
+            Modifier::Trail => PURPLE,
+        }
+    }
+}
+
+/*
+    Elite version of any enemy. Wraps the enemy, and adds one modifier per level on top of it.
+    Elites are rolled by the `Recycler`, and unwrapped with `into_base` when they are recycled.
+
+    Modifiers scale with the level, and so does the bonus score `UIController` gives for them.
+    Each modifier is drawn as a ring of its color around the enemy.
+*/
+pub struct Elite{
+    inner: Box<dyn Enemy>,
+    level: u8,
+    modifiers: Vec<Modifier>,
+    //Extra hits left, and the most it can have
+    armor: i32,
+    max_armor: i32,
+    //Time of the next trail drop
+    next_trail: f64,
+    sender: Sender<Event>
+}
+
+impl Elite{
+    pub fn wrap(inner: Box<dyn Enemy>, level: u8, sender: Sender<Event>) -> Elite{
+        let level = level.clamp(1, MAX_LEVEL);
+
+        let modifiers = Modifier::ALL
+            .choose_multiple(&mut thread_rng(), level as usize)
+            .copied()
+            .collect::<Vec<Modifier>>();
+
+        let mut elite = Elite {
+            inner: inner,
+            level: level,
+            modifiers: modifiers,
+            armor: 0,
+            max_armor: 0,
+            next_trail: 0.0,
+            sender: sender
+        };
+        elite.restore_armor();
+
+        return elite
+    }
+
+    ///Level 1 is the most common, level 3 the rarest.
+    pub fn roll_level() -> u8{
+        let roll = thread_rng().gen::<f32>();
+
+        if roll < 0.05{
+            return 3
+        }
+        else if roll < 0.3{
+            return 2
+        }
+        return 1
+    }
+
+    #[inline(always)]
+    pub fn get_level(&self) -> u8{
+        return self.level
+    }
+
+    #[inline(always)]
+    fn has(&self, modifier: Modifier) -> bool{
+        return self.modifiers.contains(&modifier)
+    }
+
+    /*
+        Armored elites start with their extra hits, vampiric ones
+        start without and can heal up to a level worth of them.
+    */
+    fn restore_armor(&mut self){
+        let level = self.level as i32;
+
+        self.armor = if self.has(Modifier::Armored) { ARMOR_PER_LEVEL * level } else { 0 };
+        self.max_armor = self.armor + if self.has(Modifier::Vampiric) { level } else { 0 };
+    }
+
+    ///Vampiric elites heal an extra hit back every time they strike the player.
+    pub fn on_strike(&mut self){
+        if self.has(Modifier::Vampiric){
+            self.armor = (self.armor + 1).min(self.max_armor);
+        }
+    }
+
+    fn explode(&self){
+        let radius = EXPLOSION_RADIUS + EXPLOSION_RADIUS_PER_LEVEL * self.level as f32;
+        self.publish(Event::new((self.get_id(), self.get_pos(), radius), EventType::EnemyExplosion));
+
+        let srequest = SoundRequest::new(true, false, 0.12);
+        self.publish(Event::new((SoundType::PentagonExplosion, srequest), EventType::PlaySound));
+    }
+
+    ///Rings in the colors of the modifiers, thicker the higher the level.
+    pub fn get_aura(&self) -> Vec<DrawCall>{
+        let pos = self.get_pos();
+        let thickness = 2.0 + 2.0 * self.level as f32;
+
+        return self.modifiers.iter()
+            .enumerate()
+            .map(|(i, modifier)| {
+                let radius = self.get_size() + thickness * (i as f32 + 1.5);
+                DrawCall::CircleLines(pos.x, pos.y, radius, thickness, modifier.get_color().with_alpha(0.8))
+            })
+            .collect()
+    }
+}
+
+//========== Elite interfaces =========
+impl Updatable for Elite{
+    fn update(&mut self, delta: f32, context: &UpdateContext) {
+        let was_alive = self.inner.is_alive();
+
+        //Only the movement speeds up, timers and knockback keep their pace
+        if self.has(Modifier::Fast){
+            let mut frame = *context.frame;
+            frame.difficulty.speed *= 1.0 + FAST_SPEED * self.level as f32;
+
+            self.inner.update(delta, &UpdateContext { frame: &frame, ..*context });
+        }
+        else{
+            self.inner.update(delta, context);
+        }
+
+        if !self.inner.is_alive(){
+            if was_alive && self.has(Modifier::Volatile){
+                self.explode();
+            }
+            return;
+        }
+
+        let now = context.frame.time;
+
+        if self.has(Modifier::Trail) && now >= self.next_trail{
+            self.next_trail = now + TRAIL_INTERVAL;
+
+            let duration = TRAIL_DURATION * self.level as f64;
+            self.publish(Event::new((self.get_pos(), self.get_size() * 0.6, now + duration), EventType::DropTrail));
+        }
+    }
+}
+
+impl Object for Elite{
+    #[inline(always)]
+    fn get_pos(&self) -> Vec2{
+        return self.inner.get_pos()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        return self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any{
+        return self
+    }
+}
+
+impl Drawable for Elite{
+    #[inline(always)]
+    fn get_draw_call(&self) -> DrawCall {
+        return self.inner.get_draw_call()
+    }
+
+    fn should_emit(&self) -> bool{
+        return self.inner.should_emit()
+    }
+}
+
+impl GameEntity for Elite{
+    #[inline(always)]
+    fn get_id(&self) -> u64 {
+        return self.inner.get_id()
+    }
+
+    fn get_size(&self) -> f32 {
+        return self.inner.get_size()
+    }
+
+    fn collides(&self, other: &dyn Collider) -> bool {
+        return self.inner.collides(other)
+    }
+
+    fn get_collider(&self) -> &dyn Collider {
+        return self.inner.get_collider()
+    }
+}
+
+impl Enemy for Elite{
+    ///Builds a level 1 elite drone of the first archetype, use `Elite::wrap` for any other enemy.
+    fn new(id: u64, pos: Vec2, size: f32, color: Color, player_pos: Vec2, sender:Sender<Event>) -> Self where Self: Sized {
+        let inner = Box::new(Drone::new(id, pos, size, color, player_pos, sender.clone()));
+
+        return Elite::wrap(inner, 1, sender)
+    }
+
+    fn set_id(&mut self, id: u64){
+        self.inner.set_id(id);
+    }
+
+    fn register_configs(&self){
+        self.inner.register_configs();
+    }
+
+    fn set_pos(&mut self, new_pos: Vec2){
+        self.inner.set_pos(new_pos);
+    }
+
+    fn set_color(&mut self, new_color: Color){
+        self.inner.set_color(new_color);
+    }
+
+    fn get_color(&self) -> Color{
+        return self.inner.get_color()
+    }
+
+    fn set_size(&mut self, new_size: f32){
+        self.inner.set_size(new_size);
+    }
+
+    fn set_target(&mut self, new_target: Vec2){
+        self.inner.set_target(new_target);
+    }
+
+    fn is_alive(&self) -> bool{
+        return self.inner.is_alive()
+    }
+
+    fn set_alive(&mut self, alive: bool) {
+        self.inner.set_alive(alive);
+    }
+
+    ///Hits are taken by the armor first.
+    fn force_state(&mut self, state: StateType){
+        if state == StateType::Hit && self.armor > 0{
+            self.armor -= 1;
+            return;
+        }
+
+        self.inner.force_state(state);
+    }
+
+    fn apply_impulse(&mut self, impulse: Vec2){
+        self.inner.apply_impulse(impulse);
+    }
+
+    fn get_state(&self) -> Option<StateType>{
+        return self.inner.get_state()
+    }
+
+    fn get_all_draw_calls(&self) -> Vec<DrawCall>{
+        return self.inner.get_all_draw_calls()
+    }
+
+    fn get_type(&self) -> EnemyType{
+        return self.inner.get_type()
+    }
+
+    fn get_bubble(&self) -> Option<&Bubble>{
+        return self.inner.get_bubble()
+    }
+
+    fn absorb(&mut self, now: f64) -> bool{
+        return self.inner.absorb(now)
+    }
+
+    fn into_base(self: Box<Self>) -> Box<dyn Enemy>{
+        return self.inner.into_base()
+    }
+
+    fn reset(&mut self, id: u64, pos: Vec2, color: Color, size: f32, target: Vec2, is_alive: bool){
+        self.inner.reset(id, pos, color, size, target, is_alive);
+
+        self.next_trail = 0.0;
+        self.restore_armor();
+    }
+}
+
+impl Publisher for Elite{
+    fn publish(&self, event: Event){
+        EventBuffer::send(&self.sender, event);
+    }
+}
+
+impl std::fmt::Debug for Elite{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Elite")
+            .field("id", &self.get_id())
+            .field("pos", &self.get_pos())
+            .field("level", &self.level)
+            .field("modifiers", &self.modifiers)
+            .finish()
+    }
+}
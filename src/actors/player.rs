@@ -1,11 +1,10 @@
-use async_trait::async_trait;
 use macroquad::prelude::*;
 use macroquad::math::Vec2;
 use macroquad::color::Color;
 
 use std::sync::mpsc::Sender;
 
-use crate::{audio_system::audio_handler::{SoundRequest, SoundType}, collision_system::collider::{Collider, RectCollider}, event_system::{event::{Event, EventType}, interface::{GameEntity, Playable, Projectile, Updatable}}, objects::{bullet::{Bullet, ProjectileType}, shield::Shield}, renderer::{artist::DrawCall, metal::ConfigType}, utils::{counter::RechargebleCounter, globals::Global, machine::{StateMachine, StateType}, timer::{SimpleTimer, Timer}, tinkerer::VariablesSettings}};
+use crate::{audio_system::audio_handler::{SoundRequest, SoundType}, collision_system::{collider::{Collider, RectCollider}, collision_detector::{CollisionImpact, PLAYER_MASS}, layer::CollisionLayer}, entity_handler::update_context::UpdateContext, event_system::{event::{Event, EventType}, interface::{GameEntity, Playable, Updatable}}, grid_system::{hazard::HazardKind, wall::WallImpact}, objects::{bullet::{BulletSpawn, ProjectileType}, shield::Shield}, renderer::{artist::DrawCall, metal::ConfigType}, utils::{counter::RechargebleCounter, globals::Global, machine::{StateMachine, StateType}, timer::{SimpleTimer, Timer}, tinkerer::VariablesSettings}};
 use crate::event_system::interface::{Publisher, Subscriber, Object, Moveable, Drawable};
 
 
@@ -33,13 +32,17 @@ pub struct Player{
     boost_timer: SimpleTimer,
     //State specifics
     immune_timer: Timer,
-    bounce: bool,
+    pending_damage: bool,
     emitted_grayscale: bool,
+    //Hazards the player stands in, and the friction multiplier they apply
+    hazards: Vec<HazardKind>,
+    surface_friction: f32,
     //Firing specifics
     left_fire: bool,
     attack_speed: SimpleTimer,
-    bullets: Vec<Bullet>,
+    ammo: usize,
     reload: SimpleTimer,
+    laser_timer: SimpleTimer,
     //Emitter specifics
     emittion_configs: Vec<(StateType, ConfigType)>,
     //Sound specifics
@@ -50,9 +53,10 @@ pub struct Player{
 
 impl Player{
 
-    pub async fn new(x: f32, y:f32, size: f32, color: Color, sender: Sender<Event>, variables: VariablesSettings) -> Self{
+    ///`id` is handed out by the `Handler`, so it never collides with enemies and bullets.
+    pub fn new(id: u64, x: f32, y:f32, size: f32, color: Color, sender: Sender<Event>, variables: VariablesSettings) -> Self{
         let player = Player { 
-            id: 0,
+            id: id,
             health: Global::get_player_health(),
             pos: Vec2::new(x, y),
             direction: Vec2::new(0.0, 0.0),
@@ -66,7 +70,7 @@ impl Player{
 
             sender: sender.clone(),
             machine: StateMachine::new(),
-            collider: RectCollider::new(x, y, size, size * 2.0),
+            collider: RectCollider::new(x, y, size, size * 2.0, CollisionLayer::Player),
             
             shield: Shield::new(Vec2::new(x, y), (size * 3.0) as usize, BLUE),
             shield_counter: RechargebleCounter::new(
@@ -82,13 +86,16 @@ impl Player{
             boost_timer: SimpleTimer::blank(),
             
             immune_timer: Timer::new(),
-            bounce: false,
+            pending_damage: false,
             emitted_grayscale: false,
+            hazards: Vec::new(),
+            surface_friction: 1.0,
 
             left_fire: true,
             attack_speed: SimpleTimer::blank(),
-            bullets: vec![],
+            ammo: Global::get_bullet_ammo_size(),
             reload: SimpleTimer::blank(),
+            laser_timer: SimpleTimer::blank(),
             
             emittion_configs: vec![
                 (StateType::Drifting, ConfigType::PlayerDrifting),
@@ -106,14 +113,13 @@ impl Player{
             variables: variables
         };
 
-        player.publish(Event::new((player.get_id(), player.emittion_configs.clone()), EventType::RegisterEmitterConf)).await;
-        player.publish(Event::new((Global::get_bullet_ammo_size(), ProjectileType::Player), EventType::RequestBlankCollection)).await;
+        player.publish(Event::new((player.get_id(), player.emittion_configs.clone()), EventType::RegisterEmitterConf));
 
         return player
     }
 
-    async fn fire(&mut self){
-        if let Some(mut bullet) = self.bullets.pop(){
+    fn fire(&mut self){
+        if self.ammo > 0{
             //Invert facing direction
             let front_vector = Vec2::new(
                 self.rotation.sin(),
@@ -149,7 +155,7 @@ impl Player{
             
             let pos = spawn_pos;
 
-            bullet.set(
+            let spawn = BulletSpawn::new(
                 pos,
                 2500.0,
                 front_vector,
@@ -158,30 +164,42 @@ impl Player{
                 ProjectileType::Player
             );
 
-            let proj = Box::new(bullet) as Box<dyn Projectile>;
-            self.publish(Event::new(Some(proj), EventType::PlayerBulletSpawn)).await;
+            self.ammo -= 1;
+            self.publish(Event::new(spawn, EventType::PlayerBulletSpawn));
 
             // Emit Sound
             let sound_request = SoundRequest::new(true, false, 0.1);
-            self.publish(Event::new((SoundType::PlayerFiring ,sound_request), EventType::PlaySound)).await;
+            self.publish(Event::new((SoundType::PlayerFiring ,sound_request), EventType::PlaySound));
             //Update UI
-            self.publish(Event::new(-1, EventType::AlterAmmo)).await;
+            self.publish(Event::new(-1, EventType::AlterAmmo));
         }
-        else if self.bullets.is_empty(){
-            //When acquiring bullets, player is reloading
-            self.publish(Event::new((Global::get_bullet_ammo_size(), ProjectileType::Player), EventType::RequestBlankCollection)).await;
+        else{
+            //Magazine is refilled once the reload is over
+            self.ammo = Global::get_bullet_ammo_size();
             self.reload.set(get_time(), Global::get_reload_timer());
         }
     }
 
+    ///Fires a laser from the front of the player. The `Handler` resolves what it hits.
+    fn fire_laser(&mut self, now: f64){
+        let front_vector = Vec2::new(
+            self.rotation.sin(),
+            -self.rotation.cos()
+        ).normalize();
+
+        let origin = self.pos + front_vector * self.size;
+        self.publish(Event::new((origin, front_vector), EventType::PlayerLaserFire));
+        self.laser_timer.set(now, Global::get_laser_cooldown());
+
+        let sound_request = SoundRequest::new(true, false, 0.2);
+        self.publish(Event::new((SoundType::PlayerFiring, sound_request), EventType::PlaySound));
+    }
+
     fn boost(&mut self, _delta: f32) -> bool{
         let now = get_time();
 
         if self.boost_timer.expired(now){
-            let forward = Vec2::new(self.rotation.sin(), -self.rotation.cos()).normalize();
-            let boost_force = forward * 800.0;
-            
-            self.velocity += boost_force;
+            self.apply_boost_impulse();
             self.boost_counter.discharge();
             self.boost_timer.set(now, Global::get_boost_timer());
 
@@ -191,6 +209,29 @@ impl Player{
         return false
     }
 
+    #[inline(always)]
+    fn apply_boost_impulse(&mut self){
+        let forward = Vec2::new(self.rotation.sin(), -self.rotation.cos()).normalize();
+        let boost_force = forward * 800.0;
+
+        self.velocity += boost_force;
+    }
+
+    /*
+        Damage zones cost one health per immunity window, without knocking the player into Hit.
+        Returns true if the player took damage.
+    */
+    fn resolve_hazard_damage(&mut self, now: f64) -> bool{
+        if !self.hazards.contains(&HazardKind::Damage) || !self.immune_timer.can_be_set(now){
+            return false
+        }
+
+        self.health -= 1;
+        self.immune_timer.set(now, 1.0, Some(0.0));
+
+        return true
+    }
+
     fn activate_boost(&mut self) -> bool{
         if self.boost_counter.allow()
             && is_key_down(KeyCode::LeftShift){
@@ -207,6 +248,42 @@ impl Player{
         return false
     }
 
+    ///Places the player on the spawn point of a map.
+    pub fn set_spawn(&mut self, pos: Vec2){
+        self.pos = pos;
+        self.velocity = Vec2::ZERO;
+    }
+
+    #[inline(always)]
+    pub fn is_shielding(&self) -> bool{
+        return self.shield.is_active()
+    }
+
+
+    /* 
+        Pushes the player back inside the bounds and reflects its velocity.
+        Scrapes only slide the player along the wall, while harder impacts 
+        bounce it off, cost health scaled by the impact speed and transition to Hit.
+
+        Returns the health lost.
+    */
+    fn resolve_wall_impact(&mut self, impact: WallImpact, now: f64) -> i32{
+        self.pos += impact.correction;
+        self.velocity = impact.reflect(self.velocity);
+        self.collider.update(self.pos);
+
+        if impact.is_scrape() || !self.immune_timer.can_be_set(now){
+            return 0
+        }
+
+        let damage = impact.get_damage();
+        
+        self.health -= damage;
+        self.immune_timer.set(now, 1.5, Some(1.0));
+        self.machine.transition(StateType::Hit);
+
+        return damage
+    }
 
     pub fn get_back_position(&self) -> Vec2 {
         let front_vector = Vec2::new(self.rotation.sin(), -self.rotation.cos()).normalize();
@@ -259,6 +336,9 @@ impl Player{
                 friction = self.variables.friction;
             },
         }
+
+        //Surface hazards scale the grip
+        let friction = (friction.0 * self.surface_friction, friction.1 * self.surface_friction);
         
         let forward = Vec2::new(self.rotation.sin(), -self.rotation.cos()).normalize();
         let current_speed = self.velocity.length();
@@ -368,16 +448,15 @@ impl Player{
 }
 
 //======= Player interfaces ========
-#[async_trait]
 impl Updatable for Player{
-    async fn update(&mut self, delta: f32, _params: Vec<Box<dyn std::any::Any + Send>>) {        
+    fn update(&mut self, delta: f32, _context: &UpdateContext) {        
         let now = get_time();
 
         //UI health call
         if self.emitted_grayscale{
             //If immune timer expired, remove grayscale
             if self.immune_timer.on_cooldown(now).is_some_and(|opt| opt){
-                self.publish(Event::new(false, EventType::GrayscalePlayersHealth)).await;
+                self.publish(Event::new(false, EventType::GrayscalePlayersHealth));
                 self.emitted_grayscale = false;
             }
         }
@@ -410,25 +489,38 @@ impl Updatable for Player{
         self.collider.update(self.pos);
         self.collider.set_rotation(self.rotation);
     
+        if self.resolve_hazard_damage(now){
+            self.publish(Event::new(1, EventType::AlterPlayerHealth));
+
+            if !self.emitted_grayscale{
+                self.emitted_grayscale = true;
+                self.publish(Event::new(true, EventType::GrayscalePlayersHealth));
+            }
+
+            if self.health <= 0{
+                self.publish(Event::new(1 as i32, EventType::GameOver));
+            }
+        }
+
         let shield_recharges = self.shield_counter.update();
         let boost_recharges = self.boost_counter.update();
 
         //UI shield call
         if shield_recharges{
-            self.publish(Event::new(1, EventType::AlterShieldCharges)).await;
+            self.publish(Event::new(1, EventType::AlterShieldCharges));
         }
         //UI boost call
         if boost_recharges{
-            self.publish(Event::new(1, EventType::AlterBoostCharges)).await
+            self.publish(Event::new(1, EventType::AlterBoostCharges))
         }
 
-        self.shield.update(delta, vec!(Box::new(self.get_pos()), Box::new(shield_color))).await;
+        self.shield.follow(self.get_pos(), shield_color);
 
         let current_state = self.machine.get_state().lock().unwrap().clone();
 
         let is_not_reloading = {
             if self.reload.expired(now){
-                self.publish(Event::new(self.bullets.len() as i32, EventType::AlterAmmo)).await;
+                self.publish(Event::new(self.ammo as i32, EventType::AlterAmmo));
                 true
             }
             else{
@@ -455,6 +547,7 @@ impl Updatable for Player{
         
         //Sub-state transitions
         let is_firing = is_mouse_button_down(MouseButton::Left) & can_attack;
+        let is_lasering = is_key_pressed(KeyCode::E) && self.laser_timer.expired(now);
         let is_boosting = self.activate_boost();
 
         let is_shielding = self.activate_shield();
@@ -462,13 +555,17 @@ impl Updatable for Player{
 
         match current_state{
             StateType::Idle => {
-                //If input, go to Move state
-                if is_key_down(KeyCode::W) | is_key_down(KeyCode::S){
+                //If input or knocked back, go to Move state
+                if is_key_down(KeyCode::W) | is_key_down(KeyCode::S) | (self.velocity != Vec2::ZERO){
                     self.machine.transition(StateType::Moving);
                 }
 
                 if is_firing{
-                    self.fire().await;
+                    self.fire();
+                }
+
+                if is_lasering{
+                    self.fire_laser(now);
                 }
             }
             StateType::Moving => {
@@ -480,7 +577,11 @@ impl Updatable for Player{
                 }
                 
                 if is_firing{
-                    self.fire().await;
+                    self.fire();
+                }
+
+                if is_lasering{
+                    self.fire_laser(now);
                 }
 
                 if is_drifting{
@@ -492,9 +593,9 @@ impl Updatable for Player{
                     
                     if res{
                         let sound_request = SoundRequest::new(true, false, 0.08);
-                        self.publish(Event::new((SoundType::PlayerBoosting, sound_request), EventType::PlaySound)).await;
+                        self.publish(Event::new((SoundType::PlayerBoosting, sound_request), EventType::PlaySound));
                         //UI update
-                        self.publish(Event::new(-1, EventType::AlterBoostCharges)).await
+                        self.publish(Event::new(-1, EventType::AlterBoostCharges))
                     }
                 }
             },
@@ -510,21 +611,20 @@ impl Updatable for Player{
                             self.machine.transition(StateType::Moving);
                         },
                         false => {
-                            //Reverse velocity vector
-                            if self.bounce{
+                            //Knockback is already applied on impact, only register the damage
+                            if self.pending_damage{
                                 self.health -= 1;
-                                self.publish(Event::new(1, EventType::AlterPlayerHealth)).await;
+                                self.publish(Event::new(1, EventType::AlterPlayerHealth));
 
                                 if self.health <= 0{
                                     died = true;
                                 }
-                                self.velocity = -self.velocity * 0.9;
-                                self.bounce = false;
+                                self.pending_damage = false;
                             }
                         }
                     }
                     //Apply loss of velocity over frame
-                    if !self.bounce{
+                    if !self.pending_damage{
                         self.velocity *= 0.98;
                     }
 
@@ -532,7 +632,7 @@ impl Updatable for Player{
                     self.pos += self.velocity * delta;
                 
                     if died{
-                        self.publish(Event::new(1 as i32, EventType::GameOver)).await;
+                        self.publish(Event::new(1 as i32, EventType::GameOver));
                     }
                 }
             },
@@ -540,7 +640,11 @@ impl Updatable for Player{
                 self.drift_to(delta);
 
                 if is_firing{
-                    self.fire().await;
+                    self.fire();
+                }
+
+                if is_lasering{
+                    self.fire_laser(now);
                 }
 
                 if !is_key_down(KeyCode::Space) && self.velocity.length() > 10.0{
@@ -586,7 +690,7 @@ impl Updatable for Player{
             }
         };
         let sound_request = SoundRequest::new(false, true, volume);
-        self.publish(Event::new((sound ,sound_request), EventType::PlaySound)).await;
+        self.publish(Event::new((sound ,sound_request), EventType::PlaySound));
     }
 }
 
@@ -705,20 +809,23 @@ impl Playable for Player{
 }
 
 //======== Event traits =============
-#[async_trait]
 impl Subscriber for Player {
-    async fn notify(&mut self, event: &Event){
+    fn notify(&mut self, event: &Event){
         let mut shield_hit = false;
         let mut enemy_hit = false;
+        let mut wall_damage = 0;
         
         match &event.event_type{
             EventType::PlayerHit => {
                 let mut current_time = get_time();
 
                 let entry = event.data.try_lock().unwrap();
-                let mut wall_hit = false;
+                let mut wall_impact = None;
+
+                if let Some((now, impact)) = entry.downcast_ref::<(f64, CollisionImpact)>(){
+                    //Knockback applies regardless of shield or immunity
+                    self.velocity += impact.get_impulse(PLAYER_MASS);
 
-                if let Some(now) = entry.downcast_ref::<f64>(){
                     //If shield inactive, register collision
                     if !self.shield.is_active(){
                         current_time = *now;
@@ -730,62 +837,74 @@ impl Subscriber for Player {
                         self.shield_counter.discharge();
                     }
                 }
-                else if let Some(wall_collision) = entry.downcast_ref::<bool>(){
+                else if let Some(impact) = entry.downcast_ref::<WallImpact>(){
                     //Wall collision is indifferent to shield status
-                    if *wall_collision{
-                        wall_hit = true;
-                    }
+                    wall_impact = Some(*impact);
                 }
 
-                if wall_hit{
-                    self.immune_timer.set(current_time, 1.5, Some(1.0));
-                    self.bounce = true;
-                    self.machine.transition(StateType::Hit);
+                if let Some(impact) = wall_impact{
+                    wall_damage = self.resolve_wall_impact(impact, current_time);
                 }
                 else if enemy_hit{
                     if self.immune_timer.can_be_set(current_time){
                         self.immune_timer.set(current_time, 1.5, Some(10.0));
-                        self.bounce = true;
+                        self.pending_damage = true;
                         self.machine.transition(StateType::Hit);
                     }
                 }
             },
-            EventType::ForwardCollectionToPlayer => {
-                if let Ok(mut result) = event.data.lock(){
-                    if let Some(data) = result.downcast_mut::<Option<Vec<Bullet>>>(){
-                        if let Some(bullets) = data.take(){
-                            
-                            if !self.bullets.is_empty(){
-                                println!("Attempting to extend while not empty");
-                            }
-                            self.bullets.clear();
-                            self.bullets.extend(bullets);
-                        }
+            EventType::PlayerOnHazards => {
+                let mut entered_boost_pad = false;
+
+                if let Ok(entry) = event.data.lock(){
+                    if let Some(hazards) = entry.downcast_ref::<Vec<HazardKind>>(){
+                        entered_boost_pad = hazards.contains(&HazardKind::BoostPad)
+                            && !self.hazards.contains(&HazardKind::BoostPad);
+
+                        self.surface_friction = hazards.iter()
+                            .map(|hazard| hazard.get_friction())
+                            .product();
+                        self.hazards = hazards.clone();
                     }
                 }
-            }
+
+                //Boost pads don't use a charge
+                if entered_boost_pad{
+                    self.apply_boost_impulse();
+
+                    let sound_request = SoundRequest::new(true, false, 0.08);
+                    self.publish(Event::new((SoundType::PlayerBoosting, sound_request), EventType::PlaySound));
+                }
+            },
             _ => {}
         }
     
         if shield_hit{
             // Emit Sound
             let sound_request = SoundRequest::new(true, false, 0.1);
-            self.publish(Event::new((SoundType::ShieldHit ,sound_request), EventType::PlaySound)).await;
-            self.publish(Event::new(-1, EventType::AlterShieldCharges)).await;
+            self.publish(Event::new((SoundType::ShieldHit ,sound_request), EventType::PlaySound));
+            self.publish(Event::new(-1, EventType::AlterShieldCharges));
+        }
+
+        if wall_damage > 0{
+            self.publish(Event::new(wall_damage, EventType::AlterPlayerHealth));
+
+            if self.health <= 0{
+                self.publish(Event::new(1 as i32, EventType::GameOver));
+            }
         }
 
-        if enemy_hit{
+        if enemy_hit || wall_damage > 0{
             if !self.emitted_grayscale{
                 self.emitted_grayscale = true;
-                self.publish(Event::new(true, EventType::GrayscalePlayersHealth)).await;
+                self.publish(Event::new(true, EventType::GrayscalePlayersHealth));
             }
         }
     }
 }
 
-#[async_trait]
 impl Publisher for Player {
-    async fn publish(&self, event: Event){
+    fn publish(&self, event: Event){
         let _ = self.sender.send(event);
     }
 }
@@ -1,12 +1,10 @@
 use std::sync::mpsc::Sender;
 
-use async_trait::async_trait;
 use macroquad::prelude::*;
 use macroquad::math::Vec2;
 use macroquad::color::Color;
-use ::rand::{thread_rng, Rng};
 
-use crate::{audio_system::audio_handler::{SoundRequest, SoundType}, collision_system::collider::{CircleCollider, Collider}, entity_handler::enemy_type::EnemyType, event_system::{event::{Event, EventType}, interface::{Drawable, Enemy, GameEntity, Moveable, Object, Publisher, Updatable}}, grid_system::grid::EntityType, objects::bullet::ProjectileType, renderer::{artist::DrawCall, metal::ConfigType}, utils::{machine::{StateMachine, StateType}, timer::SimpleTimer}};   
+use crate::{audio_system::audio_handler::{SoundRequest, SoundType}, collision_system::{collider::{Collider, PolygonCollider}, layer::CollisionLayer}, entity_handler::{behavior::{Agent, BehaviorMemory, Node, Volley}, enemy_type::EnemyType, steering::Steering, update_context::UpdateContext}, event_system::{buffer::EventBuffer, event::{Event, EventType}, interface::{Drawable, Enemy, GameEntity, Moveable, Object, Publisher, Updatable}}, grid_system::grid::EntityType, renderer::{artist::DrawCall, metal::ConfigType}, utils::{knockback::{Knockback, DEFAULT_DAMPING}, machine::{StateMachine, StateType}, timer::SimpleTimer}};   
 
 const FIRING_RANGE: f32 = 1100.0;
 const FIRING_COOLDOWN: f64 = 1.0;
@@ -25,222 +23,53 @@ pub struct TriangleBoss{
     hit_timer: SimpleTimer,
     //Components
     sender: Sender<Event>,
-    collider: CircleCollider,
+    collider: PolygonCollider,
     machine: StateMachine,
     //State specifics
     is_alive: bool,
+    knockback: Knockback,
+    steering: Steering,
     //Emittion
     emittion_configs: Vec<(StateType, ConfigType)>,
-    //Positioning specifics
-    current_destination: Option<Vec2>,
-    approach_player: bool,
-    position_switch_distance: f32,
-    //Fire specifics
-    fire_cooldown: SimpleTimer,
-    fire_counter: u32,
-    fire_pattern: Vec<bool>,
+    //Behaviour
+    behavior: Node,
+    memory: BehaviorMemory
 }
 impl TriangleBoss{
-    /* 
-        Calculates an intermediate position between triangle and player.
+    /*
+        Fires a volley every second no matter the distance, cycling through single shots and flurries.
+        Evades the player when close, and roams around it otherwise.
     */
-    fn determine_next_position(&mut self) -> Vec2 {
-        let mut rng = thread_rng();
-        
-        let distance_to_player = self.pos.distance(self.target);
-        
-        // Close to player - prefer evasive positioning
-        if distance_to_player < FIRING_RANGE {
-            if rng.gen_bool(0.7) { // 70% chance to evade after getting close
-                return self.generate_evasive_position();
-            }
-        }
-        
-        // Default positioning logic
-        if rng.gen_bool(0.6) { // 60%
-            // Intermediate Triangle - Player position
-            let direction = (self.target - self.pos).normalize();
-            let distance = self.pos.distance(self.target);
-            let intermediate_distance = distance * rng.gen_range(0.4..0.8); // 40-80% of the way
-            
-            return self.pos + direction * intermediate_distance;
-        }  
-        else if rng.gen_bool(0.1) { //10%
-            // Directly approach player
-            return self.target;
-        } 
-        else {                      // 30%
-            // Position around triangle or player
-            if rng.gen_bool(0.5) {  //coinflip
-                return self.generate_position_around(self.pos);
-            } else {
-                return self.generate_position_around(self.target);
-            }
-        }
-    }
-    
-    /* 
-        Generates an evasive position that faces away from the player and 
-        is *almost* perpendicular due to some (-60, 60) randomness. The 
-        distance is: size * (10..15)
-    */
-    fn generate_evasive_position(&self) -> Vec2 {
-        let mut rng = thread_rng();
-        
-        let from_player = (self.pos - self.target).normalize();
-        
-        let angle = rng.gen_range(-0.6..0.6) + std::f32::consts::FRAC_PI_2;
-        let perpendicular = Vec2::new(
-            from_player.x * angle.cos() - from_player.y * angle.sin(),
-            from_player.x * angle.sin() + from_player.y * angle.cos()
-        ).normalize();
-        
-        let evasive_direction = (from_player + perpendicular * 0.8).normalize();
-        let distance = self.size * rng.gen_range(10.0..15.0);
-        
-        self.pos + evasive_direction * distance
-    }
-    
-    // Generate position around a point with improved variability
-    fn generate_position_around(&self, center: Vec2) -> Vec2 {
-        let mut rng = thread_rng();
-        
-        let angle = rng.gen::<f32>() * std::f32::consts::PI * 2.0;
-        
-        let radius = self.size * rng.gen_range(20.0..50.0); //Radius randomness
-        let distance = radius * rng.gen::<f32>().sqrt();
-        
-        let offset_x = distance * angle.cos();
-        let offset_y = distance * angle.sin();
-        
-        return Vec2::new(center.x + offset_x, center.y + offset_y)
-    }
-    
-    // Check if reached destination
-    fn has_reached_destination(&self) -> bool {
-        if let Some(dest) = self.current_destination {
-
-            return self.pos.distance(dest) < self.position_switch_distance;
-        }
-        return true
-    }
-    
-    // Continuous check for player distance and firing opportunities
-    async fn check_player_interaction(&mut self){
-        let now = get_time();
-        let distance_to_player = self.pos.distance(self.target);
-
-        //Attempt to fire at the player no matter the distance to him.
-        if self.fire_cooldown.expired(now){
-            self.decide_fire_mode().await;
-            self.fire_cooldown.set(now, FIRING_COOLDOWN);
-        }
-
-        // Within firing range
-        if distance_to_player < FIRING_RANGE{
-            self.current_destination = Some(self.generate_evasive_position());
-        }
-        // Out of firing range, approach more directly
-        else if distance_to_player > FIRING_RANGE * 2.0 {
-            self.approach_player = true;
-
-            let direction = (self.target - self.pos).normalize();
-            let approach_pos = self.pos + direction * (distance_to_player * 0.6);
-
-            self.current_destination = Some(approach_pos);
-        } 
-    }
-
-    async fn decide_fire_mode(&mut self){
-        let pattern_index = (self.fire_counter as usize) % self.fire_pattern.len();
-        self.fire_counter += 1;
-
-        if self.fire_counter > self.fire_pattern.len() as u32{
-            self.fire_counter = 0;
-        }
-        
-        if self.fire_pattern[pattern_index] {
-            self.fire().await;
-        } else {
-            self.flurry().await;
-        }
-    }
-
-
-    async fn fire(&mut self){
-        let direction_to_player = (self.target - self.pos).normalize();
-        let spawn_pos = self.pos;
-
-        self.publish(Event::new((
-            self.id,
-            spawn_pos,
-            350.0 as f32, // Increased bullet speed from 300.0
-            direction_to_player,
-            10.0,
-            22.0 as f32,
-            ProjectileType::Enemy), 
-            EventType::TriangleBulletRequest)
-        ).await;
-    }
-
-    async fn flurry(&mut self){
-        let base = (self.target - self.pos).normalize();
-        let spread_angle = 15.0_f32.to_radians(); // 15 degrees on each side
-        let spawn_pos = self.pos;
-        
-        for i in 0..=40 {
-            let direction = if i == 0 {
-                // First bullet goes straight
-                base
-            } else {
-                // Alternate left and right with increasing angle
-                let side_multiplier = if i % 2 == 1 { -1.0 } else { 1.0 }; // Odd = left, even = right
-                let angle_multiplier = ((i + 1) / 2) as f32; // 1, 1, 2, 2, 3, 3, 4, 4...
-                let angle_offset = spread_angle * angle_multiplier * side_multiplier;
-                
-                // Rotate the base direction by the angle offset
-                Vec2::new(
-                    base.x * angle_offset.cos() - base.y * angle_offset.sin(),
-                    base.x * angle_offset.sin() + base.y * angle_offset.cos()
-                )
-            };
-
-            self.publish(
-                Event::new((
-                    self.id,
-                    spawn_pos,
-                    350.0 as f32,
-                    direction,
-                    10.0,
-                    22.0 as f32,
-                    ProjectileType::Enemy),
-                    EventType::BossBulletRequest)
-            ).await;
-        }
+    fn get_behavior() -> Node{
+        let (fire, flurry) = (Volley::Single, Volley::Flurry);
+
+        return Node::Parallel(vec![
+            Node::Sequence(vec![
+                Node::WaitForCooldown{ duration: FIRING_COOLDOWN, scaled: true },
+                Node::Fire{ pattern: vec![
+                    fire, fire, flurry, fire, flurry,     //fire: 9/15 = 0.6
+                    fire, fire, flurry, fire, fire,       //flurry: 1/3 = 33.333
+                    flurry, fire, fire, flurry, flurry    //dual fire: 1/15 = 0.066
+                ]}
+            ]),
+            Node::Selector(vec![
+                Node::KeepDistance{ range: FIRING_RANGE },
+                Node::Wander{ range: FIRING_RANGE }
+            ])
+        ])
     }
 }
 
 //========== Circle interfaces =========
-#[async_trait]
 impl Updatable for TriangleBoss{
-    async fn update(&mut self, delta: f32, mut params: Vec<Box<dyn std::any::Any + Send>>) {
+    fn update(&mut self, delta: f32, context: &UpdateContext) {
         if self.is_alive{
             //Update target position
-            let mut overide = None;
             let mut play_sound = false;
-            let now = get_time();
-
-            while let Some(param_item) = params.pop(){
-                if let Some(player_pos) = param_item.downcast_ref::<Vec2>(){
-                    self.target = *player_pos;
-                }
-                //REVIEW: No override is ever given since Bosses don't register enemy collitions.
-                if let Some(overide_pos) = param_item.downcast_ref::<Option<Vec2>>(){
-                    overide = *overide_pos;
-                }
-            }
+            let now = context.frame.time;
 
-            self.check_player_interaction().await;
+            self.target = context.frame.player_pos;
+            self.steering = context.steering;
 
             //Update based on state machine
             if let Ok(state) = self.machine.get_state().try_lock(){
@@ -249,7 +78,14 @@ impl Updatable for TriangleBoss{
                         self.machine.transition(StateType::Moving);
                     },
                     StateType::Moving => {
-                        self.move_to(delta, overide);
+                        let mut agent = Agent::new(self.id, self.pos, self.size, self.speed, delta, context);
+                        self.behavior.run(&mut agent, &mut self.memory);
+
+                        for event in agent.events{
+                            self.publish(event);
+                        }
+
+                        self.move_to(delta * context.frame.difficulty.speed, context.overide);
                     },
                     StateType::Hit => {
                         self.health -= 1;
@@ -271,18 +107,19 @@ impl Updatable for TriangleBoss{
                 }
             }
 
+            self.pos += self.knockback.step(delta);
             self.collider.update(self.pos);
-            self.publish(Event::new((self.id, EntityType::Enemy, self.pos, self.size), EventType::InsertOrUpdateToGrid)).await;
+            self.publish(Event::new((self.id, EntityType::Enemy, self.collider.get_layer(), self.pos, self.size), EventType::InsertOrUpdateToGrid));
         
             if play_sound{
                     // Emit sound request
                     if self.health > 0{
                         let srequest = SoundRequest::new(true, false, 0.1);
-                        self.publish(Event::new((SoundType::RectHit, srequest), EventType::PlaySound)).await;
+                        self.publish(Event::new((SoundType::RectHit, srequest), EventType::PlaySound));
                     }
                     else{
                         let srequest = SoundRequest::new(true, false, 0.1);
-                        self.publish(Event::new((SoundType::EnemyDeath, srequest), EventType::PlaySound)).await;
+                        self.publish(Event::new((SoundType::EnemyDeath, srequest), EventType::PlaySound));
                     }
                 }
         }
@@ -307,20 +144,12 @@ impl Object for TriangleBoss{
 impl Moveable for TriangleBoss{
     #[inline(always)]
     fn move_to(&mut self, delta: f32, overide: Option<Vec2>) -> (f32, f32){
-        // If override, simply go to it.
-        if let Some(pos) = overide {
-            self.current_destination = Some(pos);
-        }
-        // If we've reached destination or don't have one, get a new one
-        else if self.has_reached_destination() || self.current_destination.is_none() {
-            self.current_destination = Some(self.determine_next_position());
+        if let Some(destination) = overide{
+            self.memory.set_destination(destination);
         }
-        
-        // Move toward the current destination
-        if let Some(dest) = self.current_destination {
-            self.pos = self.pos.move_towards(dest, self.speed * delta);
-        }
-        
+
+        self.pos = self.memory.step(self.pos, &self.steering, self.speed, delta);
+
         return self.pos.into()
     }
 }
@@ -381,9 +210,11 @@ impl GameEntity for TriangleBoss{
     }
 }
 
-#[async_trait]
 impl Enemy for TriangleBoss{
     fn new(id: u64, pos: Vec2, size: f32, color: Color, player_pos: Vec2, sender:Sender<Event>) -> Self where Self: Sized {
+        let behavior = TriangleBoss::get_behavior();
+        let memory = BehaviorMemory::new(&behavior);
+
         let enemy =  TriangleBoss {
             id: id,
             pos: pos, 
@@ -397,24 +228,17 @@ impl Enemy for TriangleBoss{
             hit_timer: SimpleTimer::blank(),
 
             sender: sender,
-            collider: CircleCollider::new(pos.x, pos.y, size),
+            collider: PolygonCollider::new(pos.x, pos.y, 3, size, 30.0, CollisionLayer::Boss),
             machine: StateMachine::new(),
 
             is_alive: true,
+            knockback: Knockback::new(DEFAULT_DAMPING),
+            steering: Steering::default(),
             
             emittion_configs: vec![(StateType::Hit, ConfigType::EnemyDeath)],
 
-            current_destination: None,
-            approach_player: false,
-            position_switch_distance: 250.0,
-            
-            fire_cooldown: SimpleTimer::new(FIRING_COOLDOWN),
-            fire_counter: 0,
-            fire_pattern: vec![
-                true, true, false, true, false,     //fire: 9/15 = 0.6
-                true, true, false, true, true,      //flurry: 1/3 = 33.333
-                false, true, true, false, false     //dual fire: 1/15 = 0.066
-            ],
+            behavior: behavior,
+            memory: memory,
         };
 
         return enemy
@@ -424,8 +248,8 @@ impl Enemy for TriangleBoss{
         self.id = id;
     }
 
-    async fn register_configs(&self){
-        self.publish(Event::new((self.get_id(), self.emittion_configs.clone()), EventType::RegisterEmitterConf)).await;
+    fn register_configs(&self){
+        self.publish(Event::new((self.get_id(), self.emittion_configs.clone()), EventType::RegisterEmitterConf));
     }
 
     fn set_pos(&mut self, new_pos: Vec2){
@@ -436,6 +260,10 @@ impl Enemy for TriangleBoss{
         self.color = new_color;
     }
 
+    fn get_color(&self) -> Color{
+        return self.color
+    }
+
     fn set_size(&mut self, new_size: f32){
         self.size = new_size;
     }
@@ -456,6 +284,10 @@ impl Enemy for TriangleBoss{
         self.machine.transition(state);
     }
 
+    fn apply_impulse(&mut self, impulse: Vec2){
+        self.knockback.apply(impulse);
+    }
+
     fn get_state(&self) -> Option<StateType>{
         if let Ok(entry) = self.machine.get_state().try_lock(){
             return Some(*entry)
@@ -494,6 +326,10 @@ impl Enemy for TriangleBoss{
         return EnemyType::TriangleBoss
     }
 
+    fn into_base(self: Box<Self>) -> Box<dyn Enemy>{
+        return self
+    }
+
     fn reset(&mut self, id: u64, pos: Vec2, color: Color, size: f32, target: Vec2, is_alive: bool){
         self.id = id;
         self.pos = pos;
@@ -501,31 +337,22 @@ impl Enemy for TriangleBoss{
         self.size = size;
         self.target = target;
         self.is_alive = is_alive;
-        self.collider = CircleCollider::new(pos.x, pos.y, size);
+        self.knockback.reset();
+        self.steering = Steering::default();
+        self.collider = PolygonCollider::new(pos.x, pos.y, 3, size, 30.0, CollisionLayer::Boss);
         self.machine.transition(StateType::Idle);
 
         self.health = 30;
         self.was_hit = false;
         self.hit_timer = SimpleTimer::blank();
-        
-        self.current_destination = None;
-        self.approach_player = false;
-        self.position_switch_distance = 250.0;
-        
-        self.fire_cooldown = SimpleTimer::new(FIRING_COOLDOWN);
-        self.fire_counter = 0;
-        self.fire_pattern = vec![
-                true, true, false, true, false,     //fire: 9/15 = 0.6
-                true, true, false, true, true,      //flurry: 1/3 = 33.333
-                false, true, true, false, false     //dual fire: 1/15 = 0.066
-        ];
+
+        self.memory = BehaviorMemory::new(&self.behavior);
     }
 }
 
-#[async_trait]
 impl Publisher for TriangleBoss{
-    async fn publish(&self, event: Event){
-        let _ = self.sender.send(event);
+    fn publish(&self, event: Event){
+        EventBuffer::send(&self.sender, event);
     }
 }
 
@@ -1,12 +1,12 @@
 use std::collections::HashMap;
 
-use async_trait::async_trait;
 use macroquad::{audio::{self, PlaySoundParams, Sound}, file::set_pc_assets_folder};
+use serde::{Deserialize, Serialize};
 
 use crate::{event_system::{event::{Event, EventType}, interface::Subscriber}, utils::tinkerer::AudioSettings};
 
 
-#[derive(Eq, Hash, PartialEq, Clone, Debug)]
+#[derive(Eq, Hash, PartialEq, Clone, Debug, Deserialize, Serialize)]
 ///Unique Identifier for Sounds.
 pub enum SoundType{
     PlayerIdle,         //ok. Fires from player.
@@ -21,10 +21,11 @@ pub enum SoundType{
     EnemyDeath,         //ok. Each entity fires individually.
 
     TriangleFiring,     //ok. Fires from TriangleAssistant.
-    RectHit,            //ok. Fires from Rect.
+    RectHit,            //ok. Fires from drones with health left, set as `hit_sound`.
     HexDeflect,         //ok. Fires from Entity_Handler
 
     CircleBossDash,
+    PentagonExplosion,  //ok. Fires from the pentagon as it detonates.
 
     MainTheme           //ok. Fires from Game Manager.
 }
@@ -132,6 +133,7 @@ impl Accoustic{
         let rect_hit = audio::load_sound(&"audio/sounds/rect_hit.wav").await.unwrap();
         let hex_deflect = audio::load_sound(&"audio/sounds/hex_deflect.wav").await.unwrap();
         let circle_boss_dash = audio::load_sound(&"audio/sounds/circleboss_dash.wav").await.unwrap();
+        let pentagon_explosion = audio::load_sound(&"audio/sounds/pentagon_explosion.wav").await.unwrap();
 
         let main_theme = audio::load_sound(&"audio/theme_song/MCL.wav").await.unwrap();
 
@@ -152,6 +154,7 @@ impl Accoustic{
         sounds.insert(SoundType::RectHit, SoundRecord { sound: rect_hit, is_playing: false, looped: false, volume: 100.0 });
         sounds.insert(SoundType::HexDeflect, SoundRecord { sound: hex_deflect, is_playing: false, looped: false, volume: 100.0 });
         sounds.insert(SoundType::CircleBossDash, SoundRecord { sound: circle_boss_dash, is_playing: false, looped: false, volume: 100.0 });
+        sounds.insert(SoundType::PentagonExplosion, SoundRecord { sound: pentagon_explosion, is_playing: false, looped: false, volume: 100.0 });
 
         sounds.insert(SoundType::MainTheme, SoundRecord { sound: main_theme, is_playing: false, looped: false, volume: 100.0 });
 
@@ -260,9 +263,8 @@ impl Accoustic{
 }
 
 
-#[async_trait]
 impl Subscriber for Accoustic {
-    async fn notify(&mut self, event: &Event){
+    fn notify(&mut self, event: &Event){
         match &event.event_type{
             EventType::PlaySound => {
                 let mut state = None;
@@ -1,4 +1,5 @@
 
 pub mod collider;
 pub mod collision_detector;
-pub mod collision_tracker;
\ No newline at end of file
+pub mod collision_tracker;
+pub mod layer;
\ No newline at end of file
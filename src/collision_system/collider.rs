@@ -1,31 +1,53 @@
-use macroquad::{color::PINK, math::Vec2, shapes::DrawRectangleParams};
+use macroquad::{color::PINK, math::{vec2, Rect, Vec2}, shapes::DrawRectangleParams};
 
 use crate::renderer::artist::DrawCall;
 
-///Each entity is either assigned a circle or rectangular collider.
+use super::layer::CollisionLayer;
+
+///Each entity is either assigned a circle, rectangular, capsule or polygon collider.
 pub trait Collider{
     fn collides_with(&self, other: &dyn Collider) -> bool;
     fn update(&mut self, pos: Vec2);
 
+    fn get_layer(&self) -> CollisionLayer;
+    fn get_mask(&self) -> u32;
+    fn set_layer(&mut self, layer: CollisionLayer);
+
+    ///Axis aligned box that encloses the collider.
+    fn get_bounds(&self) -> Rect;
+
+    ///Consults the collision matrix, before any shape test is done.
+    #[inline(always)]
+    fn interacts(&self, other: &dyn Collider) -> bool{
+        return self.get_mask() & other.get_layer().bit() != 0
+    }
+
     fn collide_with_circle(&self, circle: &CircleCollider) -> bool;
     fn collide_with_rect(&self, rect: &RectCollider) -> bool;
+    fn collide_with_capsule(&self, capsule: &CapsuleCollider) -> bool;
+    fn collide_with_polygon(&self, polygon: &PolygonCollider) -> bool;
 }
 
 #[derive(Clone, Copy)]
 pub struct CircleCollider{
     x: f32,
     y: f32,
-    radius: f32
+    radius: f32,
+    layer: CollisionLayer,
+    mask: u32
 }
 impl CircleCollider{
     //REVIEW: Perhaps new should be a `Collider` method and have 
     //it return a Collider obj.
-    pub fn new(x: f32, y: f32, radius: f32) -> Self{
-        return CircleCollider { x, y, radius }
+    pub fn new(x: f32, y: f32, radius: f32, layer: CollisionLayer) -> Self{
+        return CircleCollider { x, y, radius, layer, mask: layer.get_mask() }
     }
 }
 impl Collider for CircleCollider{
     fn collides_with(&self, other: &dyn Collider) -> bool{
+        if !self.interacts(other){
+            return false
+        }
         return other.collide_with_circle(&self)
     }
 
@@ -34,6 +56,23 @@ impl Collider for CircleCollider{
         self.y = pos.y;
     }
 
+    fn get_layer(&self) -> CollisionLayer{
+        return self.layer
+    }
+
+    fn get_mask(&self) -> u32{
+        return self.mask
+    }
+
+    fn set_layer(&mut self, layer: CollisionLayer){
+        self.layer = layer;
+        self.mask = layer.get_mask();
+    }
+
+    fn get_bounds(&self) -> Rect{
+        return Rect::new(self.x - self.radius, self.y - self.radius, self.radius * 2.0, self.radius * 2.0)
+    }
+
     fn collide_with_circle(&self, circle: &CircleCollider) -> bool{
         let total_radius = self.radius + circle.radius;
         return distance_squared(self.x, self.y, circle.x, circle.y) <= total_radius * total_radius
@@ -70,10 +109,19 @@ impl Collider for CircleCollider{
         }
 
         return false    }
+
+    fn collide_with_capsule(&self, capsule: &CapsuleCollider) -> bool{
+        return capsule.collide_with_circle(self)
+    }
+
+    fn collide_with_polygon(&self, polygon: &PolygonCollider) -> bool{
+        return polygon.collide_with_circle(self)
+    }
 }
 
 impl CircleCollider{
     //Visual debugging method.
+    #[allow(dead_code)]
     pub fn get_draw_call(&self) -> DrawCall{
         return DrawCall::Circle(
             self.x, 
@@ -90,11 +138,13 @@ pub struct RectCollider{
     y: f32,
     w: f32,
     h: f32,
-    rotation: f32
+    rotation: f32,
+    layer: CollisionLayer,
+    mask: u32
 }
 impl RectCollider{
-    pub fn new(x: f32, y: f32, w: f32, h: f32) -> Self{
-        return RectCollider { x, y, w, h , rotation: 0.0}
+    pub fn new(x: f32, y: f32, w: f32, h: f32, layer: CollisionLayer) -> Self{
+        return RectCollider { x, y, w, h , rotation: 0.0, layer, mask: layer.get_mask() }
     }
 
     pub fn set_rotation(&mut self, rotation: f32){
@@ -135,6 +185,7 @@ impl RectCollider{
     }
 
     //Visual debugging method.
+    #[allow(dead_code)]
     pub fn get_draw_call(&self) -> DrawCall{
         return DrawCall::RotatedRectangle(
             self.x, 
@@ -151,6 +202,9 @@ impl RectCollider{
 }
 impl Collider for RectCollider{
     fn collides_with(&self, other: &dyn Collider) -> bool{
+        if !self.interacts(other){
+            return false
+        }
         return other.collide_with_rect(&self)
     }
 
@@ -159,6 +213,30 @@ impl Collider for RectCollider{
         self.y = pos.y;
     }
 
+    fn get_layer(&self) -> CollisionLayer{
+        return self.layer
+    }
+
+    fn get_mask(&self) -> u32{
+        return self.mask
+    }
+
+    fn set_layer(&mut self, layer: CollisionLayer){
+        self.layer = layer;
+        self.mask = layer.get_mask();
+    }
+
+    fn get_bounds(&self) -> Rect{
+        let corners = self.get_corners();
+        let (mut min, mut max) = (vec2(f32::MAX, f32::MAX), vec2(f32::MIN, f32::MIN));
+
+        for (x, y) in corners{
+            min = min.min(vec2(x, y));
+            max = max.max(vec2(x, y));
+        }
+        return Rect::new(min.x, min.y, max.x - min.x, max.y - min.y)
+    }
+
     fn collide_with_circle(&self, circle: &CircleCollider) -> bool {
         // If no rotation, calculate squared distance and if less or equal to radius, collision 
         if self.rotation == 0.0 {
@@ -235,6 +313,286 @@ impl Collider for RectCollider{
         // No separating axis found, the rectangles are colliding
         return true
     }
+
+    fn collide_with_capsule(&self, capsule: &CapsuleCollider) -> bool {
+        return capsule.collide_with_rect(self)
+    }
+
+    fn collide_with_polygon(&self, polygon: &PolygonCollider) -> bool {
+        return polygon.collide_with_rect(self)
+    }
+}
+
+/* 
+    Capsule spanning a segment from start to end, with a radius around it.
+    Fast moving entities sweep it from their previous to their current position,
+    so that thin targets between two frames are not skipped.
+*/
+#[derive(Clone, Copy)]
+pub struct CapsuleCollider{
+    start: Vec2,
+    end: Vec2,
+    radius: f32,
+    layer: CollisionLayer,
+    mask: u32
+}
+impl CapsuleCollider{
+    pub fn new(start: Vec2, end: Vec2, radius: f32, layer: CollisionLayer) -> Self{
+        return CapsuleCollider { start, end, radius, layer, mask: layer.get_mask() }
+    }
+
+    ///Stretches the capsule over the path travelled during the frame.
+    pub fn sweep(&mut self, start: Vec2, end: Vec2){
+        self.start = start;
+        self.end = end;
+    }
+
+    //Visual debugging method.
+    #[allow(dead_code)]
+    pub fn get_draw_call(&self) -> DrawCall{
+        return DrawCall::Line(
+            self.start.x, 
+            self.start.y, 
+            self.end.x, 
+            self.end.y, 
+            self.radius * 2.0, 
+            PINK
+        );
+    }
+}
+impl Collider for CapsuleCollider{
+    fn collides_with(&self, other: &dyn Collider) -> bool{
+        if !self.interacts(other){
+            return false
+        }
+        return other.collide_with_capsule(&self)
+    }
+
+    ///Moves the capsule so that it starts at pos, keeping its length and direction.
+    fn update(&mut self, pos: Vec2) {
+        let offset = self.end - self.start;
+
+        self.start = pos;
+        self.end = pos + offset;
+    }
+
+    fn get_layer(&self) -> CollisionLayer{
+        return self.layer
+    }
+
+    fn get_mask(&self) -> u32{
+        return self.mask
+    }
+
+    fn set_layer(&mut self, layer: CollisionLayer){
+        self.layer = layer;
+        self.mask = layer.get_mask();
+    }
+
+    fn get_bounds(&self) -> Rect{
+        let min = self.start.min(self.end) - self.radius;
+        let max = self.start.max(self.end) + self.radius;
+
+        return Rect::new(min.x, min.y, max.x - min.x, max.y - min.y)
+    }
+
+    fn collide_with_circle(&self, circle: &CircleCollider) -> bool {
+        let total_radius = self.radius + circle.radius;
+        let closest = closest_point_on_line_segment(circle.x, circle.y, self.start.x, self.start.y, self.end.x, self.end.y);
+
+        return distance_squared(circle.x, circle.y, closest.0, closest.1) <= total_radius * total_radius
+    }
+
+    fn collide_with_rect(&self, rect: &RectCollider) -> bool {
+        let corners = rect.get_corners();
+
+        //Either end inside the rect
+        if is_point_in_polygon(self.start.x, self.start.y, &corners) 
+            || is_point_in_polygon(self.end.x, self.end.y, &corners){
+            return true;
+        }
+
+        //Otherwise the segment must cross or come within radius of an edge
+        for i in 0..4 {
+            let j = (i + 1) % 4;
+            let edge_start = vec2(corners[i].0, corners[i].1);
+            let edge_end = vec2(corners[j].0, corners[j].1);
+
+            if segment_distance_squared(self.start, self.end, edge_start, edge_end) <= self.radius * self.radius {
+                return true;
+            }
+        }
+
+        return false
+    }
+
+    fn collide_with_capsule(&self, capsule: &CapsuleCollider) -> bool {
+        let total_radius = self.radius + capsule.radius;
+        return segment_distance_squared(self.start, self.end, capsule.start, capsule.end) <= total_radius * total_radius
+    }
+
+    fn collide_with_polygon(&self, polygon: &PolygonCollider) -> bool {
+        return polygon.collide_with_capsule(self)
+    }
+}
+
+/* 
+    Regular convex polygon, matching the shapes drawn with `draw_poly`.
+    Rotation is in degrees, same as the `Polygon` draw call.
+    Collisions against other polygons and rects use SAT.
+*/
+#[derive(Clone)]
+pub struct PolygonCollider{
+    x: f32,
+    y: f32,
+    sides: u8,
+    radius: f32,
+    rotation: f32,
+    vertices: Vec<(f32, f32)>,
+    layer: CollisionLayer,
+    mask: u32
+}
+impl PolygonCollider{
+    pub fn new(x: f32, y: f32, sides: u8, radius: f32, rotation: f32, layer: CollisionLayer) -> Self{
+        let mut collider = PolygonCollider { x, y, sides, radius, rotation, vertices: Vec::with_capacity(sides as usize), layer, mask: layer.get_mask() };
+        collider.compute_vertices();
+
+        return collider
+    }
+
+    ///Vertices are cached in world coords and recomputed on every update.
+    fn compute_vertices(&mut self){
+        let rotation = self.rotation.to_radians();
+        let step = std::f32::consts::PI * 2.0 / self.sides as f32;
+
+        self.vertices.clear();
+        for i in 0..self.sides{
+            let angle = i as f32 * step + rotation;
+            self.vertices.push((self.x + self.radius * angle.cos(), self.y + self.radius * angle.sin()));
+        }
+    }
+
+    ///Edge normals of the polygon, used as SAT axes.
+    fn get_axes(&self) -> Vec<(f32, f32)>{
+        let count = self.vertices.len();
+
+        return (0..count)
+            .map(|i| {
+                let (x1, y1) = self.vertices[i];
+                let (x2, y2) = self.vertices[(i + 1) % count];
+                normalize(y1 - y2, x2 - x1)
+            })
+            .collect()
+    }
+
+    fn overlaps(&self, other: &[(f32, f32)], other_axes: Vec<(f32, f32)>) -> bool{
+        for axis in self.get_axes().into_iter().chain(other_axes){
+            if is_separating_axis(axis, &self.vertices, other) {
+                return false;
+            }
+        }
+        return true
+    }
+
+    //Visual debugging method.
+    #[allow(dead_code)]
+    pub fn get_draw_call(&self) -> DrawCall{
+        return DrawCall::PolygonLines(
+            self.x, 
+            self.y, 
+            self.sides, 
+            self.radius, 
+            self.rotation, 
+            2.0,
+            PINK
+        );
+    }
+}
+impl Collider for PolygonCollider{
+    fn collides_with(&self, other: &dyn Collider) -> bool{
+        if !self.interacts(other){
+            return false
+        }
+        return other.collide_with_polygon(&self)
+    }
+
+    fn update(&mut self, pos: Vec2) {
+        self.x = pos.x;
+        self.y = pos.y;
+        self.compute_vertices();
+    }
+
+    fn get_layer(&self) -> CollisionLayer{
+        return self.layer
+    }
+
+    fn get_mask(&self) -> u32{
+        return self.mask
+    }
+
+    fn set_layer(&mut self, layer: CollisionLayer){
+        self.layer = layer;
+        self.mask = layer.get_mask();
+    }
+
+    fn get_bounds(&self) -> Rect{
+        return Rect::new(self.x - self.radius, self.y - self.radius, self.radius * 2.0, self.radius * 2.0)
+    }
+
+    fn collide_with_circle(&self, circle: &CircleCollider) -> bool {
+        if is_point_in_polygon(circle.x, circle.y, &self.vertices) {
+            return true;
+        }
+
+        let count = self.vertices.len();
+        for i in 0..count {
+            let (x1, y1) = self.vertices[i];
+            let (x2, y2) = self.vertices[(i + 1) % count];
+
+            let closest = closest_point_on_line_segment(circle.x, circle.y, x1, y1, x2, y2);
+            if distance_squared(circle.x, circle.y, closest.0, closest.1) <= circle.radius * circle.radius {
+                return true;
+            }
+        }
+
+        return false
+    }
+
+    fn collide_with_rect(&self, rect: &RectCollider) -> bool {
+        let corners = rect.get_corners();
+        let axes = (0..4)
+            .map(|i| {
+                let (x1, y1) = corners[i];
+                let (x2, y2) = corners[(i + 1) % 4];
+                normalize(y1 - y2, x2 - x1)
+            })
+            .collect();
+
+        return self.overlaps(&corners, axes)
+    }
+
+    fn collide_with_capsule(&self, capsule: &CapsuleCollider) -> bool {
+        if is_point_in_polygon(capsule.start.x, capsule.start.y, &self.vertices) 
+            || is_point_in_polygon(capsule.end.x, capsule.end.y, &self.vertices){
+            return true;
+        }
+
+        let count = self.vertices.len();
+        for i in 0..count {
+            let edge_start = vec2(self.vertices[i].0, self.vertices[i].1);
+            let edge_end = vec2(self.vertices[(i + 1) % count].0, self.vertices[(i + 1) % count].1);
+
+            if segment_distance_squared(capsule.start, capsule.end, edge_start, edge_end) <= capsule.radius * capsule.radius {
+                return true;
+            }
+        }
+
+        return false
+    }
+
+    fn collide_with_polygon(&self, polygon: &PolygonCollider) -> bool {
+        return self.overlaps(&polygon.vertices, polygon.get_axes())
+    }
 }
 
 fn distance_squared(x1: f32, y1: f32, x2:f32, y2: f32 ) -> f32{
@@ -243,6 +601,41 @@ fn distance_squared(x1: f32, y1: f32, x2:f32, y2: f32 ) -> f32{
     return dx * dx + dy * dy
 }
 
+/* 
+    Squared distance between segments p1-p2 and q1-q2.
+    Zero if they intersect, otherwise the smallest endpoint to segment distance.
+*/
+fn segment_distance_squared(p1: Vec2, p2: Vec2, q1: Vec2, q2: Vec2) -> f32 {
+    let d1 = p2 - p1;
+    let d2 = q2 - q1;
+    let denominator = d1.perp_dot(d2);
+
+    //Non parallel segments, check for a proper intersection
+    if denominator != 0.0 {
+        let t = (q1 - p1).perp_dot(d2) / denominator;
+        let u = (q1 - p1).perp_dot(d1) / denominator;
+
+        if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u) {
+            return 0.0;
+        }
+    }
+
+    let candidates = [
+        (q1, p1, p2),
+        (q2, p1, p2),
+        (p1, q1, q2),
+        (p2, q1, q2)
+    ];
+
+    let mut min = f32::MAX;
+    for (point, start, end) in candidates.iter() {
+        let closest = closest_point_on_line_segment(point.x, point.y, start.x, start.y, end.x, end.y);
+        min = min.min(distance_squared(point.x, point.y, closest.0, closest.1));
+    }
+
+    return min
+}
+
 fn normalize(x: f32, y: f32) -> (f32, f32) {
     let len = (x*x + y*y).sqrt();
     if len == 0.0 {
@@ -251,7 +644,7 @@ fn normalize(x: f32, y: f32) -> (f32, f32) {
     (x / len, y / len)
 }
 
-fn is_separating_axis(axis: (f32, f32), corners1: &[(f32, f32); 4], corners2: &[(f32, f32); 4]) -> bool {
+fn is_separating_axis(axis: (f32, f32), corners1: &[(f32, f32)], corners2: &[(f32, f32)]) -> bool {
     // Project all corners onto the axis
     let mut min1 = f32::MAX;
     let mut max1 = f32::MIN;
@@ -281,14 +674,15 @@ fn is_separating_axis(axis: (f32, f32), corners1: &[(f32, f32); 4], corners2: &[
 
     For each corner, draw a horizontal line.
     
-    If the line intersects the rectangle an odd number of times, 
+    If the line intersects the polygon an odd number of times, 
     then the point is inside.
 */
-fn is_point_in_polygon(x: f32, y: f32, corners: &[(f32, f32); 4]) -> bool {
+fn is_point_in_polygon(x: f32, y: f32, corners: &[(f32, f32)]) -> bool {
     let mut inside = false;
+    let count = corners.len();
 
-    for i in 0..4 {
-        let j = (i + 1) % 4;
+    for i in 0..count {
+        let j = (i + 1) % count;
         let (xi, yi) = corners[i];
         let (xj, yj) = corners[j];
         
@@ -323,6 +717,111 @@ fn closest_point_on_line_segment(px: f32, py: f32, x1: f32, y1: f32, x2: f32, y2
     (x1 + t_clamped * dx, y1 + t_clamped * dy)
 }
 
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    //Distance a fast bullet covers in a frame, several times the size of the targets
+    const STEP: f32 = 120.0;
+    const BULLET_RADIUS: f32 = 4.0;
+
+    fn bullet(start: Vec2, end: Vec2) -> CapsuleCollider{
+        return CapsuleCollider::new(start, end, BULLET_RADIUS, CollisionLayer::PlayerBullet)
+    }
+
+    /*
+        A bullet jumping over thin targets between two frames. A collider at either end of the frame
+        misses them, the capsule swept over the frame hits them, from both sides of the dispatch.
+    */
+    #[test]
+    fn fast_bullet_does_not_tunnel(){
+        let (previous, current) = (Vec2::new(0.0, 0.0), Vec2::new(STEP, 0.0));
+
+        let enemy = CircleCollider::new(STEP / 2.0, 0.0, 6.0, CollisionLayer::Enemy);
+        let wall = RectCollider::new(STEP / 2.0 - 1.0, -50.0, 2.0, 100.0, CollisionLayer::Wall);
+
+        for pos in [previous, current]{
+            let still = CircleCollider::new(pos.x, pos.y, BULLET_RADIUS, CollisionLayer::PlayerBullet);
+
+            assert!(!still.collides_with(&enemy));
+            assert!(!still.collides_with(&wall));
+        }
+
+        let swept = bullet(previous, current);
+
+        assert!(swept.collides_with(&enemy) && enemy.collides_with(&swept));
+        assert!(swept.collides_with(&wall) && wall.collides_with(&swept));
+    }
+
+    #[test]
+    fn fast_bullet_through_rotated_wall(){
+        let mut wall = RectCollider::new(STEP / 2.0 - 1.0, -50.0, 2.0, 100.0, CollisionLayer::Wall);
+        wall.set_rotation(0.6);
+
+        let swept = bullet(Vec2::new(0.0, 10.0), Vec2::new(STEP, -10.0));
+
+        assert!(swept.collides_with(&wall) && wall.collides_with(&swept));
+    }
+
+    ///Swept bullets passing beside a target, or stopping short of it, don't hit it.
+    #[test]
+    fn swept_bullet_separated(){
+        let enemy = CircleCollider::new(STEP / 2.0, 0.0, 6.0, CollisionLayer::Enemy);
+        let wall = RectCollider::new(STEP / 2.0 - 1.0, -10.0, 2.0, 20.0, CollisionLayer::Wall);
 
+        let beside = bullet(Vec2::new(0.0, 20.0), Vec2::new(STEP, 20.0));
+        assert!(!beside.collides_with(&enemy) && !enemy.collides_with(&beside));
+        assert!(!beside.collides_with(&wall) && !wall.collides_with(&beside));
 
+        let short = bullet(Vec2::new(0.0, 0.0), Vec2::new(STEP / 2.0 - 20.0, 0.0));
+        assert!(!short.collides_with(&enemy));
+        assert!(!short.collides_with(&wall));
+    }
+
+    ///Square polygon with axis aligned edges, `half_width` from its center.
+    fn square(x: f32, y: f32, half_width: f32) -> PolygonCollider{
+        return PolygonCollider::new(x, y, 4, half_width * std::f32::consts::SQRT_2, 45.0, CollisionLayer::Enemy)
+    }
+
+    #[test]
+    fn polygon_edge_contact_with_circle(){
+        let polygon = square(0.0, 0.0, 50.0);
+
+        //Centered off the right edge, reaching just over it or stopping just short of it
+        let touching = CircleCollider::new(60.0, 20.0, 10.5, CollisionLayer::PlayerBullet);
+        let apart = CircleCollider::new(60.0, 20.0, 9.5, CollisionLayer::PlayerBullet);
+
+        assert!(polygon.collides_with(&touching) && touching.collides_with(&polygon));
+        assert!(!polygon.collides_with(&apart) && !apart.collides_with(&polygon));
+    }
 
+    #[test]
+    fn polygon_edge_contact_with_rect(){
+        let polygon = square(0.0, 0.0, 50.0);
+
+        let touching = RectCollider::new(49.5, -10.0, 10.0, 20.0, CollisionLayer::Wall);
+        let apart = RectCollider::new(50.5, -10.0, 10.0, 20.0, CollisionLayer::Wall);
+
+        assert!(polygon.collides_with(&touching) && touching.collides_with(&polygon));
+        assert!(!polygon.collides_with(&apart) && !apart.collides_with(&polygon));
+    }
+
+    /*
+        Targets in the corner of a triangles bounds, but off its slanted edge.
+        A collider filling the bounds would hit them.
+    */
+    #[test]
+    fn polygon_separated_within_bounds(){
+        //Pointing up, with its slanted right edge from (0, -50) to (43.3, 25)
+        let triangle = PolygonCollider::new(0.0, 0.0, 3, 50.0, -90.0, CollisionLayer::Enemy);
+
+        let circle = CircleCollider::new(40.0, -40.0, 5.0, CollisionLayer::PlayerBullet);
+        let rect = RectCollider::new(35.0, -50.0, 10.0, 10.0, CollisionLayer::Wall);
+        let other = square(45.0, -45.0, 5.0);
+
+        assert!(triangle.get_bounds().overlaps(&circle.get_bounds()));
+        assert!(!triangle.collides_with(&circle) && !circle.collides_with(&triangle));
+        assert!(!triangle.collides_with(&rect) && !rect.collides_with(&triangle));
+        assert!(!triangle.collides_with(&other) && !other.collides_with(&triangle));
+    }
+}
@@ -1,13 +1,47 @@
 use std::sync::mpsc::Sender;
 
-use async_trait::async_trait;
-use macroquad::time::get_time;
+use macroquad::{math::Vec2, time::get_time};
 
-use crate::{entity_handler::enemy_type::EnemyType, event_system::{event::{Event, EventType}, interface::{Enemy, Projectile, Publisher}}, objects::bullet::ProjectileType};
+use crate::{entity_handler::projectile_store::ProjectileRef, event_system::{event::{Event, EventType}, interface::{Enemy, Publisher}}, objects::{bubble::Bubble, bullet::ProjectileType}};
 
 use super::{collider::Collider, collision_tracker::CollisionTracker};
 
 
+pub const PLAYER_MASS: f32 = 2.0;
+//Impulse exchanged between player and enemies on contact.
+const CONTACT_FORCE: f32 = 1000.0;
+//Impulse a bullet transfers to the entity it hits.
+const BULLET_FORCE: f32 = 200.0;
+//When the shield is active, enemies are deflected harder and the player barely moves.
+const SHIELD_PUSH: f32 = 2.5;
+const SHIELD_RECOIL: f32 = 0.3;
+
+/* 
+    Impact that accompanies a collision event.
+    The normal points from the other entity towards the receiver, and
+    force is the impulse magnitude, that the receiver scales by its own mass.
+*/
+#[derive(Clone, Copy, Debug)]
+pub struct CollisionImpact{
+    pub normal: Vec2,
+    pub force: f32
+}
+
+impl CollisionImpact{
+    pub fn new(normal: Vec2, force: f32) -> CollisionImpact{
+        return CollisionImpact { 
+            normal: normal, 
+            force: force 
+        }
+    }
+
+    ///Velocity change for a receiver of `mass`.
+    pub fn get_impulse(&self, mass: f32) -> Vec2{
+        return self.normal * self.force / mass
+    }
+}
+
+
 pub struct CollisionDetector{
     sender: Sender<Event>,
     tracker: CollisionTracker
@@ -23,7 +57,7 @@ impl CollisionDetector{
     }
     
     ///Detect collision between Player - Vec<close `Enemy` entities>
-    pub async fn detect_player_collision(&mut self, player_id: u64, player: &dyn Collider, enemies: Vec<Option<&dyn Enemy>>){
+    pub fn detect_player_collision(&mut self, player_id: u64, player_pos: Vec2, is_shielding: bool, player: &dyn Collider, enemies: Vec<Option<&dyn Enemy>>){
         for entry in enemies{
             if let Some(enemy) = entry{
 
@@ -32,8 +66,23 @@ impl CollisionDetector{
                 //Only publish the collision events, if the collision can be registered.
                 if enemy.collides(player){
                     if self.tracker.register_entity_collision(player_id, enemy_id){
-                        self.publish(Event::new(enemy.get_id(), EventType::EnemyHit)).await;
-                        self.publish(Event::new(get_time(), EventType::PlayerHit)).await;
+                        let normal = (player_pos - enemy.get_pos()).normalize_or_zero();
+                        
+                        //Impulse is exchanged based on the reduced mass of the pair, so heavy enemies shove the player around.
+                        let mass = enemy.get_type().get_mass();
+                        let force = CONTACT_FORCE * (mass * PLAYER_MASS) / (mass + PLAYER_MASS);
+
+                        let (enemy_force, player_force) = match is_shielding{
+                            true => (force * SHIELD_PUSH, force * SHIELD_RECOIL),
+                            false => (force, force),
+                        };
+
+                        self.publish(Event::new((enemy_id, CollisionImpact::new(-normal, enemy_force)), EventType::EnemyHit));
+                        self.publish(Event::new((get_time(), CollisionImpact::new(normal, player_force)), EventType::PlayerHit));
+
+                        if !is_shielding{
+                            self.publish(Event::new(enemy_id, EventType::EnemyStrike));
+                        }
                     }
                 }
             }
@@ -41,21 +90,44 @@ impl CollisionDetector{
     }
 
     ///Detect collision between Player - Vec<close `Projectile` entities>
-    pub async fn detect_enemy_projectile_collision(&self, player: &dyn Collider, projectiles: Vec<Option<&dyn Projectile>>){
+    pub fn detect_enemy_projectile_collision(&self, player_pos: Vec2, player: &dyn Collider, projectiles: Vec<Option<ProjectileRef>>){
         for entry in projectiles{
             if let Some(projectile) = entry{
                 if projectile.collides(player){
-                    self.publish(Event::new(projectile.get_id(), EventType::EnemyBulletHit)).await;
-                    self.publish(Event::new(get_time(), EventType::PlayerHit)).await;
+                    let normal = (player_pos - projectile.pos).normalize_or_zero();
+
+                    self.publish(Event::new(projectile.id, EventType::EnemyBulletHit));
+                    self.publish(Event::new((get_time(), CollisionImpact::new(normal, BULLET_FORCE)), EventType::PlayerHit));
+                }
+            }
+        }
+    }
+
+    /*
+        Detect collision between Players Projectile - Vec<active `Bubble`s>.
+        Bubbles consume the bullets that touch them. Returns true if the projectile was absorbed.
+    */
+    pub fn detect_bubble_collision(&mut self, projectile: ProjectileRef, bubbles: &[&Bubble]) -> bool{
+        if projectile.ptype != ProjectileType::Player || !projectile.is_active{
+            return false
+        }
+
+        for bubble in bubbles{
+            if bubble.collides(projectile.collider){
+                if self.tracker.register_projectile_collision(projectile.id, bubble.owner){
+                    self.publish(Event::new(projectile.id, EventType::PlayerBulletHit));
+                    self.publish(Event::new(bubble.owner, EventType::BubbleHit));
                 }
+                return true
             }
         }
+        return false
     }
 
     ///Detect collision between Players Projectile - Vec<close `Enemy` entities>.
-    pub async fn detect_players_projectile_collision(&mut self, projectile: &dyn Projectile, enemies: Vec<Option<&dyn Enemy>>){
-        let collider = projectile.get_collider();
-        let player_projectile_id = projectile.get_id();
+    pub fn detect_players_projectile_collision(&mut self, projectile: ProjectileRef, enemies: Vec<Option<&dyn Enemy>>){
+        let collider = projectile.collider;
+        let player_projectile_id = projectile.id;
 
         for entry in enemies{
             if let Some(enemy) = entry{
@@ -64,12 +136,14 @@ impl CollisionDetector{
                 if enemy.collides(collider){
                     if self.tracker.register_projectile_collision(player_projectile_id, enemy_id){
                         
-                        if enemy.get_type().eq(&EnemyType::Hexagon){
-                            self.publish(Event::new((player_projectile_id, ProjectileType::Enemy), EventType::DeflectBulletAndSwitch)).await;
+                        if enemy.get_type().deflects(){
+                            self.publish(Event::new((player_projectile_id, ProjectileType::Enemy), EventType::DeflectBulletAndSwitch));
                         }
                         else{
-                            self.publish(Event::new(enemy_id, EventType::EnemyHit)).await;
-                            self.publish(Event::new(player_projectile_id, EventType::PlayerBulletHit)).await;
+                            let normal = (enemy.get_pos() - projectile.pos).normalize_or_zero();
+
+                            self.publish(Event::new((enemy_id, CollisionImpact::new(normal, BULLET_FORCE)), EventType::EnemyHit));
+                            self.publish(Event::new(player_projectile_id, EventType::PlayerBulletHit));
                         }
                     }
                 }
@@ -77,38 +151,10 @@ impl CollisionDetector{
         }
     }
 
-    ///Detects inter-Enemy collision, in order for enemies to not clip onto each other.
-    pub async fn detect_enemy_collision(&self, mut enemies: Vec<&dyn Enemy>) {
-        let mut enemies_cloned = enemies
-            .iter()
-            .map(|x| *x)
-            .collect::<Vec<&dyn Enemy>>();
-        
-        while enemies.len() != 0{
-            if let Some(enemy_i) = enemies.pop(){
-                enemies_cloned.retain(|enemy| enemy.get_id() != enemy_i.get_id());
-                
-                for enemy_j in &enemies_cloned {
-                    
-                    //Bosses do not collide with enemies
-                    let is_boss = {
-                        enemy_i.get_type().is_boss()
-                        &&
-                        enemy_j.get_type().is_boss()
-                    };
-    
-                    if enemy_i.collides(enemy_j.get_collider()) && !is_boss{
-                        self.publish(Event::new((enemy_i.get_id(), enemy_j.get_id()), EventType::CollidingEnemies)).await;
-                    }
-                }
-            }
-        }
-    }
 }
 
-#[async_trait]
 impl Publisher for CollisionDetector {
-    async fn publish(&self, event: Event){
+    fn publish(&self, event: Event){
         if let Err(res) = self.sender.send(event){
             println!("Error during collision event: {:?}", res)
         }
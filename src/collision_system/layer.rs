@@ -0,0 +1,47 @@
+/*
+    Every collider carries a layer. Which layers interact is decided
+    in a single place, the collision matrix of `CollisionLayer::get_mask`.
+*/
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum CollisionLayer{
+    Player,
+    Enemy,
+    Boss,
+    PlayerBullet,
+    EnemyBullet,
+    Pickup,
+    Wall,
+    Hazard
+}
+
+impl CollisionLayer{
+    ///Bit of the layer in a mask. Masks are 32 bits wide, so there is room for more layers.
+    #[inline(always)]
+    pub fn bit(&self) -> u32{
+        return 1 << (*self as u32)
+    }
+
+    ///Collision matrix. Returns the bitmask of layers this layer collides with.
+    pub fn get_mask(&self) -> u32{
+        let layers: &[CollisionLayer] = match self{
+            CollisionLayer::Player => &[CollisionLayer::Enemy, CollisionLayer::Boss, CollisionLayer::EnemyBullet, CollisionLayer::Pickup, CollisionLayer::Wall, CollisionLayer::Hazard],
+            CollisionLayer::Enemy => &[CollisionLayer::Player, CollisionLayer::Enemy, CollisionLayer::Boss, CollisionLayer::PlayerBullet, CollisionLayer::Wall],
+            //Bosses do not collide with other bosses
+            CollisionLayer::Boss => &[CollisionLayer::Player, CollisionLayer::Enemy, CollisionLayer::PlayerBullet, CollisionLayer::Wall],
+            CollisionLayer::PlayerBullet => &[CollisionLayer::Enemy, CollisionLayer::Boss, CollisionLayer::Wall],
+            CollisionLayer::EnemyBullet => &[CollisionLayer::Player, CollisionLayer::Wall],
+            CollisionLayer::Pickup => &[CollisionLayer::Player],
+            CollisionLayer::Wall => &[CollisionLayer::Player, CollisionLayer::Enemy, CollisionLayer::Boss, CollisionLayer::PlayerBullet, CollisionLayer::EnemyBullet],
+            //Hazards only affect the player
+            CollisionLayer::Hazard => &[CollisionLayer::Player],
+        };
+
+        return layers.iter().fold(0, |mask, layer| mask | layer.bit())
+    }
+
+    ///True if the matrix allows the two layers to collide.
+    #[inline(always)]
+    pub fn interacts(&self, other: CollisionLayer) -> bool{
+        return self.get_mask() & other.bit() != 0
+    }
+}
@@ -3,6 +3,12 @@ pub mod entity_handler;
 pub mod spawn_manager;
 pub mod factory;
 pub mod enemy_type;
+pub mod archetype;
+pub mod behavior;
 pub mod recycler;
-pub mod bullet_pool;
-pub mod triangle_assistant;
\ No newline at end of file
+pub mod projectile_store;
+pub mod enemy_store;
+pub mod triangle_assistant;
+pub mod steering;
+pub mod id_allocator;
+pub mod update_context;
\ No newline at end of file
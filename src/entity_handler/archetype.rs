@@ -0,0 +1,294 @@
+use std::{fs, sync::OnceLock};
+
+use macroquad::{color::Color, math::Vec2};
+use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{audio_system::audio_handler::SoundType, collision_system::{collider::{CircleCollider, Collider, PolygonCollider}, layer::CollisionLayer}, renderer::{artist::DrawCall, metal::ConfigType}, utils::tinkerer::TinkererError};
+
+use super::{behavior::Node, enemy_type::EnemyType};
+
+const ARCHETYPES_PATH: &str = "assets/enemies";
+//Longest chain of splits followed, in case an archetype splits into itself
+const MAX_SPLITS: u8 = 8;
+
+static ARCHETYPES: OnceLock<Vec<EnemyArchetype>> = OnceLock::new();
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum ShapeKind{
+    Circle,
+    ///Regular polygon, with `sides` and `rotation` in degrees.
+    Polygon
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum ColorRule{
+    ///Color of the current wave.
+    Wave,
+    ///Always `tint`, regardless of the wave.
+    Fixed,
+    ///Color of the current wave, fading out as health is lost.
+    Fading
+}
+
+///Range enemies are sized in, by the radius enclosing their shape.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub struct SizeRange{
+    pub min: f32,
+    pub max: f32
+}
+
+impl SizeRange{
+    #[inline(always)]
+    pub fn sample(&self) -> f32{
+        if self.max <= self.min{
+            return self.min
+        }
+        return thread_rng().gen_range(self.min..self.max)
+    }
+}
+
+///Children an archetype breaks into when it dies.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Splits{
+    ///Name of the childrens archetype.
+    pub children: String,
+    ///Children smaller than this aren't spawned.
+    pub min_size: f32,
+    ///Size of the children, relative to their parent.
+    pub scale: f32
+}
+
+///Bubble an archetype projects around itself, absorbing player bullets for everything inside.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub struct BubbleConfig{
+    pub radius: f32,
+    ///Bullets absorbed before it breaks.
+    pub charges: u32,
+    ///Seconds a broken bubble takes to come back.
+    pub regrow: f64
+}
+
+/*
+    Enemy archetype file. Describes everything a `Drone` needs to look and act as a distinct enemy.
+
+    Enemies are positioned by their center, and `size` is the radius enclosing their shape.
+    Archetypes appear in waves of `complexity` and above, the factory pools `pool` times its size of each.
+    Complexity 0 archetypes never spawn in waves, only as the children of a split.
+*/
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct EnemyArchetype{
+    pub name: String,
+    pub size: SizeRange,
+    pub speed: f32,
+    pub health: i32,
+    pub mass: f32,
+    pub color: ColorRule,
+    #[serde(default)]
+    pub tint: [u8; 4],
+    pub shape: ShapeKind,
+    #[serde(default)]
+    pub sides: u8,
+    #[serde(default)]
+    pub rotation: f32,
+    pub behavior: Node,
+    ///Sends player bullets back, instead of getting hit.
+    #[serde(default)]
+    pub deflects: bool,
+    #[serde(default)]
+    pub splits: Option<Splits>,
+    #[serde(default)]
+    pub bubble: Option<BubbleConfig>,
+    pub emitter: ConfigType,
+    pub death_sound: SoundType,
+    #[serde(default)]
+    pub hit_sound: Option<SoundType>,
+    pub volume: f32,
+    pub score: f64,
+    pub complexity: u8,
+    pub pool: f32
+}
+
+impl EnemyArchetype{
+    /*
+        Reads every archetype in the enemies folder, sorted by complexity and name, with the children only ones last.
+        Invalid files are skipped, but the game can't run without a single archetype.
+    */
+    pub fn load_all() -> Vec<EnemyArchetype>{
+        let mut archetypes = Vec::new();
+
+        match fs::read_dir(ARCHETYPES_PATH){
+            Ok(entries) => {
+                for entry in entries.flatten(){
+                    let path = entry.path();
+
+                    if path.extension().is_some_and(|ext| ext == "yaml"){
+                        match EnemyArchetype::read(&path.to_string_lossy()){
+                            Ok(archetype) => archetypes.push(archetype),
+                            Err(err) => eprintln!("Failed loading archetype {:?}: {}", path, err),
+                        }
+                    }
+                }
+            },
+            Err(err) => eprintln!("Failed reading archetypes: {}", err),
+        }
+
+        if archetypes.is_empty(){
+            panic!("No enemy archetypes loaded from {}", ARCHETYPES_PATH);
+        }
+
+        archetypes.sort_by_key(|archetype| (archetype.complexity == 0, archetype.complexity, archetype.name.clone()));
+        //Ids are a single byte
+        archetypes.truncate(u8::MAX as usize + 1);
+
+        return archetypes
+    }
+
+    pub fn read(path: &str) -> Result<EnemyArchetype, TinkererError>{
+        let contents = fs::read_to_string(path)
+            .map_err(TinkererError::IOError)?;
+
+        let archetype: EnemyArchetype = serde_yaml::from_str(&contents)
+            .map_err(TinkererError::InvalidFormat)?;
+
+        Ok(archetype)
+    }
+
+    ///Pooled enemies of this archetype, for a factory of `size`.
+    #[inline(always)]
+    pub fn get_pool_size(&self, size: usize) -> usize{
+        return (size as f32 * self.pool) as usize
+    }
+
+    #[inline(always)]
+    pub fn get_color(&self, wave_color: Color, health: i32) -> Color{
+        match self.color{
+            ColorRule::Wave => wave_color,
+            ColorRule::Fixed => Color::from_rgba(self.tint[0], self.tint[1], self.tint[2], self.tint[3]),
+            ColorRule::Fading => {
+                let remaining = health as f32 / self.health.max(1) as f32;
+                wave_color.with_alpha(0.1 + remaining.clamp(0.04, 1.0) * 0.9)
+            }
+        }
+    }
+
+    #[inline(always)]
+    pub fn get_outline_call(&self, x: f32, y: f32, size: f32, thickness: f32, color: Color) -> DrawCall{
+        match self.shape{
+            ShapeKind::Circle => DrawCall::CircleLines(x, y, size, thickness, color),
+            ShapeKind::Polygon => DrawCall::PolygonLines(x, y, self.sides, size, self.rotation, thickness, color),
+        }
+    }
+
+    pub fn get_collider(&self, x: f32, y: f32, size: f32) -> ShapeCollider{
+        match self.shape{
+            ShapeKind::Circle => ShapeCollider::Circle(CircleCollider::new(x, y, size, CollisionLayer::Enemy)),
+            ShapeKind::Polygon => ShapeCollider::Polygon(PolygonCollider::new(x, y, self.sides, size, self.rotation, CollisionLayer::Enemy)),
+        }
+    }
+
+    #[inline(always)]
+    pub fn get_draw_call(&self, x: f32, y: f32, size: f32, color: Color) -> DrawCall{
+        match self.shape{
+            ShapeKind::Circle => DrawCall::Circle(x, y, size, color),
+            ShapeKind::Polygon => DrawCall::Polygon(x, y, self.sides, size, self.rotation, color),
+        }
+    }
+}
+
+///Collider matching the `ShapeKind` of an archetype.
+#[derive(Clone)]
+pub enum ShapeCollider{
+    Circle(CircleCollider),
+    Polygon(PolygonCollider)
+}
+
+impl ShapeCollider{
+    #[inline(always)]
+    pub fn get(&self) -> &dyn Collider{
+        match self{
+            ShapeCollider::Circle(collider) => collider,
+            ShapeCollider::Polygon(collider) => collider,
+        }
+    }
+
+    #[inline(always)]
+    pub fn update(&mut self, pos: Vec2){
+        match self{
+            ShapeCollider::Circle(collider) => collider.update(pos),
+            ShapeCollider::Polygon(collider) => collider.update(pos),
+        }
+    }
+
+    //Visual debugging method.
+    #[allow(dead_code)]
+    pub fn get_draw_call(&self) -> DrawCall{
+        match self{
+            ShapeCollider::Circle(collider) => collider.get_draw_call(),
+            ShapeCollider::Polygon(collider) => collider.get_draw_call(),
+        }
+    }
+}
+
+/*
+    Archetypes loaded once, on first use.
+    An archetype is refered to by its index, carried in `EnemyType::Drone`.
+*/
+pub struct Archetypes;
+
+impl Archetypes{
+    #[inline(always)]
+    pub fn all() -> &'static [EnemyArchetype]{
+        return ARCHETYPES.get_or_init(EnemyArchetype::load_all)
+    }
+
+    ///Archetype behind `id`. Ids only come from `Archetypes`, an unknown one is a bug.
+    #[inline(always)]
+    pub fn get(id: u8) -> &'static EnemyArchetype{
+        return Archetypes::all()
+            .get(id as usize)
+            .unwrap_or_else(|| panic!("Unknown enemy archetype id {}", id))
+    }
+
+    ///Id of the archetype named `name`.
+    pub fn find(name: &str) -> Option<u8>{
+        return Archetypes::all()
+            .iter()
+            .position(|archetype| archetype.name == name)
+            .map(|id| id as u8)
+    }
+
+    ///Type and size of the children an enemy of `archetype` and `size` breaks into. None once they'd be too small.
+    pub fn get_split(archetype: &EnemyArchetype, size: f32) -> Option<(EnemyType, f32)>{
+        let splits = archetype.splits.as_ref()?;
+        let child_size = size * splits.scale;
+
+        if child_size < splits.min_size{
+            return None
+        }
+        return Archetypes::find(&splits.children).map(|id| (EnemyType::Drone(id), child_size))
+    }
+
+    ///Splits left down the chain of children, from `archetype` on.
+    pub fn get_splits_left(archetype: &EnemyArchetype) -> u8{
+        let mut left = 0;
+        let mut next = archetype.splits.as_ref();
+
+        while let Some(splits) = next.filter(|_| left < MAX_SPLITS){
+            left += 1;
+            next = Archetypes::find(&splits.children).and_then(|id| Archetypes::get(id).splits.as_ref());
+        }
+
+        return left
+    }
+
+    ///Enemy types of the archetypes that appear at `complexity`.
+    pub fn get_types_up_to(complexity: u8) -> Vec<EnemyType>{
+        return Archetypes::all()
+            .iter()
+            .enumerate()
+            .filter(|(_, archetype)| archetype.complexity > 0 && archetype.complexity <= complexity)
+            .map(|(id, _)| EnemyType::Drone(id as u8))
+            .collect()
+    }
+}
@@ -0,0 +1,517 @@
+use macroquad::math::Vec2;
+use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{audio_system::audio_handler::{SoundRequest, SoundType}, event_system::event::{Event, EventType}, objects::bullet::ProjectileType};
+
+use super::{steering::Steering, update_context::UpdateContext};
+
+//Destinations closer than this count as reached
+const POSITION_SWITCH_DISTANCE: f32 = 250.0;
+//Enemy bullets
+const BULLET_SPEED: f32 = 350.0;
+const BULLET_LIFETIME: f64 = 10.0;
+const BULLET_SIZE: f32 = 22.0;
+//Flurry volleys
+const FLURRY_BULLETS: usize = 40;
+const FLURRY_SPREAD: f32 = 15.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Status{
+    Success,
+    Failure,
+    Running
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum Volley{
+    ///One bullet at the player, limited by the `TriangleAssistant`.
+    Single,
+    ///A fan of bullets, spreading out from the player.
+    Flurry
+}
+
+/*
+    Behaviour tree node. Enemies are a composition of these, in code or in their archetype file.
+
+    Leaves read the enemy through an `Agent`, and steer it by writing a destination into its `BehaviorMemory`.
+    Composites tick their children in order, a `Sequence` resumes from the child that was left running.
+*/
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub enum Node{
+    ///Ticks children until one fails.
+    Sequence(Vec<Node>),
+    ///Ticks children until one doesn't fail.
+    Selector(Vec<Node>),
+    ///Ticks every child, succeeding if any did.
+    Parallel(Vec<Node>),
+    ///Heads for the player, around anything in the way when following the flow field.
+    Chase{ flow_field: bool },
+    ///Evades when the player is within `range`, and closes in when further than twice `range`. Fails in between.
+    KeepDistance{ range: f32 },
+    ///Circles the player at `radius`, turning `speed` radians per second.
+    Orbit{ radius: f32, speed: f32 },
+    ///Rushes past the player at `speed` times the enemies speed, running for `duration` seconds.
+    Dash{ speed: f32, duration: f64 },
+    ///Whether the player is within `range`, and in sight if `sight`.
+    InRange{ range: f32, sight: bool },
+    ///Fires the next volley of `pattern` at the player.
+    Fire{ pattern: Vec<Volley> },
+    ///Sidesteps the closest player bullet. Fails without any nearby.
+    Flee,
+    ///Lights a `fuse` of that many seconds once the player is within `range`, rushing it at `speed` times the enemies speed.
+    ///Fails until lit, and succeeds once the fuse runs out, blowing the enemy up in `radius`.
+    Explode{ range: f32, fuse: f64, radius: f32, speed: f32 },
+    ///Roams between the player and random points, mostly evading once within `range`.
+    Wander{ range: f32 },
+    ///Succeeds once every `duration` seconds, divided by the difficulty fire rate if `scaled`.
+    WaitForCooldown{
+        duration: f64,
+        #[serde(default)]
+        scaled: bool
+    }
+}
+
+///What the nodes read of the enemy they run for.
+pub struct Agent<'a>{
+    pub id: u64,
+    pub pos: Vec2,
+    pub size: f32,
+    pub speed: f32,
+    pub delta: f32,
+    pub context: &'a UpdateContext<'a>,
+    ///Events the enemy publishes after the tick.
+    pub events: Vec<Event>
+}
+
+impl<'a> Agent<'a>{
+    pub fn new(id: u64, pos: Vec2, size: f32, speed: f32, delta: f32, context: &'a UpdateContext<'a>) -> Agent<'a>{
+        return Agent {
+            id: id,
+            pos: pos,
+            size: size,
+            speed: speed,
+            delta: delta,
+            context: context,
+            events: Vec::new()
+        }
+    }
+
+    #[inline(always)]
+    fn get_target(&self) -> Vec2{
+        return self.context.frame.player_pos
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct NodeState{
+    //Time the node is ready or done at
+    until: f64,
+    //Child to resume from
+    child: usize,
+    //Fired volleys
+    count: usize,
+    //Dash destination
+    point: Option<Vec2>,
+    //Orbit angle around the player, in radians
+    angle: Option<f32>
+}
+
+/*
+    Per enemy state of a behaviour tree.
+    Nodes are indexed in pre-order, and the destination persists until a node replaces it.
+*/
+#[derive(Clone, Default)]
+pub struct BehaviorMemory{
+    nodes: Vec<NodeState>,
+    destination: Option<Vec2>,
+    //Speed multiplier of a running dash or a burning fuse, both go straight
+    dash: Option<f32>,
+    //Time a lit fuse runs out at
+    fuse: Option<f64>
+}
+
+impl BehaviorMemory{
+    pub fn new(tree: &Node) -> BehaviorMemory{
+        return BehaviorMemory {
+            nodes: vec![NodeState::default(); tree.count()],
+            destination: None,
+            dash: None,
+            fuse: None
+        }
+    }
+
+    #[inline(always)]
+    pub fn set_destination(&mut self, destination: Vec2){
+        self.destination = Some(destination);
+    }
+
+    ///Time the fuse runs out at, once lit by an `Explode` node.
+    #[inline(always)]
+    pub fn get_fuse(&self) -> Option<f64>{
+        return self.fuse
+    }
+
+    ///Moves `pos` towards the destination. Dashes go straight, everything else blends in the steering.
+    #[inline(always)]
+    pub fn step(&self, pos: Vec2, steering: &Steering, speed: f32, delta: f32) -> Vec2{
+        match (self.destination, self.dash){
+            (Some(destination), Some(scale)) => pos.move_towards(destination, speed * scale * delta),
+            (Some(destination), None) => steering.blend(pos, destination, speed * delta),
+            (None, _) => pos,
+        }
+    }
+}
+
+impl Node{
+    ///Nodes in the tree, this one included.
+    pub fn count(&self) -> usize{
+        match self{
+            Node::Sequence(children) | Node::Selector(children) | Node::Parallel(children) => {
+                1 + children.iter().map(|child| child.count()).sum::<usize>()
+            },
+            _ => 1
+        }
+    }
+
+    ///Whether any node of the tree fires.
+    pub fn fires(&self) -> bool{
+        match self{
+            Node::Sequence(children) | Node::Selector(children) | Node::Parallel(children) => {
+                children.iter().any(|child| child.fires())
+            },
+            Node::Fire{ .. } => true,
+            _ => false
+        }
+    }
+
+    ///Blast radius of the `Explode` node in the tree, if any.
+    pub fn get_blast(&self) -> Option<f32>{
+        match self{
+            Node::Sequence(children) | Node::Selector(children) | Node::Parallel(children) => {
+                children.iter().find_map(|child| child.get_blast())
+            },
+            Node::Explode{ radius, .. } => Some(*radius),
+            _ => None
+        }
+    }
+
+    #[inline(always)]
+    pub fn run(&self, agent: &mut Agent, memory: &mut BehaviorMemory) -> Status{
+        memory.dash = None;
+
+        return self.tick(0, agent, memory)
+    }
+
+    fn tick(&self, index: usize, agent: &mut Agent, memory: &mut BehaviorMemory) -> Status{
+        let now = agent.context.frame.time;
+        let target = agent.get_target();
+
+        match self{
+            Node::Sequence(children) => {
+                let start = memory.nodes[index].child;
+
+                for (i, (child, child_index)) in Node::indexed(children, index).enumerate().skip(start){
+                    match child.tick(child_index, agent, memory){
+                        Status::Success => continue,
+                        Status::Running => {
+                            memory.nodes[index].child = i;
+                            return Status::Running
+                        },
+                        Status::Failure => {
+                            memory.nodes[index].child = 0;
+                            return Status::Failure
+                        },
+                    }
+                }
+
+                memory.nodes[index].child = 0;
+                return Status::Success
+            },
+            Node::Selector(children) => {
+                for (child, child_index) in Node::indexed(children, index){
+                    match child.tick(child_index, agent, memory){
+                        Status::Failure => continue,
+                        status => return status,
+                    }
+                }
+                return Status::Failure
+            },
+            Node::Parallel(children) => {
+                let mut status = Status::Failure;
+
+                for (child, child_index) in Node::indexed(children, index){
+                    match child.tick(child_index, agent, memory){
+                        Status::Failure => {},
+                        Status::Running => status = Status::Running,
+                        Status::Success => if status == Status::Failure { status = Status::Success },
+                    }
+                }
+                return status
+            },
+            Node::Chase{ flow_field } => {
+                let field = agent.context.flow_field.filter(|_| *flow_field);
+                let destination = field
+                    .and_then(|field| field.sample(agent.pos))
+                    .map(|direction| agent.pos + direction * agent.speed)
+                    .unwrap_or(target);
+
+                memory.set_destination(destination);
+                return Status::Success
+            },
+            Node::KeepDistance{ range } => {
+                let distance = agent.pos.distance(target);
+
+                if distance < *range{
+                    memory.set_destination(get_evasive_position(agent, target));
+                    return Status::Success
+                }
+                else if distance > range * 2.0{
+                    let direction = (target - agent.pos).normalize_or_zero();
+                    memory.set_destination(agent.pos + direction * (distance * 0.6));
+                    return Status::Success
+                }
+                return Status::Failure
+            },
+            Node::Orbit{ radius, speed } => {
+                let state = &mut memory.nodes[index];
+                //Start from the current angle around the player
+                let angle = state.angle
+                    .unwrap_or_else(|| (agent.pos - target).to_angle()) + speed * agent.delta;
+
+                state.angle = Some(angle);
+                memory.set_destination(target + Vec2::from_angle(angle) * *radius);
+                return Status::Success
+            },
+            Node::Dash{ speed, duration } => {
+                let state = &mut memory.nodes[index];
+
+                let destination = match state.point{
+                    Some(_) if now >= state.until => {
+                        state.point = None;
+                        return Status::Success
+                    },
+                    Some(point) => point,
+                    None => {
+                        let point = get_overshoot_position(agent.pos, target);
+
+                        state.point = Some(point);
+                        state.until = now + duration;
+
+                        let request = SoundRequest::new(true, true, 0.1);
+                        agent.events.push(Event::new((SoundType::CircleBossDash, request), EventType::PlaySound));
+                        point
+                    },
+                };
+
+                memory.set_destination(destination);
+                memory.dash = Some(*speed);
+                return Status::Running
+            },
+            Node::InRange{ range, sight } => {
+                let in_range = agent.pos.distance(target) < *range;
+
+                match in_range && (!sight || agent.context.line_of_sight){
+                    true => return Status::Success,
+                    false => return Status::Failure,
+                }
+            },
+            Node::Fire{ pattern } => {
+                if pattern.is_empty(){
+                    return Status::Failure
+                }
+
+                let state = &mut memory.nodes[index];
+                let volley = pattern[state.count % pattern.len()];
+                state.count += 1;
+
+                fire(agent, volley, target);
+                return Status::Success
+            },
+            Node::Flee => {
+                let pos = agent.pos;
+                let closest_threat = agent.context.threats.iter()
+                    .min_by(|a, b| pos.distance_squared(**a).total_cmp(&pos.distance_squared(**b)));
+
+                match closest_threat{
+                    Some(threat) => {
+                        memory.set_destination(get_evasive_position(agent, *threat));
+                        return Status::Success
+                    },
+                    None => return Status::Failure,
+                }
+            },
+            Node::Explode{ range, fuse, speed, .. } => {
+                let at = match memory.fuse{
+                    Some(at) => at,
+                    None if agent.pos.distance(target) < *range => {
+                        memory.fuse = Some(now + fuse);
+                        now + fuse
+                    },
+                    None => return Status::Failure,
+                };
+
+                if now >= at{
+                    return Status::Success
+                }
+
+                //Locked onto the player, ignoring the flow field
+                memory.set_destination(target);
+                memory.dash = Some(*speed);
+                return Status::Running
+            },
+            Node::Wander{ range } => {
+                let reached = memory.destination
+                    .map_or(true, |destination| agent.pos.distance(destination) < POSITION_SWITCH_DISTANCE);
+
+                if reached{
+                    memory.set_destination(get_next_position(agent, target, *range));
+                }
+                return Status::Success
+            },
+            Node::WaitForCooldown{ duration, scaled } => {
+                let state = &mut memory.nodes[index];
+
+                if now < state.until{
+                    return Status::Failure
+                }
+
+                let rate = match scaled{
+                    true => agent.context.frame.difficulty.fire_rate as f64,
+                    false => 1.0,
+                };
+
+                state.until = now + duration / rate;
+                return Status::Success
+            },
+        }
+    }
+
+    ///Children paired with their pre-order index.
+    #[inline(always)]
+    fn indexed(children: &[Node], index: usize) -> impl Iterator<Item = (&Node, usize)>{
+        let mut next = index + 1;
+
+        return children.iter().map(move |child| {
+            let child_index = next;
+            next += child.count();
+            (child, child_index)
+        })
+    }
+}
+
+/*
+    Position between the agent and the player, or around either.
+    Within `range` of the player, it mostly evades instead.
+*/
+fn get_next_position(agent: &Agent, target: Vec2, range: f32) -> Vec2{
+    let mut rng = thread_rng();
+    let distance_to_player = agent.pos.distance(target);
+
+    // Close to player - prefer evasive positioning
+    if distance_to_player < range && rng.gen_bool(0.7){ // 70% chance to evade after getting close
+        return get_evasive_position(agent, target)
+    }
+
+    if rng.gen_bool(0.6){ // 60%
+        // Intermediate position between agent and player
+        let direction = (target - agent.pos).normalize_or_zero();
+        let intermediate_distance = distance_to_player * rng.gen_range(0.4..0.8); // 40-80% of the way
+
+        return agent.pos + direction * intermediate_distance
+    }
+    else if rng.gen_bool(0.1){ //10%
+        // Directly approach player
+        return target
+    }
+    else{                      // 30%
+        // Position around agent or player
+        let center = if rng.gen_bool(0.5){ agent.pos } else { target };
+        return get_position_around(agent, center)
+    }
+}
+
+/*
+    Generates an evasive position that faces away from `danger` (usually the player) and
+    is *almost* perpendicular due to some (-60, 60) randomness. The
+    distance is: size * (10..15)
+*/
+fn get_evasive_position(agent: &Agent, danger: Vec2) -> Vec2{
+    let mut rng = thread_rng();
+
+    let from_danger = (agent.pos - danger).normalize_or_zero();
+
+    let angle = rng.gen_range(-0.6..0.6) + std::f32::consts::FRAC_PI_2;
+    let perpendicular = Vec2::from_angle(angle).rotate(from_danger);
+
+    let evasive_direction = (from_danger + perpendicular * 0.8).normalize_or_zero();
+    let distance = agent.size * rng.gen_range(10.0..15.0);
+
+    return agent.pos + evasive_direction * distance
+}
+
+// Generate position around a point with improved variability
+fn get_position_around(agent: &Agent, center: Vec2) -> Vec2{
+    let mut rng = thread_rng();
+
+    let angle = rng.gen::<f32>() * std::f32::consts::PI * 2.0;
+
+    let radius = agent.size * rng.gen_range(20.0..50.0); //Radius randomness
+    let distance = radius * rng.gen::<f32>().sqrt();
+
+    return center + Vec2::from_angle(angle) * distance
+}
+
+///Point past the player, further the further away it is.
+fn get_overshoot_position(pos: Vec2, target: Vec2) -> Vec2{
+    let distance = pos.distance(target);
+
+    let base_overshoot = 150.0;
+    let distance_factor = (distance * 0.25).min(200.0);
+    // Add some randomness
+    let random_variation = thread_rng().gen_range(-50.0..=100.0); // Bias toward longer overshoots
+
+    let overshoot = (base_overshoot + distance_factor + random_variation).max(50.0);
+
+    return target + (target - pos).normalize_or_zero() * overshoot
+}
+
+fn fire(agent: &mut Agent, volley: Volley, target: Vec2){
+    let base = (target - agent.pos).normalize_or_zero();
+
+    match volley{
+        Volley::Single => {
+            agent.events.push(Event::new((
+                agent.id,
+                agent.pos,
+                BULLET_SPEED,
+                base,
+                BULLET_LIFETIME,
+                BULLET_SIZE,
+                ProjectileType::Enemy),
+                EventType::TriangleBulletRequest)
+            );
+        },
+        Volley::Flurry => {
+            let spread_angle = FLURRY_SPREAD.to_radians(); // On each side
+
+            for i in 0..=FLURRY_BULLETS{
+                // Alternate left and right with increasing angle, the first bullet goes straight
+                let side_multiplier = if i % 2 == 1 { -1.0 } else { 1.0 };
+                let angle_multiplier = ((i + 1) / 2) as f32; // 0, 1, 1, 2, 2, 3, 3...
+                let direction = Vec2::from_angle(spread_angle * angle_multiplier * side_multiplier).rotate(base);
+
+                agent.events.push(Event::new((
+                    agent.id,
+                    agent.pos,
+                    BULLET_SPEED,
+                    direction,
+                    BULLET_LIFETIME,
+                    BULLET_SIZE,
+                    ProjectileType::Enemy),
+                    EventType::BossBulletRequest)
+                );
+            }
+        },
+    }
+}
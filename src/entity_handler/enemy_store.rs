@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+
+use macroquad::math::Vec2;
+
+use crate::{collision_system::layer::CollisionLayer, event_system::interface::Enemy};
+
+use super::enemy_type::EnemyType;
+
+
+/*
+    Dense storage for the active enemies.
+
+    Behaviour stays inside the boxed enemies, while the attributes the `Handler` reads every frame
+    are copied into columns next to them. Index `i` of every column belongs to the same enemy,
+    and removing swaps the last enemy into the gap, so the columns stay dense.
+    Columns are refreshed after the enemies update or get moved by the handler.
+*/
+pub struct EnemyStore{
+    ids: Vec<u64>,
+    entities: Vec<Box<dyn Enemy>>,
+    centers: Vec<Vec2>,
+    sizes: Vec<f32>,
+    kinds: Vec<EnemyType>,
+    layers: Vec<CollisionLayer>,
+    alive: Vec<bool>,
+    //Id -> column index
+    index: HashMap<u64, usize>
+}
+
+impl EnemyStore{
+    pub fn new() -> Self{
+        return EnemyStore {
+            ids: Vec::new(),
+            entities: Vec::new(),
+            centers: Vec::new(),
+            sizes: Vec::new(),
+            kinds: Vec::new(),
+            layers: Vec::new(),
+            alive: Vec::new(),
+            index: HashMap::new()
+        }
+    }
+
+    ///Adds an enemy, unless its id is already taken.
+    #[inline(always)]
+    pub fn insert(&mut self, enemy: Box<dyn Enemy>){
+        let id = enemy.get_id();
+
+        if self.index.contains_key(&id){
+            return;
+        }
+
+        self.index.insert(id, self.ids.len());
+        self.ids.push(id);
+        self.centers.push(enemy.get_pos());
+        self.sizes.push(enemy.get_size());
+        self.kinds.push(enemy.get_type());
+        self.layers.push(enemy.get_collider().get_layer());
+        self.alive.push(enemy.is_alive());
+        self.entities.push(enemy);
+    }
+
+    ///Swap removes an enemy, handing its box back.
+    pub fn remove(&mut self, id: &u64) -> Option<Box<dyn Enemy>>{
+        let i = self.index.remove(id)?;
+
+        self.ids.swap_remove(i);
+        self.centers.swap_remove(i);
+        self.sizes.swap_remove(i);
+        self.kinds.swap_remove(i);
+        self.layers.swap_remove(i);
+        self.alive.swap_remove(i);
+        let enemy = self.entities.swap_remove(i);
+
+        //The last enemy took the place of the removed one
+        if let Some(moved) = self.ids.get(i){
+            self.index.insert(*moved, i);
+        }
+
+        return Some(enemy)
+    }
+
+    ///Copies the attributes of the enemy at index `i` into the columns.
+    #[inline(always)]
+    pub fn refresh_at(&mut self, i: usize){
+        let enemy = self.entities[i].as_ref();
+
+        self.centers[i] = enemy.get_pos();
+        self.sizes[i] = enemy.get_size();
+        self.layers[i] = enemy.get_collider().get_layer();
+        self.alive[i] = enemy.is_alive();
+    }
+
+    #[inline(always)]
+    pub fn refresh(&mut self){
+        for i in 0..self.entities.len(){
+            self.refresh_at(i);
+        }
+    }
+
+    #[inline(always)]
+    pub fn get(&self, id: &u64) -> Option<&dyn Enemy>{
+        return self.index.get(id).map(|&i| self.entities[i].as_ref())
+    }
+
+    #[inline(always)]
+    pub fn get_mut(&mut self, id: &u64) -> Option<&mut Box<dyn Enemy>>{
+        return self.index.get(id).map(|&i| &mut self.entities[i])
+    }
+
+    #[inline(always)]
+    pub fn entities(&self) -> &[Box<dyn Enemy>]{
+        return &self.entities
+    }
+
+    #[inline(always)]
+    pub fn entities_mut(&mut self) -> &mut [Box<dyn Enemy>]{
+        return &mut self.entities
+    }
+
+    #[inline(always)]
+    pub fn ids(&self) -> &[u64]{
+        return &self.ids
+    }
+
+    #[inline(always)]
+    pub fn centers(&self) -> &[Vec2]{
+        return &self.centers
+    }
+
+    #[inline(always)]
+    pub fn sizes(&self) -> &[f32]{
+        return &self.sizes
+    }
+
+    #[inline(always)]
+    pub fn kinds(&self) -> &[EnemyType]{
+        return &self.kinds
+    }
+
+    #[inline(always)]
+    pub fn layers(&self) -> &[CollisionLayer]{
+        return &self.layers
+    }
+
+    #[inline(always)]
+    pub fn alive(&self) -> &[bool]{
+        return &self.alive
+    }
+
+    #[inline(always)]
+    pub fn len(&self) -> usize{
+        return self.ids.len()
+    }
+
+    #[inline(always)]
+    pub fn capacity(&self) -> usize{
+        return self.ids.capacity()
+    }
+
+    pub fn shrink_to_fit(&mut self){
+        self.ids.shrink_to_fit();
+        self.entities.shrink_to_fit();
+        self.centers.shrink_to_fit();
+        self.sizes.shrink_to_fit();
+        self.kinds.shrink_to_fit();
+        self.layers.shrink_to_fit();
+        self.alive.shrink_to_fit();
+        self.index.shrink_to_fit();
+    }
+}
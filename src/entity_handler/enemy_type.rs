@@ -1,11 +1,12 @@
+use crate::utils::globals::Global;
+
+use super::archetype::Archetypes;
 
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum EnemyType{
-    Circle,
-    Triangle,
-    Rect,
-    Hexagon,
+    ///Data driven enemy, by index of its archetype in `Archetypes`.
+    Drone(u8),
     CircleBoss,
     TriangleBoss
 }
@@ -18,4 +19,47 @@ impl EnemyType{
             _ => {false}
         }
     }
-}
\ No newline at end of file
+
+    ///Mass used to scale impact forces. Heavier enemies are pushed less and shove harder.
+    pub fn get_mass(&self) -> f32{
+        match self{
+            EnemyType::Drone(id) => Archetypes::get(*id).mass,
+            EnemyType::CircleBoss => 10.0,
+            EnemyType::TriangleBoss => 10.0,
+        }
+    }
+
+    pub fn get_score(&self) -> f64{
+        match self{
+            EnemyType::Drone(id) => Archetypes::get(*id).score,
+            EnemyType::CircleBoss => Global::get_boss_points(),
+            EnemyType::TriangleBoss => Global::get_boss_points(),
+        }
+    }
+
+    ///Type and size of the children an enemy of `size` breaks into, from the `splits` of its archetype.
+    pub fn get_split(&self, size: f32) -> Option<(EnemyType, f32)>{
+        match self{
+            EnemyType::Drone(id) => Archetypes::get_split(Archetypes::get(*id), size),
+            _ => None
+        }
+    }
+
+    ///Whether the enemy fires through the `TriangleAssistant`, and needs line of sight.
+    #[inline(always)]
+    pub fn fires(&self) -> bool{
+        match self{
+            EnemyType::Drone(id) => Archetypes::get(*id).behavior.fires(),
+            _ => false
+        }
+    }
+
+    ///Whether player bullets are sent back instead of hitting.
+    #[inline(always)]
+    pub fn deflects(&self) -> bool{
+        match self{
+            EnemyType::Drone(id) => Archetypes::get(*id).deflects,
+            _ => false
+        }
+    }
+}
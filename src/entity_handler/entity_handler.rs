@@ -1,61 +1,154 @@
-use std::{collections::HashMap, sync::mpsc::Sender};
+use std::{collections::HashMap, sync::{mpsc::Sender, Arc}, thread, time::Instant};
 
-use async_trait::async_trait;
-use macroquad::{math::{vec2, Rect, Vec2}, time::get_time};
+use macroquad::{color::PURPLE, math::{Rect, Vec2}};
+use rand::{thread_rng, Rng};
 
-use crate::{audio_system::audio_handler::{SoundRequest, SoundType}, event_system::{event::{Event, EventType}, interface::{Enemy, Projectile, Publisher, Subscriber}}, objects::bullet::ProjectileType, renderer::artist::DrawCall, utils::{machine::StateType, timer::SimpleTimer}};
+use crate::{actors::elite::Elite, audio_system::audio_handler::{SoundRequest, SoundType}, collision_system::{collision_detector::CollisionImpact, layer::CollisionLayer}, event_system::{buffer::EventBuffer, event::{Event, EventType}, interface::{Enemy, Publisher, Subscriber, Updatable}}, grid_system::{arena::Arena, flow_field::FlowField, grid::{EntityType, Grid}, wall::Wall}, objects::{bubble::{Bubble, BUBBLE_ID}, bullet::{BulletSpawn, ProjectileType}, laser::{Laser, LASER_WIDTH}}, renderer::{artist::DrawCall, metal::ConfigType}, utils::{clock::Clock, globals::Global, machine::StateType, timer::SimpleTimer}};
 
-use super::enemy_type::EnemyType;
+use super::{enemy_store::EnemyStore, id_allocator::IdAllocator, projectile_store::{ProjectileRef, ProjectileStore}, steering::Steering, update_context::{FrameContext, UpdateContext}};
 
-pub enum OverideType{
-    Displace(Vec2)
-}
-impl OverideType{
-    fn get_vec(&self) -> Vec2{
-        match self{
-            OverideType::Displace(vec) => *vec,
-        }
-    }
-}
 
 const CLEANUP: f64 = 10.0;
+const LASER_FORCE: f32 = 350.0;
+const LASER_DURATION: f64 = 0.3;
+//Radius of the line of sight ray, so that bullets fired along it don't clip obstacles
+const SIGHT_WIDTH: f32 = 10.0;
+const EXPLOSION_FORCE: f32 = 1500.0;
+const TRAIL_FORCE: f32 = 300.0;
+//Seconds between two hurts from elite trails
+const TRAIL_COOLDOWN: f64 = 0.5;
 
 pub struct Handler{
-    enemies: HashMap<u64, Box<dyn Enemy>>,
-    projectiles: HashMap<u64, Box<dyn Projectile>>,
-    enemy_overides: HashMap<u64, OverideType>,
+    enemies: EnemyStore,
+    projectiles: ProjectileStore,
+    steering: HashMap<u64, Steering>,
+    //Player bullets near each enemy, found while steering
+    threats: HashMap<u64, Vec<Vec2>>,
+    last_positions: HashMap<u64, Vec2>,
+    flow_field: Arc<FlowField>,
+    flow_timer: SimpleTimer,
+    blocked_areas: Option<Vec<Rect>>,
+    line_of_sight: HashMap<u64, bool>,
+    //Laser shots waiting to be resolved, and the beams drawn after
+    pending_lasers: Vec<(Vec2, Vec2)>,
+    lasers: Vec<Laser>,
+    //Explosions waiting to be resolved, as the id that blew up, center and radius
+    pending_explosions: Vec<(u64, Vec2, f32)>,
+    //Where bubbles broke since the last emitter calls
+    bubble_breaks: Vec<Vec2>,
+    //Elite trails, as center, radius and expiry time
+    trails: Vec<(Vec2, f32, f64)>,
+    trail_timer: SimpleTimer,
+    //Ids of the player, enemies and bullets
+    ids: IdAllocator,
     sender: Sender<Event>,
-    cleanup_timer: SimpleTimer
+    cleanup_timer: SimpleTimer,
+    //Enemy count past which updates go to worker threads
+    parallel_threshold: usize,
+    //Duration of the last entity update, in seconds
+    update_time: f64
 }
 
 impl Handler{
     pub fn new(sender: Sender<Event>) -> Self{
+        let _ = sender.send(Event::new((BUBBLE_ID, vec![(StateType::Hit, ConfigType::BubbleBreak)]), EventType::RegisterEmitterConf));
+
         return Handler{
-            enemies: HashMap::new(),        //All active enemies
-            projectiles: HashMap::new(),    //All active projectiles
-            enemy_overides: HashMap::new(),
+            enemies: EnemyStore::new(),                                         //All active enemies
+            projectiles: ProjectileStore::new(Global::get_projectile_capacity()),  //All active projectiles
+            steering: HashMap::new(),
+            threats: HashMap::new(),
+            last_positions: HashMap::new(),
+            flow_field: Arc::new(FlowField::new(Global::get_grid_size(), Global::get_cell_size())),
+            flow_timer: SimpleTimer::blank(),
+            blocked_areas: None,
+            line_of_sight: HashMap::new(),
+            pending_lasers: Vec::new(),
+            lasers: Vec::new(),
+            pending_explosions: Vec::new(),
+            bubble_breaks: Vec::new(),
+            trails: Vec::new(),
+            trail_timer: SimpleTimer::blank(),
+            ids: IdAllocator::new(),
             sender: sender,
-            cleanup_timer: SimpleTimer::new(CLEANUP)
+            //Set on the first update, so a handler can be built without a window
+            cleanup_timer: SimpleTimer::blank(),
+            parallel_threshold: Global::get_parallel_update_threshold(),
+            update_time: 0.0
         }
     }
 
-    pub async fn update(&mut self, delta: f32, player_pos: Vec2){
-        let now = get_time();
+    ///Id for an entity living outside the handler, like the player.
+    pub fn allocate_id(&mut self) -> u64{
+        return self.ids.allocate()
+    }
 
-        self.remove_expired_entities().await;
+    ///Gives an incoming enemy its id, and registers its emitters under it.
+    fn admit(&mut self, mut enemy: Box<dyn Enemy>){
+        enemy.set_id(self.ids.allocate());
+        enemy.register_configs();
 
-        for (id, enemy) in self.enemies.iter_mut() {
-            let overide = self.enemy_overides.remove(id).map(|o| o.get_vec());
-            
-            // Pass context directly instead of boxing
-            enemy.update(delta, vec![Box::new(player_pos), Box::new(overide)]).await;
-        }
+        self.enemies.insert(enemy);
+    }
+
+    ///Used by the harness, to time both update paths with the same enemies.
+    #[cfg(test)]
+    pub fn set_parallel_threshold(&mut self, threshold: usize){
+        self.parallel_threshold = threshold;
+    }
+
+    ///Duration of the last entity update, in seconds.
+    #[cfg(test)]
+    pub fn get_update_time(&self) -> f64{
+        return self.update_time
+    }
+
+    #[cfg(test)]
+    pub fn get_enemy_count(&self) -> usize{
+        return self.enemies.len()
+    }
+
+    ///Frame time comes from `frame`, ticked by the `Clock` beforehand.
+    pub fn update(&mut self, delta: f32, frame: &FrameContext){
+        let now = frame.time;
 
-        // Update projectiles
-        for (_, projectile) in self.projectiles.iter_mut() {
-            projectile.update(delta, vec![]).await;
+        self.remove_expired_entities(frame.player_pos);
+
+        if self.flow_timer.expired(now){
+            self.refresh_flow_field(frame.player_pos);
+            self.flow_timer.set(now, Global::get_flow_field_refresh());
         }
 
+        let started = Instant::now();
+
+        let ids = self.enemies.ids().to_vec();
+
+        let enemies = self.enemies.entities_mut().iter_mut()
+            .zip(ids.iter())
+            .map(|(enemy, id)| {
+                let context = UpdateContext {
+                    frame: frame,
+                    steering: self.steering.remove(id).unwrap_or_default(),
+                    flow_field: Some(&self.flow_field),
+                    line_of_sight: self.line_of_sight.get(id).copied().unwrap_or(true),
+                    threats: self.threats.get(id).map_or(&[], |threats| threats.as_slice()),
+                    overide: None
+                };
+
+                (enemy, context)
+            })
+            .collect::<Vec<_>>();
+
+        Handler::update_entities(enemies, delta, self.parallel_threshold, &self.sender);
+        self.enemies.refresh();
+
+        //Bullets are plain columns, and release their ids as they go, kept on this thread
+        self.projectiles.update(delta, now, &mut self.ids);
+
+        self.update_time = started.elapsed().as_secs_f64();
+        self.lasers.retain(|laser| laser.is_active(now));
+        self.trails.retain(|(_, _, expires_at)| *expires_at > now);
+
         if self.cleanup_timer.expired(now){
             self.cleanup();
             self.cleanup_timer.set(now, CLEANUP);
@@ -64,23 +157,71 @@ impl Handler{
         self.debug();
     }
 
+    /*
+        Updates entities with their context. Past `threshold` entities,
+        they are split in chunks and updated on worker threads.
+
+        Updates are independent, except for the events they publish. Each worker buffers its events,
+        and the buffers are forwarded in chunk order, so events keep the order of a sequential update.
+    */
+    fn update_entities<T: Updatable + ?Sized>(jobs: Vec<(&mut Box<T>, UpdateContext)>, delta: f32, threshold: usize, sender: &Sender<Event>){
+        if jobs.len() < threshold{
+            for (entity, context) in jobs{
+                entity.update(delta, &context);
+            }
+            return
+        }
+
+        let workers = thread::available_parallelism().map_or(1, |count| count.get());
+        let chunk_size = jobs.len().div_ceil(workers);
+
+        let mut jobs = jobs.into_iter();
+        let mut chunks = Vec::with_capacity(workers);
+
+        loop{
+            let chunk = jobs.by_ref().take(chunk_size).collect::<Vec<_>>();
+
+            if chunk.is_empty(){
+                break;
+            }
+            chunks.push(chunk);
+        }
+
+        let buffers = thread::scope(|scope| {
+            let handles = chunks.into_iter()
+                .map(|chunk| scope.spawn(move || {
+                    EventBuffer::begin();
+
+                    for (entity, context) in chunk{
+                        entity.update(delta, &context);
+                    }
+
+                    EventBuffer::end()
+                }))
+                .collect::<Vec<_>>();
+
+            handles.into_iter()
+                .map(|handle| handle.join().expect("Entity update thread panicked"))
+                .collect::<Vec<Vec<Event>>>()
+        });
+
+        for event in buffers.into_iter().flatten(){
+            let _ = sender.send(event);
+        }
+    }
     
-    async fn remove_expired_entities(&mut self){
-        let enemies_remove = self.enemies
-            .iter()
-            .filter(|(_, enemy)| !enemy.is_alive())
-            .map(|(id, _)| *id)
-            .collect::<Vec<u64>>();
-        
-        let projecitles_remove = self.projectiles
+    /*
+        Drops dead enemies and sends them to be recycled, scoring them along with their elite level.
+        Enemies whose archetype `splits` break into two or three children, spawned by the `Factory`.
+    */
+    fn remove_expired_entities(&mut self, player_pos: Vec2){
+        let enemies_remove = self.enemies.entities()
             .iter()
-            .filter(|(_, proj)| !proj.is_active())
-            .map(|(id, _)| *id)
+            .filter(|enemy| !enemy.is_alive())
+            .map(|enemy| enemy.get_id())
             .collect::<Vec<u64>>();
-
         
         let mut enemies_to_recycle = Vec::new();
-        let mut bullets_to_recycle = Vec::new();
 
         //Drop enemies
         let mut enemies = Vec::new();
@@ -89,66 +230,322 @@ impl Handler{
             if let Some(enemy) = self.enemies.remove(&id){
 
                 let etype = enemy.get_type();
-                self.publish(Event::new(id, EventType::RemoveEntityFromGrid)).await;
+                //Elites score a bonus by level
+                let level = enemy.as_any()
+                    .downcast_ref::<Elite>()
+                    .map_or(0, |elite| elite.get_level());
+
+                self.publish(Event::new(id, EventType::RemoveEntityFromGrid));
                 
-                if etype.eq(&EnemyType::Triangle){
-                    self.publish(Event::new(enemy.get_id(), EventType::RemoveTriangle)).await;
+                if etype.fires(){
+                    self.publish(Event::new(enemy.get_id(), EventType::RemoveTriangle));
+                }
+                self.publish(Event::new((enemy.get_id(), StateType::Hit), EventType::UnregisterEmitterConf));
+
+                if let Some((child_type, child_size)) = etype.get_split(enemy.get_size()){
+                    let count = thread_rng().gen_range(2..=3_usize);
+                    self.publish(Event::new((child_type, enemy.get_pos(), child_size, enemy.get_color(), player_pos, count), EventType::SpawnChildren));
                 }
-                self.publish(Event::new((enemy.get_id(), StateType::Hit), EventType::UnregisterEmitterConf)).await;
                 
                 enemies_to_recycle.push(Some(enemy));
-                self.enemy_overides.remove(&id);
+                self.steering.remove(&id);
+                self.threats.remove(&id);
+                self.last_positions.remove(&id);
+                self.line_of_sight.remove(&id);
+                //Events still carrying the old id are dropped from here on
+                self.ids.release(id);
                 
-                enemies.push(etype);
-            }
-        }
-        self.publish(Event::new((enemies.len() as u64, enemies), EventType::AddScorePoints)).await;
-
-        //Drop projectiles
-        for id in projecitles_remove{
-            if let Some(proj) = self.projectiles.remove(&id){
-                self.publish(Event::new(id, EventType::RemoveEntityFromGrid)).await;
-
-                let boxed_bullet = Some(Box::new(proj.as_bullet()));
-                bullets_to_recycle.push(boxed_bullet);
+                enemies.push((etype, level));
             }
         }
+        self.publish(Event::new((enemies.len() as u64, enemies), EventType::AddScorePoints));
 
         //Recycle enemies
         if !enemies_to_recycle.is_empty(){
             let recycling_batch = std::mem::take(&mut enemies_to_recycle);
-            self.publish(Event::new(recycling_batch, EventType::BatchRecycle)).await;
+            self.publish(Event::new(recycling_batch, EventType::BatchRecycle));
         }
 
         drop(enemies_to_recycle);
+    }
+
+    ///Keeps enemies inside the map, by pushing back the ones that crossed the wall.
+    #[inline(always)]
+    pub fn confine_enemies(&mut self, wall: &Wall){
+        for i in 0..self.enemies.len(){
+            let enemy = &mut self.enemies.entities_mut()[i];
+            let pos = enemy.get_pos();
+            let size = enemy.get_size();
+
+            if let Some(correction) = wall.confine(pos - size, pos + size){
+                enemy.set_pos(pos + correction);
+                self.enemies.refresh_at(i);
+            }
+        }
+    }
+
+    ///Pushes enemies out of the arena obstacles the `Grid` reports as nearby.
+    pub fn resolve_obstacles(&mut self, arena: &Arena, grid: &Grid){
+        for i in 0..self.enemies.len(){
+            if !self.enemies.alive()[i]{
+                continue;
+            }
+
+            let center = self.enemies.centers()[i];
+            let radius = self.enemies.sizes()[i];
 
-        //Recycle projectiles
-        if !bullets_to_recycle.is_empty(){
-            let recycling_batch = std::mem::take(&mut bullets_to_recycle);
-            self.publish(Event::new(recycling_batch, EventType::BatchBulletRecycle)).await;
+            let obstacles = grid.query_radius(center, radius, self.enemies.layers()[i])
+                .into_iter()
+                .filter(|(etype, _)| *etype == EntityType::Obstacle)
+                .map(|(_, id)| id)
+                .collect::<Vec<u64>>();
+
+            if obstacles.is_empty(){
+                continue;
+            }
+
+            if let Some((_, correction)) = arena.get_correction(&obstacles, center, radius){
+                let enemy = &mut self.enemies.entities_mut()[i];
+                
+                enemy.set_pos(enemy.get_pos() + correction);
+                self.enemies.refresh_at(i);
+            }
         }
+    }
 
-        drop(bullets_to_recycle);
+    /*
+        Casts a ray from every triangle to the player, through the `Grid`.
+        Triangles only fire when no obstacle is in the way.
+    */
+    pub fn update_line_of_sight(&mut self, grid: &Grid, arena: &Arena, player_pos: Vec2){
+        self.line_of_sight.clear();
+
+        for i in 0..self.enemies.len(){
+            if !self.enemies.alive()[i] || !self.enemies.kinds()[i].fires(){
+                continue;
+            }
+
+            let id = self.enemies.ids()[i];
+            let pos = self.enemies.centers()[i];
+
+            let hits = grid.raycast(pos, player_pos - pos, pos.distance(player_pos), SIGHT_WIDTH, CollisionLayer::EnemyBullet, |etype, id| {
+                match etype{
+                    EntityType::Obstacle => arena.get_obstacle_collider(id),
+                    _ => None
+                }
+            });
+
+            self.line_of_sight.insert(id, hits.is_empty());
+        }
+    }
+
+    /* 
+        Resolves the laser shots fired since the last call.
+        Lasers pierce every enemy on their path and stop at the first obstacle.
+    */
+    pub fn resolve_lasers(&mut self, grid: &Grid, arena: &Arena){
+        let length = Global::get_laser_length();
+
+        for (origin, direction) in std::mem::take(&mut self.pending_lasers){
+            let hits = grid.raycast(origin, direction, length, LASER_WIDTH, CollisionLayer::PlayerBullet, |etype, id| {
+                match etype{
+                    EntityType::Enemy => self.enemies.get(&id)
+                        .filter(|enemy| enemy.is_alive())
+                        .map(|enemy| enemy.get_collider()),
+                    EntityType::Obstacle => arena.get_obstacle_collider(id),
+                    _ => None
+                }
+            });
+
+            let mut reach = length;
+
+            for hit in hits{
+                if hit.entity_type == EntityType::Obstacle{
+                    reach = hit.distance;
+                    break;
+                }
+
+                self.publish(Event::new((hit.id, CollisionImpact::new(direction, LASER_FORCE)), EventType::EnemyHit));
+            }
+
+            self.lasers.push(Laser::new(origin, origin + direction * reach, LASER_DURATION));
+        }
+    }
+
+    /*
+        Resolves the explosions since the last call. Enemies the `Grid` finds in radius are hit
+        and thrown outwards, and so is the player, if its bounds are caught in the blast.
+    */
+    pub fn resolve_explosions(&mut self, grid: &Grid, player_bounds: Rect){
+        let now = Clock::now();
+
+        for (source, center, radius) in std::mem::take(&mut self.pending_explosions){
+            let caught = grid.query_radius(center, radius, CollisionLayer::Enemy)
+                .into_iter()
+                .filter(|(etype, id)| *etype == EntityType::Enemy && *id != source);
+
+            for (_, id) in caught{
+                if let Some(enemy) = self.enemies.get(&id).filter(|enemy| enemy.is_alive()){
+                    let normal = (enemy.get_pos() - center).normalize_or_zero();
+                    self.publish(Event::new((id, CollisionImpact::new(normal, EXPLOSION_FORCE)), EventType::EnemyHit));
+                }
+            }
+
+            let (min, max) = (player_bounds.point(), player_bounds.point() + player_bounds.size());
+
+            if center.clamp(min, max).distance(center) <= radius{
+                let normal = (player_bounds.center() - center).normalize_or_zero();
+                self.publish(Event::new((now, CollisionImpact::new(normal, EXPLOSION_FORCE)), EventType::PlayerHit));
+            }
+        }
+    }
+
+    ///Hurts the player when it steps on an elite trail, at most once every `TRAIL_COOLDOWN`.
+    pub fn resolve_trails(&mut self, player_bounds: Rect){
+        let now = Clock::now();
+
+        if !self.trail_timer.expired(now){
+            return;
+        }
+
+        let (min, max) = (player_bounds.point(), player_bounds.point() + player_bounds.size());
+
+        let touched = self.trails.iter()
+            .find(|(center, radius, _)| center.clamp(min, max).distance(*center) <= *radius);
+
+        if let Some((center, _, _)) = touched{
+            let normal = (player_bounds.center() - *center).normalize_or_zero();
+            self.publish(Event::new((now, CollisionImpact::new(normal, TRAIL_FORCE)), EventType::PlayerHit));
+
+            self.trail_timer.set(now, TRAIL_COOLDOWN);
+        }
+    }
+
+    ///Resizes the flow field to a new map.
+    pub fn set_map_size(&mut self, grid_size: i32, cell_size: i32){
+        self.flow_field = Arc::new(FlowField::new(grid_size, cell_size));
+        self.flow_timer = SimpleTimer::blank();
+    }
+
+    ///Areas enemies can't path through. Applied on the next flow field refresh.
+    #[inline(always)]
+    pub fn set_blocked_areas(&mut self, areas: Vec<Rect>){
+        self.blocked_areas = Some(areas);
+    }
+
+    ///Recomputes the flow field towards the player, with enemies marked as costly to path through.
+    fn refresh_flow_field(&mut self, player_pos: Vec2){
+        //Enemies only borrow the field through their `UpdateContext` while updating, so the `Arc` is never shared here and this doesn't clone.
+        let field = Arc::make_mut(&mut self.flow_field);
+        
+        if let Some(areas) = self.blocked_areas.take(){
+            field.set_blocked(&areas);
+        }
+
+        field.clear_costs();
+        for i in 0..self.enemies.len(){
+            if self.enemies.alive()[i]{
+                field.add_cost(self.enemies.centers()[i], self.enemies.sizes()[i]);
+            }
+        }
+
+        field.compute(player_pos);
+    }
+
+    /* 
+        Computes separation and alignment steering for every enemy, from its neighbours in the `Grid`.
+        Headings are estimated from the displacement since the previous call.
+        Player bullets found among the neighbours are kept as the enemies threats.
+        Both are passed to the enemies on the next update.
+    */
+    pub fn steer_enemies(&mut self, grid: &Grid, delta: f32){
+        let mut boids: HashMap<u64, (Vec2, f32, Vec2)> = HashMap::with_capacity(self.enemies.len());
+
+        for i in 0..self.enemies.len(){
+            if !self.enemies.alive()[i]{
+                continue;
+            }
+
+            let id = self.enemies.ids()[i];
+            let center = self.enemies.centers()[i];
+            let previous = self.last_positions.insert(id, center).unwrap_or(center);
+
+            let heading = match delta > 0.0{
+                true => (center - previous) / delta,
+                false => Vec2::ZERO,
+            };
+
+            boids.insert(id, (center, self.enemies.sizes()[i], heading));
+        }
+
+        self.steering.clear();
+        self.threats.clear();
+
+        for i in 0..self.enemies.len(){
+            if !self.enemies.alive()[i]{
+                continue;
+            }
+
+            let id = self.enemies.ids()[i];
+            let (center, size, _) = boids[&id];
+
+            //Neighbours further than this don't contribute to the steering
+            let reach = size + Global::get_steering_radius();
+
+            let nearby = grid.query_radius(center, reach, self.enemies.layers()[i]);
+
+            let neighbours = nearby.iter()
+                .filter(|(etype, other)| *etype == EntityType::Enemy && *other != id)
+                .filter_map(|(_, other)| boids.get(other).copied())
+                .collect::<Vec<(Vec2, f32, Vec2)>>();
+
+            let threats = nearby.iter()
+                .filter(|(etype, _)| *etype == EntityType::Projectile)
+                .filter_map(|(_, other)| self.projectiles.get(other))
+                .filter(|projectile| projectile.ptype == ProjectileType::Player)
+                .map(|projectile| projectile.pos)
+                .collect::<Vec<Vec2>>();
+
+            self.steering.insert(id, Steering::compute(center, size, &neighbours));
+
+            if !threats.is_empty(){
+                self.threats.insert(id, threats);
+            }
+        }
     }
 
     #[inline(always)]
     pub fn get_draw_calls(&mut self, viewport: Rect) -> Vec<(i32, DrawCall)>{
         let mut draw_calls: Vec<(i32, DrawCall)> = Vec::new();
 
-        self.enemies.iter_mut()
-            .map(|(_, boxed)| boxed)
+        self.trails.iter()
+            .filter(|(center, _, _)| viewport.contains(*center))
+            .for_each(|(center, radius, _)| draw_calls.push((3, DrawCall::Circle(center.x, center.y, *radius, PURPLE.with_alpha(0.25)))));
+
+        self.enemies.entities()
+            .iter()
             .filter(|enemy| {
                 viewport.contains(enemy.get_pos()) && enemy.is_alive()
             })
             .for_each(|enemy|{
 
-                if !enemy.get_type().is_boss(){
-                    draw_calls.push((4, enemy.get_draw_call()));
-                    
+                match enemy.get_type(){
+                    etype if etype.is_boss() => {
+                        for call in enemy.get_all_draw_calls(){
+                            draw_calls.push((6, call));
+                        }
+                    },
+                    //Fuse telegraph, splits left and bubbles
+                    _ => {
+                        for call in enemy.get_all_draw_calls(){
+                            draw_calls.push((4, call));
+                        }
+                    },
                 }
-                else {
-                    for call in enemy.get_all_draw_calls(){
-                        draw_calls.push((6, call));
+
+                if let Some(elite) = enemy.as_any().downcast_ref::<Elite>(){
+                    for call in elite.get_aura(){
+                        draw_calls.push((5, call));
                     }
                 }
                 //DEBUG for colliders
@@ -157,18 +554,19 @@ impl Handler{
                 // }
             });
     
-        self.projectiles.iter_mut()
-            .map(|(_, boxed)| boxed)
-            .filter(|proj| {
-                viewport.contains(proj.get_pos()) && proj.is_active()
-            })
-            .for_each(|projectile|{
-                draw_calls.push((9, projectile.get_draw_call()));
-                
-                // for call in projectile.get_all_draw_calls(){
-                //     draw_calls.push((9, call));
-                // }
-            });
+        self.projectiles.get_draw_calls(viewport)
+            .into_iter()
+            .for_each(|call| draw_calls.push((9, call)));
+
+        //DEBUG for colliders
+        // for projectile in self.projectiles.iter(){
+        //     draw_calls.push((9, projectile.collider.get_draw_call()));
+        // }
+        
+        let now = Clock::now();
+
+        self.lasers.iter()
+            .for_each(|laser| draw_calls.push((9, laser.get_draw_call(now))));
         
         return draw_calls
     }
@@ -183,80 +581,58 @@ impl Handler{
     */
     #[inline(always)]
     pub fn get_emitter_calls(&mut self) -> Vec<(u64, StateType, Vec2)>{
-        let mut calls = self.enemies.iter()
-            .filter(|(_, enemy)| {
+        let mut calls = self.enemies.entities()
+            .iter()
+            .filter(|enemy| {
                 enemy.should_emit()
             })
-            .map(|(id, enemy)| {
-                let mut pos = enemy.get_pos();
-                //Rect enemies have assigned position on top left corner.
-                if enemy.get_type() == EnemyType::Rect{
-                    let half_size = enemy.get_size() / 2.0;
-                    
-                    //Correct with an offset so that the particle comes from center of rect.
-                    pos = vec2(pos.x + half_size, pos.y + half_size)
-                }
-
-                (*id, enemy.get_state().unwrap_or(StateType::Idle) , pos)
+            .map(|enemy| {
+                (enemy.get_id(), enemy.get_state().unwrap_or(StateType::Idle) , enemy.get_pos())
             })
             .collect::<Vec<(u64, StateType, Vec2)>>();
-        
-        calls.extend(self.projectiles.iter()
-                .filter(|(_, proj)| {
-                    proj.should_emit()
-                })
-                .map(|(id, proj)| {
-                    (*id, proj.get_state().unwrap_or(StateType::Idle), proj.get_pos())
-                })
-                .collect::<Vec<(u64, StateType, Vec2)>>());
+
+        calls.extend(self.bubble_breaks.drain(..).map(|pos| (BUBBLE_ID, StateType::Hit, pos)));
 
         return calls
     }
 
+    ///Bubbles that currently hold, of living enemies.
     #[inline(always)]
-    fn insert_enemy(&mut self, id: u64, enemy: Box<dyn Enemy>){
-        self.enemies.entry(id)
-            .or_insert(enemy);
+    pub fn get_bubbles(&self) -> Vec<&Bubble>{
+        return self.enemies.entities()
+            .iter()
+            .filter(|enemy| enemy.is_alive())
+            .filter_map(|enemy| enemy.get_bubble())
+            .filter(|bubble| bubble.is_active())
+            .collect()
     }
 
+    ///Writes the projectiles positions and removals straight into the `Grid`.
     #[inline(always)]
-    fn insert_projectile(&mut self, id: u64, projectile: Box<dyn Projectile>){
-        self.projectiles.entry(id)
-        .or_insert(projectile);
+    pub fn sync_projectiles(&mut self, grid: &mut Grid){
+        self.projectiles.sync_grid(grid);
     }
 
     #[inline(always)]
     pub fn get_enemy(&self, id: &u64) -> Option<&dyn Enemy>{
-        if let Some((_, entry)) = self.enemies.get_key_value(&id){
-            return Some(entry.as_ref())
-        }
-        return None
+        return self.enemies.get(id)
     }
 
     #[inline(always)]
-    pub fn get_projectile(&self, id: &u64) -> Option<&dyn Projectile>{
-        if let Some((_, entry)) = self.projectiles.get_key_value(&id){
-            return Some(entry.as_ref())
-        }
-        return None
+    pub fn get_projectile(&self, id: &u64) -> Option<ProjectileRef<'_>>{
+        return self.projectiles.get(id)
     }
 
     #[inline(always)]
-    pub fn get_projectiles(&self) -> Vec<&dyn Projectile>{
-        let projectiles: Vec<&dyn Projectile> = self.projectiles
-            .iter()
-            .map(|(_, projectile)| projectile.as_ref())
-            .collect();
-
-        return projectiles
+    pub fn get_projectiles(&self) -> Vec<ProjectileRef<'_>>{
+        return self.projectiles.iter().collect()
     }
 
     #[inline(always)]
     pub fn get_active_enemy_count(&self) -> usize{
-        return self.enemies.iter()
-            .filter(|(_, enemy)| {
-                enemy.is_alive()
-            })
+        return self.enemies.alive()
+            .iter()
+            .filter(|alive| **alive)
             .count()
     }
 
@@ -265,160 +641,228 @@ impl Handler{
         let debug = std::env::var("DEBUG:ENTITY_HANDLER").unwrap_or("false".to_string());
 
         if debug.eq("true"){
-            println!("SIZE| Enemies: {:?}, Projectiles: {:?}, overrides: {:?}", self.enemies.len(), self.projectiles.len(), self.enemy_overides.len());
-            println!("CAPACITY| Enemies: {:?}, Projectiles: {:?}, overrides: {:?}", self.enemies.capacity(), self.projectiles.capacity(), self.enemy_overides.capacity());
+            println!("SIZE| Enemies: {:?}, Projectiles: {:?}, steering: {:?}", self.enemies.len(), self.projectiles.len(), self.steering.len());
+            println!("CAPACITY| Enemies: {:?}, Projectiles: {:?}, steering: {:?}", self.enemies.capacity(), self.projectiles.capacity(), self.steering.capacity());
+            println!("UPDATE| {:.3} ms", self.update_time * 1000.0);
 
         }
     }
 
     fn cleanup(&mut self){
         self.enemies.shrink_to_fit();
-        self.projectiles.shrink_to_fit();
-        self.enemy_overides.shrink_to_fit();
+        self.steering.shrink_to_fit();
+        self.threats.shrink_to_fit();
+        self.last_positions.shrink_to_fit();
+        self.line_of_sight.shrink_to_fit();
     }
 }   
 
 
-#[async_trait]
 impl Publisher for Handler{
-    async fn publish(&self, event: Event) {
+    fn publish(&self, event: Event) {
         let _ = self.sender.send(event.clone());
     }
 }
 
-#[async_trait]
 impl Subscriber for Handler{
-    async fn notify(&mut self, event: &Event) {
+    fn notify(&mut self, event: &Event) {
         match &event.event_type{
             EventType::EnemySpawn => {
                 if let Ok(mut entry) = event.data.lock(){
                     if let Some(data) = entry.downcast_mut::<Option<Box<dyn Enemy>>>(){
                         let entity = data.take().unwrap();
-                        let id = entity.get_id();
-                        self.insert_enemy(id, entity);
+                        self.admit(entity);
                     }
                 }
             },
             EventType::EnemyHit => {
                 if let Ok(entry) = event.data.lock(){
-                    if let Some(data) = entry.downcast_ref::<u64>(){
-                        if let Some(enemy) = self.enemies.get_mut(data){
+                    if let Some((id, impact)) = entry.downcast_ref::<(u64, CollisionImpact)>(){
+                        //Hits published before the enemy got recycled
+                        if !self.ids.is_current(*id){
+                            return;
+                        }
+
+                        if let Some(enemy) = self.enemies.get_mut(id){
                             
                             if enemy.is_alive(){
                                 enemy.force_state(StateType::Hit);
+                                //Every hit pushes back, it only shows on enemies that survive it, like armored elites and bosses
+                                enemy.apply_impulse(impact.get_impulse(enemy.get_type().get_mass()));
                             }
                         }
                     }
                 }
                 
             }
+            EventType::PlayerLaserFire => {
+                if let Ok(entry) = event.data.lock(){
+                    if let Some((origin, direction)) = entry.downcast_ref::<(Vec2, Vec2)>(){
+                        self.pending_lasers.push((*origin, direction.normalize_or_zero()));
+                    }
+                }
+            },
             EventType::BatchEnemySpawn => {
                 if let Ok(mut entry) = event.data.lock(){
                     if let Some(data) = entry.downcast_mut::<Vec<Option<Box<dyn Enemy>>>>(){
                         data.iter_mut().for_each(|entry| {
                             let entity = entry.take().unwrap();
-                            let id = entity.get_id();
-                            
-                            self.insert_enemy(id, entity);
+                            self.admit(entity);
                         });
                     }
                 }
             },
             EventType::PlayerBulletSpawn | EventType::EnemyBulletSpawn => {
-                if let Ok(mut entry) = event.data.lock(){
-                    if let Some(data) = entry.downcast_mut::<Option<Box<dyn Projectile>>>(){
-                        
-                        if let Some(entity) = data.take(){
-                            let id = entity.get_id();
-                            self.insert_projectile(id, entity);
-                        }
+                if let Ok(entry) = event.data.lock(){
+                    if let Some(spawn) = entry.downcast_ref::<BulletSpawn>(){
+                        //A full store drops the bullet, like an empty pool used to
+                        let _ = self.projectiles.spawn(*spawn, Clock::now(), &mut self.ids);
                     }
                 }
             },
             EventType::PlayerBulletHit | EventType::EnemyBulletHit => {
-                if let Ok(mut entry) = event.data.lock(){
-                    if let Some(data) = entry.downcast_mut::<u64>(){
-                        if let Some(proj) = self.projectiles.get_mut(&data){
+                if let Ok(entry) = event.data.lock(){
+                    if let Some(data) = entry.downcast_ref::<u64>(){
+                        if self.ids.is_current(*data){
+                            self.projectiles.hit(*data);
+                        }
+                    }
+                }
+            },
+            EventType::EnemyExplosion => {
+                if let Ok(entry) = event.data.lock(){
+                    if let Some(explosion) = entry.downcast_ref::<(u64, Vec2, f32)>(){
+                        self.pending_explosions.push(*explosion);
+                    }
+                }
+            },
+            EventType::BubbleHit => {
+                let mut broken = None;
 
-                            if proj.is_active(){
-                                proj.force_state(StateType::Hit);
+                if let Ok(entry) = event.data.lock(){
+                    if let Some(id) = entry.downcast_ref::<u64>(){
+                        if let Some(enemy) = self.enemies.get_mut(id).filter(|enemy| enemy.is_alive()){
+                            if enemy.absorb(Clock::now()){
+                                broken = Some(enemy.get_pos());
                             }
                         }
                     }
                 }
+
+                if let Some(pos) = broken{
+                    self.bubble_breaks.push(pos);
+
+                    let srequest = SoundRequest::new(true, false, 0.1);
+                    self.publish(Event::new((SoundType::ShieldHit, srequest), EventType::PlaySound));
+                }
             },
-            EventType::CollidingEnemies => {
+            EventType::EnemyStrike => {
                 if let Ok(entry) = event.data.lock(){
-                    if let Some(data) = entry.downcast_ref::<(u64, u64)>(){
-                        if let Some(enemyx) = self.enemies.get_mut(&data.0){
-                            let idx = enemyx.get_id();
-                            let posx = enemyx.get_pos();
-                            let sizex = enemyx.get_size();
-                            let extype = enemyx.get_type();
-
-                            if let Some(enemyy) = self.enemies.get_mut(&data.1){
-                                let idy = enemyy.get_id();
-                                let posy = enemyy.get_pos();
-                                let sizey = enemyy.get_size();
-                                let eytype = enemyy.get_type();
-
-                                let direction = Vec2::new(posy.x - posx.x, posy.y - posx.y);
-                                let distance = direction.length();
-                                let com_radius = sizex + sizey;
-
-                                let volume: f32 = {
-                                    if extype.eq(&EnemyType::Rect) || eytype.eq(&EnemyType::Rect){
-                                        50.0
-                                    }
-                                    else{
-                                        10.0
-                                    }
-                                };
-
-                                let normalized_dir = direction.normalize();
-                                let overlap = (com_radius - distance) / 2.0 + volume;
-
-                                let pos_x_corrected = posx - (normalized_dir * overlap);
-                                let pos_y_corrected = posy + (normalized_dir * overlap);
-                                //Move in negative
-                                self.enemy_overides.insert(idx, OverideType::Displace(pos_x_corrected));
-                                //Move in positive
-                                self.enemy_overides.insert(idy, OverideType::Displace(pos_y_corrected));
-                            }
+                    if let Some(id) = entry.downcast_ref::<u64>(){
+                        if let Some(elite) = self.enemies.get_mut(id).and_then(|enemy| enemy.as_any_mut().downcast_mut::<Elite>()){
+                            elite.on_strike();
                         }
                     }
-                } 
+                }
+            },
+            EventType::DropTrail => {
+                if let Ok(entry) = event.data.lock(){
+                    if let Some(trail) = entry.downcast_ref::<(Vec2, f32, f64)>(){
+                        self.trails.push(*trail);
+                    }
+                }
             },
             EventType::DeflectBulletAndSwitch => {
                 let mut reverted = false;
-                let mut pid: Option<u64> = None;
                 
                 if let Ok(entry) = event.data.lock(){
                     if let Some(data) = entry.downcast_ref::<(u64, ProjectileType)>(){
-                        let id = data.0.to_owned();
-                        let origin = data.1.to_owned();
-
-                        if let Some(proj) = self.projectiles.get_mut(&id){
-
-                            if proj.is_active(){
-                                proj.revert(origin);
-                                reverted = true;
-                                pid = Some(id);
-                            }
-                        }
+                        //The layer change reaches the grid on the next sync
+                        reverted = self.ids.is_current(data.0) && self.projectiles.revert(data.0, data.1);
                     }
                 }
 
                 if reverted{
-                    let id = pid.unwrap_or(0);
-                    self.publish(Event::new(id, EventType::RemoveEntityFromGrid)).await;
-
                     // Emit sound request
                     let srequest = SoundRequest::new(true, false, 0.1);
-                    self.publish(Event::new((SoundType::HexDeflect, srequest), EventType::PlaySound)).await;
+                    self.publish(Event::new((SoundType::HexDeflect, srequest), EventType::PlaySound));
                 }
             }
             _ => unreachable!()
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests{
+    use crate::{entity_handler::{archetype::Archetypes, enemy_type::EnemyType}, utils::harness::{Harness, Timings}};
+
+    const SEED: u64 = 40;
+    //Frames run before timing, so pools, maps and the flow field settle
+    const WARMUP: usize = 120;
+    const FRAMES: usize = 600;
+
+    #[test]
+    fn harness_runs_without_a_window(){
+        let mut harness = Harness::new(64, SEED);
+        harness.run(30);
+
+        assert_eq!(harness.handler_mut().get_enemy_count(), 64);
+        assert!(harness.get_events() > 0);
+    }
+
+    /*
+        Frame times of the sequential and the parallel entity update, on the same enemies.
+        Run with `cargo test --release bench_update_paths -- --ignored --nocapture --test-threads=1`.
+    */
+    #[test]
+    #[ignore]
+    fn bench_update_paths(){
+        let workers = std::thread::available_parallelism().map_or(1, |count| count.get());
+        println!("Workers: {}", workers);
+        println!("{:>8} {:>11} {:>12} {:>12} {:>12}", "enemies", "path", "update ms", "update p95", "frame ms");
+
+        for enemies in [128, 256, 512, 1024, 2048, 4096]{
+            for (path, threshold) in [("sequential", usize::MAX), ("parallel", 0)]{
+                let mut harness = Harness::new(enemies, SEED);
+                harness.handler_mut().set_parallel_threshold(threshold);
+                harness.run(WARMUP);
+
+                let timings = harness.run(FRAMES);
+
+                println!("{:>8} {:>11} {:>12.3} {:>12.3} {:>12.3}",
+                    enemies,
+                    path,
+                    Timings::mean(&timings.updates) * 1000.0,
+                    Timings::percentile(&timings.updates, 95.0) * 1000.0,
+                    Timings::mean(&timings.frames) * 1000.0
+                );
+            }
+        }
+    }
+
+    /*
+        Allocations per handler update, with circles only and with every archetype.
+        Both counts take the sequential path, unless `Global::get_parallel_update_threshold` is lowered.
+        Run with `cargo test --release bench_update_allocations -- --ignored --nocapture --test-threads=1`.
+    */
+    #[test]
+    #[ignore]
+    fn bench_update_allocations(){
+        let circle = Archetypes::find("Circle").map_or(EnemyType::Drone(0), EnemyType::Drone);
+
+        println!("{:>8} {:>11} {:>14} {:>14}", "enemies", "enemies of", "allocs/frame", "per enemy");
+
+        for enemies in [128, 1024]{
+            for (kinds, types) in [("circle", vec![circle]), ("all", Archetypes::get_types_up_to(u8::MAX))]{
+                let mut harness = Harness::with_types(enemies, SEED, types);
+                harness.run(WARMUP);
+
+                let timings = harness.run(FRAMES);
+                let allocations = timings.allocations.iter().sum::<usize>() as f64 / FRAMES as f64;
+
+                println!("{:>8} {:>11} {:>14.1} {:>14.2}", enemies, kinds, allocations, allocations / enemies as f64);
+            }
+        }
+    }
+}
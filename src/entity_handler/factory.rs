@@ -1,8 +1,6 @@
 use std::collections::{HashMap, VecDeque};
 use std::sync::mpsc::Sender;
 
-use async_trait::async_trait;
-
 use macroquad::math::{vec2, Rect, Vec2};
 use macroquad::color::Color;
 use rand::{thread_rng, Rng};
@@ -11,43 +9,54 @@ use crate::event_system::event::{Event, EventType};
 use crate::event_system::interface::{Enemy, Publisher, Subscriber};
 use crate::utils::globals::Global;
 
+use super::archetype::Archetypes;
 use super::enemy_type::EnemyType;
 use super::recycler::Recycler;
 
+//Speed children are thrown apart at
+const SPLIT_IMPULSE: f32 = 400.0;
+
 
 pub struct Factory{
     queue: VecDeque<Box<dyn Enemy>>,
     sender: Sender<Event>,
-    recycler: Recycler
+    recycler: Recycler,
+    bounds: f32,
+    spawn_regions: Vec<Rect>
 }
 
 impl Factory{
-    pub async fn new(sender: Sender<Event>, size: usize, enemy_sender: Sender<Event>) -> Self{
-        let mut recycler = Recycler::new(enemy_sender.clone(), size).await;
+    pub fn new(sender: Sender<Event>, size: usize, enemy_sender: Sender<Event>) -> Self{
+        let mut recycler = Recycler::new(enemy_sender.clone(), size);
         let mut size_param = HashMap::new();
         //Review: Enemy pool size is the same as the factories, because in the extreme case that the factory
         //Review: spanws only one enemy type, the pool has to match the factories queue size.
         
-        let hash_size = size / 2;
-
-        size_param.insert(EnemyType::Circle, size);
-        size_param.insert(EnemyType::Triangle, size);
-        size_param.insert(EnemyType::Rect, hash_size);
-        size_param.insert(EnemyType::Hexagon, hash_size);
+        for (id, archetype) in Archetypes::all().iter().enumerate(){
+            size_param.insert(EnemyType::Drone(id as u8), archetype.get_pool_size(size));
+        }
         size_param.insert(EnemyType::CircleBoss, 3);
         size_param.insert(EnemyType::TriangleBoss, 3);
 
-        recycler.pre_populate(size_param).await;
+        recycler.pre_populate(size_param);
 
         return Factory {
             queue: VecDeque::with_capacity(size),
             sender: sender,
-            recycler: recycler
+            recycler: recycler,
+            bounds: (Global::get_grid_size() * Global::get_cell_size()) as f32,
+            spawn_regions: Vec::new()
         }
     }
 
-    pub async fn queue_enemy(&mut self, enemy_type: EnemyType, pos: Vec2, size: f32, color: Color, player_pos: Vec2){
-        let some_enemy = self.recycler.get_enemy(enemy_type, pos, size, color, player_pos).await;
+    ///Map side length and the regions enemies spawn in. Without regions, enemies spawn around the viewport.
+    pub fn set_spawn_area(&mut self, bounds: f32, regions: Vec<Rect>){
+        self.bounds = bounds;
+        self.spawn_regions = regions;
+    }
+
+    pub fn queue_enemy(&mut self, enemy_type: EnemyType, pos: Vec2, size: f32, color: Color, player_pos: Vec2){
+        let some_enemy = self.recycler.get_enemy(enemy_type, pos, size, color, player_pos);
         /* 
             The idea is that, every time the queue is full, shift to the left 1 place,
             then remove the last element and place new one, to mimic a cyclic list.
@@ -74,45 +83,65 @@ impl Factory{
         }
     }
 
-    async fn queue_template(&mut self, mut template: VecDeque<EnemyType>, player_pos: Vec2, color: Color){
+    fn queue_template(&mut self, mut template: VecDeque<EnemyType>, player_pos: Vec2, color: Color){
         while template.len() > 0{
             if let Some(etype) = template.pop_front(){
                 let pos = Vec2{ x: 0.0, y: 0.0};
 
-                match etype{
-                    EnemyType::Circle => {
-                        let size = thread_rng().gen_range(35..45) as f32;
-                        self.queue_enemy(EnemyType::Circle, pos, size, color, player_pos).await;
-                    },
-                    EnemyType::Triangle => {
-                        let size = thread_rng().gen_range(40..50) as f32;
-                        self.queue_enemy(EnemyType::Triangle, pos, size, color, player_pos).await;
-                    },
-                    EnemyType::Rect => {
-                        let size = thread_rng().gen_range(220..240) as f32;
-                        self.queue_enemy(EnemyType::Rect, pos, size, color, player_pos).await;
-                    },
-                    EnemyType::Hexagon => {
-                        let size = thread_rng().gen_range(100..150) as f32;
-                        self.queue_enemy(EnemyType::Hexagon, pos, size, color, player_pos).await;
-                    },
-                    EnemyType::CircleBoss => {
-                        let size = thread_rng().gen_range(100..150) as f32;
-                        self.queue_enemy(EnemyType::CircleBoss, pos, size, color, player_pos).await;
-                    }
-                    EnemyType::TriangleBoss => {
-                        let size = thread_rng().gen_range(120..170) as f32;
-                        self.queue_enemy(EnemyType::TriangleBoss, pos, size, color, player_pos).await;
-                    }
-                }
+                let size = match etype{
+                    EnemyType::Drone(id) => Archetypes::get(id).size.sample(),
+                    EnemyType::CircleBoss => thread_rng().gen_range(100..150) as f32,
+                    EnemyType::TriangleBoss => thread_rng().gen_range(120..170) as f32,
+                };
+
+                self.queue_enemy(etype, pos, size, color, player_pos);
             }
         }
     }
 
+    /*
+        Spawns `count` children of `enemy_type` around `center`, right away instead of queueing them.
+        They are spread evenly around the parent, and pushed apart.
+    */
+    fn spawn_children(&mut self, enemy_type: EnemyType, center: Vec2, size: f32, color: Color, player_pos: Vec2, count: usize){
+        let offset = thread_rng().gen_range(0.0..std::f32::consts::TAU);
+
+        let children = (0..count)
+            .map(|i| {
+                let direction = Vec2::from_angle(offset + std::f32::consts::TAU * i as f32 / count as f32);
+
+                let mut child = self.recycler.get_or_generate_enemy(enemy_type, center + direction * size, size, color, player_pos);
+                child.apply_impulse(direction * SPLIT_IMPULSE);
+
+                Some(child)
+            })
+            .collect::<Vec<Option<Box<dyn Enemy>>>>();
+
+        self.publish(Event::new(children, EventType::BatchEnemySpawn));
+    }
+
     fn get_enemy_spawn_position(&self, viewport: Rect) -> Vec2 {
         let mut rng = thread_rng();
+
+        if !self.spawn_regions.is_empty(){
+            //Prefer regions out of sight, so enemies don't pop in on screen
+            let hidden: Vec<&Rect> = self.spawn_regions
+                .iter()
+                .filter(|region| !region.overlaps(&viewport))
+                .collect();
+
+            let region = match hidden.is_empty(){
+                true => &self.spawn_regions[rng.gen_range(0..self.spawn_regions.len())],
+                false => hidden[rng.gen_range(0..hidden.len())],
+            };
+
+            return vec2(
+                region.x + rng.gen::<f32>() * region.w,
+                region.y + rng.gen::<f32>() * region.h
+            )
+        }
         
-        let world_width = (Global::get_grid_size() * Global::get_cell_size()) as f32;
+        let world_width = self.bounds;
         let world_height = world_width;
         
         let min_offset = 50.0;
@@ -170,7 +199,7 @@ impl Factory{
     }
 
 
-    async fn forward(&mut self, mut enemies: Vec<Option<Box<dyn Enemy>>>, viewport: Rect){
+    fn forward(&mut self, mut enemies: Vec<Option<Box<dyn Enemy>>>, viewport: Rect){
         enemies.iter_mut()
             .for_each(|boxxed| {
                 if let Some(enemy) = boxxed{
@@ -178,21 +207,19 @@ impl Factory{
                 }
             });
         
-        self.publish(Event::new(enemies, EventType::BatchEnemySpawn)).await
+        self.publish(Event::new(enemies, EventType::BatchEnemySpawn))
     }
 
 }
 
-#[async_trait]
 impl Publisher for Factory{
-    async fn publish(&self, event: Event) {
+    fn publish(&self, event: Event) {
         let _ = self.sender.send(event.clone());
     }
 }
 
-#[async_trait]
 impl Subscriber for Factory{
-    async fn notify(&mut self, event: &Event) {
+    fn notify(&mut self, event: &Event) {
         match event.event_type{
             EventType::QueueEnemy => {
                 let mut enemies = Vec::new();
@@ -210,26 +237,7 @@ impl Subscriber for Factory{
                 }
 
                 for (enemy_type, pos, size, color, player_pos) in enemies{
-                    match enemy_type{
-                        EnemyType::Circle => {
-                            self.queue_enemy(EnemyType::Circle, pos, size, color, player_pos).await;
-                        },
-                        EnemyType::Triangle => {
-                            self.queue_enemy(EnemyType::Triangle, pos, size, color, player_pos).await;
-                        },
-                        EnemyType::Rect => {
-                            self.queue_enemy(EnemyType::Rect, pos, size, color, player_pos).await;
-                        },
-                        EnemyType::Hexagon => {
-                            self.queue_enemy(EnemyType::Hexagon, pos, size, color, player_pos).await;
-                        },
-                        EnemyType::CircleBoss => {
-                            self.queue_enemy(EnemyType::CircleBoss, pos, size, color, player_pos).await;
-                        },
-                        EnemyType::TriangleBoss => {
-                            self.queue_enemy(EnemyType::TriangleBoss, pos, size, color, player_pos).await;
-                        }
-                    }
+                    self.queue_enemy(enemy_type, pos, size, color, player_pos);
                 }
             },
             EventType::QueueTemplate => {
@@ -248,7 +256,7 @@ impl Subscriber for Factory{
 
                 match entry{
                     Some(entry) => {
-                        self.queue_template(entry.0, entry.1, entry.2).await;
+                        self.queue_template(entry.0, entry.1, entry.2);
                     },
                     None => eprintln!("Missing template in QueueTemplate|Factory"),
                 }
@@ -279,7 +287,20 @@ impl Subscriber for Factory{
                         viewport = Some(rect.to_owned())
                     }
                 }
-                self.forward(queue, viewport.unwrap()).await;
+                self.forward(queue, viewport.unwrap());
+            },
+            EventType::SpawnChildren => {
+                let mut request = None;
+
+                if let Ok(result) = event.data.lock(){
+                    if let Some(data) = result.downcast_ref::<(EnemyType, Vec2, f32, Color, Vec2, usize)>(){
+                        request = Some(*data);
+                    }
+                }
+
+                if let Some((enemy_type, center, size, color, player_pos, count)) = request{
+                    self.spawn_children(enemy_type, center, size, color, player_pos, count);
+                }
             },
             EventType::FactoryResize => {
                 if let Ok(result) = event.data.lock(){
@@ -0,0 +1,80 @@
+//Upper half of an id holds the generation, lower half the slot.
+const GENERATION_SHIFT: u64 = 32;
+const SLOT_MASK: u64 = (1 << GENERATION_SHIFT) - 1;
+
+/*
+    Single source of ids for the player, enemies and bullets. Owned by the `Handler`, which
+    gives enemies their id as they come in, and hands one out to the player when the game is built.
+
+    An id is a slot and the generation of that slot. Releasing an id bumps the generation,
+    so when the slot is handed out again, ids still held by old events no longer match and can be dropped.
+    Generations start at 1, which keeps allocated ids above the fixed ids of the wall, bubbles, obstacles and hazards.
+*/
+pub struct IdAllocator{
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    free: Vec<u32>
+}
+
+impl IdAllocator{
+    pub fn new() -> Self{
+        return IdAllocator {
+            generations: Vec::new(),
+            alive: Vec::new(),
+            free: Vec::new()
+        }
+    }
+
+    pub fn allocate(&mut self) -> u64{
+        let slot = match self.free.pop(){
+            Some(slot) => slot,
+            None => {
+                self.generations.push(1);
+                self.alive.push(false);
+                (self.generations.len() - 1) as u32
+            }
+        };
+
+        self.alive[slot as usize] = true;
+
+        return IdAllocator::compose(slot, self.generations[slot as usize])
+    }
+
+    ///Frees the slot of `id` for reuse. Stale or unknown ids are ignored.
+    pub fn release(&mut self, id: u64){
+        if !self.is_current(id){
+            return;
+        }
+
+        let slot = IdAllocator::get_slot(id) as usize;
+
+        self.generations[slot] = self.generations[slot].wrapping_add(1).max(1);
+        self.alive[slot] = false;
+        self.free.push(slot as u32);
+    }
+
+    ///Whether `id` still belongs to a live entity.
+    #[inline(always)]
+    pub fn is_current(&self, id: u64) -> bool{
+        let slot = IdAllocator::get_slot(id) as usize;
+
+        return slot < self.generations.len()
+            && self.alive[slot]
+            && self.generations[slot] == IdAllocator::get_generation(id)
+    }
+
+    #[inline(always)]
+    fn compose(slot: u32, generation: u32) -> u64{
+        return (generation as u64) << GENERATION_SHIFT | slot as u64
+    }
+
+    #[inline(always)]
+    fn get_slot(id: u64) -> u32{
+        return (id & SLOT_MASK) as u32
+    }
+
+    #[inline(always)]
+    fn get_generation(id: u64) -> u32{
+        return (id >> GENERATION_SHIFT) as u32
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+
+use macroquad::math::{Rect, Vec2};
+
+use crate::{collision_system::collider::{CapsuleCollider, Collider}, grid_system::grid::{EntityType, Grid}, objects::bullet::{get_draw_call, get_tail, get_tip, BulletSpawn, ProjectileType}, renderer::artist::DrawCall};
+
+use super::id_allocator::IdAllocator;
+
+
+/*
+    Read only view of a single projectile inside the `ProjectileStore`.
+    Cheap to copy, so it is handed out by value to the `CollisionDetector` and the `Arena`.
+*/
+#[derive(Clone, Copy)]
+pub struct ProjectileRef<'a>{
+    pub id: u64,
+    pub pos: Vec2,
+    pub ptype: ProjectileType,
+    pub is_active: bool,
+    pub collider: &'a CapsuleCollider
+}
+
+impl<'a> ProjectileRef<'a>{
+    #[inline(always)]
+    pub fn collides(&self, other: &dyn Collider) -> bool{
+        return self.collider.collides_with(other)
+    }
+}
+
+/*
+    Struct of arrays storage for every bullet in the game.
+
+    Each column holds one attribute, and index `i` of every column belongs to the same bullet.
+    Removing swaps the last bullet into the gap, so the columns stay dense and updates walk them in order.
+    Ids come from the `IdAllocator` of the `Handler`, and are released when a bullet is removed.
+*/
+pub struct ProjectileStore{
+    ids: Vec<u64>,
+    positions: Vec<Vec2>,
+    directions: Vec<Vec2>,
+    speeds: Vec<f32>,
+    sizes: Vec<f32>,
+    expiries: Vec<f64>,
+    origins: Vec<ProjectileType>,
+    hits: Vec<bool>,
+    colliders: Vec<CapsuleCollider>,
+    //Id -> column index
+    index: HashMap<u64, usize>,
+    //Ids removed since the last grid sync
+    removed: Vec<u64>,
+    capacity: usize
+}
+
+impl ProjectileStore{
+    pub fn new(capacity: usize) -> Self{
+        return ProjectileStore {
+            ids: Vec::with_capacity(capacity),
+            positions: Vec::with_capacity(capacity),
+            directions: Vec::with_capacity(capacity),
+            speeds: Vec::with_capacity(capacity),
+            sizes: Vec::with_capacity(capacity),
+            expiries: Vec::with_capacity(capacity),
+            origins: Vec::with_capacity(capacity),
+            hits: Vec::with_capacity(capacity),
+            colliders: Vec::with_capacity(capacity),
+            index: HashMap::with_capacity(capacity),
+            removed: Vec::with_capacity(capacity),
+            capacity: capacity
+        }
+    }
+
+    ///Adds a bullet from its blueprint. Returns `None` when the store is full.
+    #[inline(always)]
+    pub fn spawn(&mut self, spawn: BulletSpawn, now: f64, ids: &mut IdAllocator) -> Option<u64>{
+        if self.ids.len() >= self.capacity{
+            return None
+        }
+
+        let id = ids.allocate();
+
+        self.index.insert(id, self.ids.len());
+        self.ids.push(id);
+        self.positions.push(spawn.pos);
+        self.directions.push(spawn.direction);
+        self.speeds.push(spawn.speed);
+        self.sizes.push(spawn.size);
+        self.expiries.push(now + spawn.lifetime);
+        self.origins.push(spawn.ptype);
+        self.hits.push(false);
+        self.colliders.push(CapsuleCollider::new(
+            get_tail(spawn.pos, spawn.direction, spawn.size),
+            get_tip(spawn.pos, spawn.direction, spawn.size),
+            spawn.size * 0.25,      // Half of the sprites base width
+            spawn.ptype.get_layer()
+        ));
+
+        return Some(id)
+    }
+
+    /*
+        Drops the bullets that expired or were hit, then moves the rest.
+        Colliders are swept from the previous tail to the current tip, so thin targets can't be skipped.
+    */
+    pub fn update(&mut self, delta: f32, now: f64, ids: &mut IdAllocator){
+        let mut i = 0;
+
+        while i < self.ids.len(){
+            if self.hits[i] || self.expiries[i] <= now{
+                self.remove(i, ids);
+                continue;
+            }
+            i += 1;
+        }
+
+        for i in 0..self.ids.len(){
+            let direction = self.directions[i];
+            let size = self.sizes[i];
+            let previous_tail = get_tail(self.positions[i], direction, size);
+
+            self.positions[i] += direction * self.speeds[i] * delta;
+            self.colliders[i].sweep(previous_tail, get_tip(self.positions[i], direction, size));
+        }
+    }
+
+    ///Swap removes the bullet at column `i`, and releases its id.
+    #[inline(always)]
+    fn remove(&mut self, i: usize, ids: &mut IdAllocator){
+        let id = self.ids.swap_remove(i);
+        self.positions.swap_remove(i);
+        self.directions.swap_remove(i);
+        self.speeds.swap_remove(i);
+        self.sizes.swap_remove(i);
+        self.expiries.swap_remove(i);
+        self.origins.swap_remove(i);
+        self.hits.swap_remove(i);
+        self.colliders.swap_remove(i);
+
+        self.index.remove(&id);
+
+        //The last bullet took the place of the removed one
+        if let Some(moved) = self.ids.get(i){
+            self.index.insert(*moved, i);
+        }
+
+        ids.release(id);
+        self.removed.push(id);
+    }
+
+    ///Marks a bullet as hit. It is dropped on the next update.
+    #[inline(always)]
+    pub fn hit(&mut self, id: u64){
+        if let Some(&i) = self.index.get(&id){
+            self.hits[i] = true;
+        }
+    }
+
+    ///Hands the bullet over to `ptype`, sending it back at half speed. Returns false if it was already gone.
+    pub fn revert(&mut self, id: u64, ptype: ProjectileType) -> bool{
+        let i = match self.index.get(&id){
+            Some(&i) if !self.hits[i] => i,
+            _ => return false,
+        };
+
+        self.origins[i] = ptype;
+        self.colliders[i].set_layer(ptype.get_layer());
+        self.speeds[i] = self.speeds[i] / 2.0;
+        self.directions[i] = -self.directions[i];
+
+        return true
+    }
+
+    ///Writes the bullets straight into the `Grid`, after dropping the removed ones.
+    pub fn sync_grid(&mut self, grid: &mut Grid){
+        for id in self.removed.drain(..){
+            grid.remove_entity(id);
+        }
+
+        for i in 0..self.ids.len(){
+            grid.update_entity(self.ids[i], EntityType::Projectile, self.colliders[i].get_layer(), self.positions[i], self.sizes[i]);
+        }
+    }
+
+    #[inline(always)]
+    fn get_ref(&self, i: usize) -> ProjectileRef<'_>{
+        return ProjectileRef {
+            id: self.ids[i],
+            pos: self.positions[i],
+            ptype: self.origins[i],
+            is_active: !self.hits[i],
+            collider: &self.colliders[i]
+        }
+    }
+
+    #[inline(always)]
+    pub fn get(&self, id: &u64) -> Option<ProjectileRef<'_>>{
+        return self.index.get(id).map(|&i| self.get_ref(i))
+    }
+
+    #[inline(always)]
+    pub fn iter(&self) -> impl Iterator<Item = ProjectileRef<'_>>{
+        return (0..self.ids.len()).map(|i| self.get_ref(i))
+    }
+
+    #[inline(always)]
+    pub fn get_draw_calls(&self, viewport: Rect) -> Vec<DrawCall>{
+        return (0..self.ids.len())
+            .filter(|&i| !self.hits[i] && viewport.contains(self.positions[i]))
+            .map(|i| get_draw_call(self.positions[i], self.directions[i], self.sizes[i]))
+            .collect()
+    }
+
+    #[inline(always)]
+    pub fn len(&self) -> usize{
+        return self.ids.len()
+    }
+
+    #[inline(always)]
+    pub fn capacity(&self) -> usize{
+        return self.capacity
+    }
+}
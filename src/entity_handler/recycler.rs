@@ -1,11 +1,13 @@
-use std::{collections::{HashMap, VecDeque}, sync::{atomic::{AtomicU64, Ordering}, mpsc::Sender}};
+use std::{collections::{HashMap, VecDeque}, sync::mpsc::Sender};
 use macroquad::{color::{Color, WHITE}, math::Vec2};
-use crate::{actors::{circle::Circle, circle_boss::CircleBoss, hexagon::Hexagon, rect, triangle::Triangle, triangle_boss::TriangleBoss}, event_system::{event::Event, interface::Enemy}, utils::machine::StateType};
+use rand::{thread_rng, Rng};
+use crate::{actors::{circle_boss::CircleBoss, drone::Drone, elite::Elite, triangle_boss::TriangleBoss}, event_system::{event::Event, interface::Enemy}, utils::machine::StateType};
 
-use super::enemy_type::EnemyType;
+use super::{archetype::Archetypes, enemy_type::EnemyType};
 
+//Chance of an enemy taken out of a pool rolling as an `Elite`
+const ELITE_CHANCE: f64 = 0.08;
 
-static COUNTER: AtomicU64 = AtomicU64::new(1026);
 
 pub struct Recycler{
     pools: HashMap<EnemyType, VecDeque<Box<dyn Enemy>>>,
@@ -13,13 +15,12 @@ pub struct Recycler{
 }
 
 impl Recycler{
-    pub async fn new(enemy_sender: Sender<Event>, size: usize) -> Self {
+    pub fn new(enemy_sender: Sender<Event>, size: usize) -> Self {
         let mut pools = HashMap::new();
         
-        pools.insert(EnemyType::Circle, VecDeque::with_capacity(size));
-        pools.insert(EnemyType::Triangle, VecDeque::with_capacity(size));
-        pools.insert(EnemyType::Rect, VecDeque::with_capacity(size / 2));
-        pools.insert(EnemyType::Hexagon, VecDeque::with_capacity(size / 2));
+        for (id, archetype) in Archetypes::all().iter().enumerate(){
+            pools.insert(EnemyType::Drone(id as u8), VecDeque::with_capacity(archetype.get_pool_size(size)));
+        }
         pools.insert(EnemyType::CircleBoss, VecDeque::with_capacity(3));
         pools.insert(EnemyType::TriangleBoss, VecDeque::with_capacity(3));
         
@@ -29,7 +30,9 @@ impl Recycler{
         }
     }
 
-    pub fn recycle(&mut self, mut enemy: Box<dyn Enemy>) {
+    ///Elites are unwrapped, so only the base enemy goes back to its pool.
+    pub fn recycle(&mut self, enemy: Box<dyn Enemy>) {
+        let mut enemy = enemy.into_base();
         enemy.set_alive(false);
         
         if let Some(pool) = self.pools.get_mut(&enemy.get_type()) {
@@ -37,7 +40,7 @@ impl Recycler{
         }
     }
 
-    pub async fn pre_populate(&mut self, counts: HashMap<EnemyType, usize>) {
+    pub fn pre_populate(&mut self, counts: HashMap<EnemyType, usize>) {
         let default_pos = Vec2::new(0.0, 0.0);
         let default_size = 1.0;
         let default_color = WHITE;
@@ -60,7 +63,8 @@ impl Recycler{
     }
 
     
-    pub async fn get_enemy(&mut self, 
+    ///Takes an enemy out of its pool, which may roll as an `Elite` of any level.
+    pub fn get_enemy(&mut self, 
         enemy_type: EnemyType, 
         pos: Vec2, 
         size: f32, 
@@ -69,10 +73,14 @@ impl Recycler{
 
         if let Some(pool) = self.pools.get_mut(&enemy_type) {
             if let Some(mut enemy) = pool.pop_front() {
-                enemy.reset(self.generate_id(), pos, color, size, player_pos, true);
+                //The `Handler` gives it an id once it comes in
+                enemy.reset(0, pos, color, size, player_pos, true);
 
                 enemy.force_state(StateType::Idle);
-                enemy.register_configs().await;
+
+                if thread_rng().gen_bool(ELITE_CHANCE){
+                    return Some(Box::new(Elite::wrap(enemy, Elite::roll_level(), self.enemy_sender.clone())));
+                }
 
                 return Some(enemy);
             }
@@ -81,6 +89,21 @@ impl Recycler{
         return None
     }
 
+    ///Like `get_enemy`, but builds a new enemy when its pool ran dry.
+    pub fn get_or_generate_enemy(&mut self, 
+        enemy_type: EnemyType, 
+        pos: Vec2, 
+        size: f32, 
+        color: Color, 
+        player_pos: Vec2) -> Box<dyn Enemy> {
+
+        if let Some(enemy) = self.get_enemy(enemy_type, pos, size, color, player_pos){
+            return enemy
+        }
+
+        return self.generate_enemy(enemy_type, pos, size, color, player_pos)
+    }
+
     pub fn generate_enemy(&mut self, 
                           enemy_type: EnemyType, 
                           pos: Vec2, 
@@ -88,43 +111,13 @@ impl Recycler{
                           color: Color, 
                           player_pos: Vec2) -> Box<dyn Enemy> {
         match enemy_type {
-            EnemyType::Circle => {
-                Box::new(Circle::new(
-                    0, 
-                    pos, 
-                    size, 
-                    color, 
-                    player_pos, 
-                    self.enemy_sender.clone()
-                ))
-            },
-            EnemyType::Triangle => {
-                Box::new(Triangle::new(
-                    0, 
-                    pos, 
-                    size, 
-                    color, 
-                    player_pos, 
-                    self.enemy_sender.clone()
-                ))
-            },
-            EnemyType::Rect => {
-                Box::new(rect::Rect::new(
+            EnemyType::Drone(_) => {
+                Box::new(Drone::with_archetype(
                     0, 
+                    enemy_type,
                     pos, 
                     size, 
                     color, 
-                    player_pos, 
-                    self.enemy_sender.clone()
-                ))
-            },
-            EnemyType::Hexagon => {
-                Box::new(Hexagon::new(
-                    0, 
-                    pos, 
-                    size, 
-                    color, 
-                    player_pos, 
                     self.enemy_sender.clone()
                 ))
             },
@@ -151,15 +144,4 @@ impl Recycler{
         }
     }
 
-    fn generate_id(&self) -> u64 {
-        let id = COUNTER.fetch_add(1, Ordering::SeqCst);
-        
-        // Reset counter
-        if id > 8192 {
-            COUNTER.store(1026, Ordering::SeqCst);
-        }
-        
-        return id
-    }
-
 }
\ No newline at end of file
@@ -1,12 +1,11 @@
 use std::{collections::VecDeque, sync::mpsc::Sender, usize};
 
-use async_trait::async_trait;
 use macroquad::{color::*, math::{Rect, Vec2}, time::get_time};
 use rand::{seq::SliceRandom, thread_rng};
 
 use crate::{event_system::{event::{Event, EventType}, interface::Publisher}, utils::timer::SimpleTimer};
 
-use crate::entity_handler::enemy_type::EnemyType;
+use crate::entity_handler::{archetype::Archetypes, enemy_type::EnemyType, update_context::Difficulty};
 
 
 #[derive(Clone, Copy)]
@@ -34,28 +33,19 @@ impl EnemyComplexity {
     fn get_enemy_type(self) -> EnemyType{
         let mut rnd = thread_rng();
 
-        let pool: Vec<EnemyType> = match self {
-            EnemyComplexity::Simple => {
-                vec![EnemyType::Circle].into()
-            },
-            EnemyComplexity::Average => {
-                vec![EnemyType::Circle, EnemyType::Triangle].into()
-            },
-            EnemyComplexity::Complex => {
-                vec![EnemyType::Circle, EnemyType::Triangle, EnemyType::Rect, EnemyType::CircleBoss].into()
-            },
-            EnemyComplexity::Expert => {
-                vec![EnemyType::Circle, EnemyType::Triangle, EnemyType::Rect, EnemyType::Hexagon].into()
-            },
-            EnemyComplexity::Hell => {
-                vec![EnemyType::Circle, EnemyType::Triangle, EnemyType::Rect, EnemyType::Hexagon, EnemyType::TriangleBoss].into()
-            }, 
-        };
+        //Archetypes join the pool from their complexity onwards
+        let mut pool = Archetypes::get_types_up_to(self as u8);
+
+        match self {
+            EnemyComplexity::Complex => pool.push(EnemyType::CircleBoss),
+            EnemyComplexity::Hell => pool.push(EnemyType::TriangleBoss),
+            _ => {}
+        }
 
         if let Some(etype) = pool.choose(&mut rnd).clone(){
             return *etype
         }
-        return EnemyType::Circle
+        return EnemyType::Drone(0)
     }
 
     #[inline(always)]
@@ -132,7 +122,7 @@ impl SpawnManager{
         If for any reason (Level up) the `enemy_count` surpasses the `factory_queue_capacity`, the factory
         reserved additional space equal to the difference.
     */
-    pub async fn update(&mut self, player_pos: Vec2, 
+    pub fn update(&mut self, player_pos: Vec2, 
                                     active_enemies: usize, 
                                     viewport: Rect, 
                                     factory_queue_size: usize, 
@@ -159,7 +149,7 @@ impl SpawnManager{
         //If enemy count exceeds factory limits, increase capacity, and set queue size to `active_enemies`.
         if enemy_count > factory_queue_capacity{
             let cap = enemy_count - factory_queue_capacity;
-            self.publish(Event::new(cap, EventType::FactoryResize)).await;
+            self.publish(Event::new(cap, EventType::FactoryResize));
         }
 
         //Number of enemies to queue in factory
@@ -183,7 +173,7 @@ impl SpawnManager{
             let template = self.get_spawn_template(amount);
             let color = self.config.complexity.get_color();
 
-            self.publish(Event::new((template, player_pos, color), EventType::QueueTemplate)).await;
+            self.publish(Event::new((template, player_pos, color), EventType::QueueTemplate));
         }
         //Review: The case of factory surplus doesn't need any handling since it will be used later on.
 
@@ -191,11 +181,17 @@ impl SpawnManager{
             self.spawn_timer.set(now, self.config.spawn_interval);
 
             if spawn_enemies != 0{
-                self.publish(Event::new((spawn_enemies, viewport), EventType::ForwardEnemiesToHandler)).await;
+                self.publish(Event::new((spawn_enemies, viewport), EventType::ForwardEnemiesToHandler));
             }
         }
     }
  
+    ///Multipliers enemies apply at the current level.
+    #[inline(always)]
+    pub fn get_difficulty(&self) -> Difficulty{
+        return Difficulty::from_level(self.level)
+    }
+
     #[inline(always)]
     fn advance_level(&mut self, now: f64){
         self.level += 1;
@@ -233,9 +229,8 @@ impl SpawnManager{
     }
 }
 
-#[async_trait]
 impl Publisher for SpawnManager{
-    async fn publish(&self, event: Event){
+    fn publish(&self, event: Event){
         let _ = self.sender.send(event);
     }
 }
\ No newline at end of file
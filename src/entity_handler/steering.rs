@@ -0,0 +1,73 @@
+use macroquad::math::Vec2;
+
+use crate::utils::globals::Global;
+
+/*
+    Boids style steering force of an enemy, computed by the `Handler`
+    from its neighbours in the `Grid` and passed along with the update params.
+*/
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Steering(pub Vec2);
+
+impl Steering{
+    /*
+        Separation pushes away from neighbours, stronger the closer they are.
+        Alignment nudges the enemy towards the average heading of its neighbours.
+
+        Neighbours are (center, size, heading) triplets.
+    */
+    pub fn compute(center: Vec2, size: f32, neighbours: &[(Vec2, f32, Vec2)]) -> Steering{
+        let radius = Global::get_steering_radius();
+
+        let mut separation = Vec2::ZERO;
+        let mut alignment = Vec2::ZERO;
+        let mut count = 0;
+
+        for (other_center, other_size, heading) in neighbours{
+            let offset = center - *other_center;
+            let range = size + other_size + radius;
+            let distance = offset.length();
+
+            if distance >= range{
+                continue;
+            }
+
+            separation += offset.normalize_or_zero() * (1.0 - distance / range);
+            alignment += heading.normalize_or_zero();
+            count += 1;
+        }
+
+        if count == 0{
+            return Steering::default()
+        }
+
+        let alignment = (alignment / count as f32).normalize_or_zero();
+
+        return Steering(
+            separation * Global::get_separation_weight()
+            + alignment * Global::get_alignment_weight()
+        )
+    }
+
+    /*
+        Moves from `pos` towards `destination` by at most `max_step`,
+        with the steering force blended into the direction.
+    */
+    #[inline(always)]
+    pub fn blend(&self, pos: Vec2, destination: Vec2, max_step: f32) -> Vec2{
+        let to_destination = destination - pos;
+        let distance = to_destination.length();
+
+        if max_step <= 0.0{
+            return pos
+        }
+
+        //Slow down on arrival instead of overshooting
+        let chase = match distance > max_step{
+            true => to_destination / distance,
+            false => to_destination / max_step,
+        };
+
+        return pos + (chase + self.0).clamp_length_max(1.0) * max_step
+    }
+}
@@ -0,0 +1,94 @@
+use std::sync::Arc;
+
+use macroquad::math::Vec2;
+
+use crate::grid_system::flow_field::FlowField;
+
+use super::steering::Steering;
+
+
+//Per level past the first, capped at `MAX_SPEED` and `MAX_FIRE_RATE`.
+const SPEED_STEP: f32 = 0.05;
+const FIRE_RATE_STEP: f32 = 0.1;
+const MAX_SPEED: f32 = 1.5;
+const MAX_FIRE_RATE: f32 = 2.0;
+
+///Multipliers enemies apply to their base attributes, growing with the spawners level.
+#[derive(Clone, Copy, Debug)]
+pub struct Difficulty{
+    ///Scales the distance enemies cover per frame.
+    pub speed: f32,
+    ///Scales how often enemies fire, cooldowns are divided by it.
+    pub fire_rate: f32
+}
+
+impl Difficulty{
+    pub fn from_level(level: i32) -> Difficulty{
+        let steps = (level - 1).max(0) as f32;
+
+        return Difficulty {
+            speed: (1.0 + steps * SPEED_STEP).min(MAX_SPEED),
+            fire_rate: (1.0 + steps * FIRE_RATE_STEP).min(MAX_FIRE_RATE)
+        }
+    }
+}
+
+impl Default for Difficulty{
+    fn default() -> Self{
+        return Difficulty::from_level(1)
+    }
+}
+
+///Frame data shared by every entity updated this frame.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FrameContext{
+    pub player_pos: Vec2,
+    pub player_velocity: Vec2,
+    pub is_shielding: bool,
+    pub time: f64,
+    pub difficulty: Difficulty
+}
+
+impl FrameContext{
+    pub fn new(time: f64, difficulty: Difficulty) -> FrameContext{
+        return FrameContext {
+            time: time,
+            difficulty: difficulty,
+            ..Default::default()
+        }
+    }
+
+    pub fn set_player(&mut self, pos: Vec2, velocity: Vec2, is_shielding: bool){
+        self.player_pos = pos;
+        self.player_velocity = velocity;
+        self.is_shielding = is_shielding;
+    }
+}
+
+/*
+    Everything an entity reads during `Updatable::update`.
+    The `Handler` builds one per enemy, borrowing the frame data and what it computed for that enemy.
+*/
+pub struct UpdateContext<'a>{
+    pub frame: &'a FrameContext,
+    pub steering: Steering,
+    pub flow_field: Option<&'a Arc<FlowField>>,
+    pub line_of_sight: bool,
+    ///Positions of the player bullets near the entity.
+    pub threats: &'a [Vec2],
+    ///Destination that replaces the entities own, if set.
+    pub overide: Option<Vec2>
+}
+
+impl<'a> UpdateContext<'a>{
+    pub fn new(frame: &'a FrameContext) -> UpdateContext<'a>{
+        return UpdateContext {
+            frame: frame,
+            steering: Steering::default(),
+            flow_field: None,
+            line_of_sight: true,
+            threats: &[],
+            overide: None
+        }
+    }
+}
@@ -1,3 +1,4 @@
 pub mod dispatcher;
 pub mod interface;
 pub mod event;
+pub mod buffer;
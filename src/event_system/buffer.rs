@@ -0,0 +1,46 @@
+use std::{cell::RefCell, sync::mpsc::Sender};
+
+use super::event::Event;
+
+thread_local! {
+    static BUFFER: RefCell<Option<Vec<Event>>> = const { RefCell::new(None) };
+}
+
+/*
+    Per-thread event buffer.
+
+    While a thread is buffering, events published through `EventBuffer::send` are held back,
+    instead of going straight to the `Dispatcher`. Worker threads buffer their events,
+    and their owner forwards the buffers in a fixed order once they finish.
+*/
+pub struct EventBuffer;
+
+impl EventBuffer{
+    ///Starts buffering on the current thread.
+    pub fn begin(){
+        BUFFER.with(|buffer| *buffer.borrow_mut() = Some(Vec::new()));
+    }
+
+    ///Stops buffering on the current thread, and returns what was buffered.
+    pub fn end() -> Vec<Event>{
+        return BUFFER.with(|buffer| buffer.borrow_mut().take().unwrap_or_default())
+    }
+
+    ///Buffers the event if the current thread is buffering, otherwise sends it.
+    #[inline(always)]
+    pub fn send(sender: &Sender<Event>, event: Event){
+        let unbuffered = BUFFER.with(|buffer| {
+            match buffer.borrow_mut().as_mut(){
+                Some(events) => {
+                    events.push(event);
+                    None
+                },
+                None => Some(event),
+            }
+        });
+
+        if let Some(event) = unbuffered{
+            let _ = sender.send(event);
+        }
+    }
+}
@@ -33,13 +33,13 @@ impl Dispatcher{
         return self.sender.clone()
     }
 
-    pub async fn dispatch(&self){
+    pub fn dispatch(&self){
         while let Ok(event) = self.receiver.try_recv() {
             if let Some(subscriber_list) = self.subscribers.get(&event.event_type) {
                 for subscriber in subscriber_list {
                     match subscriber.lock(){
                         Ok(mut sub) => {
-                            sub.notify(&event).await;
+                            sub.notify(&event);
                         },
                         Err(err) => eprintln!("Error during dispatching: {}", err),
                     }
@@ -49,11 +49,11 @@ impl Dispatcher{
     }
 
     #[allow(dead_code)]
-    pub async fn dispatch_event(&self, event: Event){
+    pub fn dispatch_event(&self, event: Event){
         if let Some(subscriber_list) = self.subscribers.get(&event.event_type) {
             for subscriber in subscriber_list {
                 if let Ok(mut sub) = subscriber.lock() {
-                    sub.notify(&event).await;
+                    sub.notify(&event);
                 }
             }
         }
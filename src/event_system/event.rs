@@ -8,14 +8,19 @@ pub enum EventType{
     GameOver,
     //Player
     PlayerHit,
+    PlayerOnHazards,
     //Handler
     EnemySpawn,
     EnemyHit,
     BatchEnemySpawn,
     PlayerBulletSpawn,
     PlayerBulletHit,
-    CollidingEnemies,
+    PlayerLaserFire,
     DeflectBulletAndSwitch,
+    EnemyExplosion,
+    BubbleHit,
+    EnemyStrike,
+    DropTrail,
     //Enemies
     EnemyBulletSpawn,
     EnemyBulletHit,
@@ -24,12 +29,9 @@ pub enum EventType{
     QueueTemplate,
     ForwardEnemiesToHandler,
     FactoryResize,
+    SpawnChildren,
     //Factory-Recycler
     BatchRecycle,
-    //BulletPool
-    BatchBulletRecycle,
-    RecycleBullet,
-    RequestBlankCollection,
     //Grid
     InsertOrUpdateToGrid,
     RemoveEntityFromGrid,
@@ -37,9 +39,6 @@ pub enum EventType{
     RegisterEmitterConf,
     UnregisterEmitterConf,
     DrawEmitter,
-    //Actors
-    ForwardCollectionToPlayer,
-    ForwardCollectionToEntity,
     //Triangle Assistant
     TriangleBulletRequest,
     BossBulletRequest,
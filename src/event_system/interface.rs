@@ -1,19 +1,16 @@
 use std::{any::Any, sync::mpsc::Sender};
 
-use async_trait::async_trait;
 use macroquad::{color::Color, math::Vec2};
 
-use crate::{collision_system::collider::Collider, entity_handler::enemy_type::EnemyType, event_system::event::Event, objects::bullet::{Bullet, ProjectileType}, renderer::artist::DrawCall, utils::machine::StateType};
+use crate::{collision_system::collider::Collider, entity_handler::{enemy_type::EnemyType, update_context::UpdateContext}, event_system::event::Event, objects::bubble::Bubble, renderer::artist::DrawCall, utils::machine::StateType};
 
 //========= Event related interfaces ==========
-#[async_trait]
 pub trait Subscriber: Send + Sync{
-    async fn notify(&mut self, event: &Event);
+    fn notify(&mut self, event: &Event);
 }
 
-#[async_trait]
 pub trait Publisher: Send + Sync{
-    async fn publish(&self, event: Event);
+    fn publish(&self, event: Event);
 }
 
 //======= General traits ==========
@@ -24,9 +21,8 @@ pub trait Object: Send + Sync{
     fn as_any_mut(&mut self) -> &mut dyn Any;
 }
 
-#[async_trait]
 pub trait Updatable: Object{
-    async fn update(&mut self, delta: f32, params: Vec<Box<dyn Any + Send>>);
+    fn update(&mut self, delta: f32, context: &UpdateContext);
 }
 
 pub trait Moveable: Object{
@@ -38,7 +34,6 @@ pub trait Drawable{
     fn should_emit(&self) -> bool;
 }
 
-#[async_trait]
 pub trait GameEntity: Updatable + Drawable{
     fn get_id(&self) -> u64;
     fn get_size(&self) -> f32;
@@ -46,49 +41,42 @@ pub trait GameEntity: Updatable + Drawable{
     fn get_collider(&self) -> &dyn Collider;
 }
 
-#[async_trait]
 pub trait Playable: GameEntity{
     fn get_state(&self) ->Option<StateType>;
     fn drift_to(&mut self, delta: f32) -> (f32, f32);     
 }
 #[allow(dead_code)]
-#[async_trait]
 pub trait Enemy: GameEntity{
     fn new(id: u64, pos: Vec2, size: f32, color: Color, player_pos: Vec2, sender: Sender<Event>) -> Self where Self: Sized;
     fn set_id(&mut self, id: u64);
     fn set_pos(&mut self, new_pos: Vec2);
     fn set_color(&mut self, new_color: Color);
+    fn get_color(&self) -> Color;
     fn set_size(&mut self, new_size: f32);
     fn set_target(&mut self, new_target: Vec2);
 
-    async fn register_configs(&self);
+    fn register_configs(&self);
     
     fn is_alive(&self) -> bool;
     fn set_alive(&mut self, alive: bool);
 
     fn force_state(&mut self, state: StateType);
     fn get_state(&self) -> Option<StateType>;
+    fn apply_impulse(&mut self, impulse: Vec2);
 
     fn get_all_draw_calls(&self) -> Vec<DrawCall>;  //REVIEW: Currently only serves debugging purposes.
     fn get_type(&self) -> EnemyType;
 
-    fn reset(&mut self, id: u64, pos: Vec2, color: Color, size: f32, target: Vec2, is_alive: bool);
-}
-
-#[allow(dead_code)]
-#[async_trait]
-pub trait Projectile: GameEntity{
-    fn get_ptype(&self) -> ProjectileType;
-    fn revert(&mut self, ptype: ProjectileType);
-    
-    fn is_active(&self) -> bool;
-    fn set_active(&mut self, alive: bool);
-    fn reset(&mut self, id: u64);
+    ///Shield bubble the enemy projects, if any.
+    fn get_bubble(&self) -> Option<&Bubble>{
+        return None
+    }
+    ///Takes a player bullet on the bubble. Returns true if that broke it.
+    fn absorb(&mut self, _now: f64) -> bool{
+        return false
+    }
+    ///Strips whatever the enemy is wrapped in, like an `Elite`, back to the pooled enemy.
+    fn into_base(self: Box<Self>) -> Box<dyn Enemy>;
 
-    fn force_state(&mut self, state: StateType);
-    fn get_state(&self) -> Option<StateType>;
-    
-    fn get_all_draw_calls(&self) -> Vec<DrawCall>;  //REVIEW: Currently only serves debugging purposes.
-
-    fn as_bullet(self: Box<Self>) -> Bullet;
+    fn reset(&mut self, id: u64, pos: Vec2, color: Color, size: f32, target: Vec2, is_alive: bool);
 }
\ No newline at end of file
@@ -8,22 +8,28 @@ use std::sync::mpsc::Sender;
 use std::sync::{Arc, Mutex};
 
 use crate::audio_system::audio_handler::{Accoustic, SoundRequest, SoundType};
+use crate::collision_system::collider::Collider;
 use crate::collision_system::collision_detector::CollisionDetector;
+use crate::collision_system::layer::CollisionLayer;
 use crate::entity_handler::entity_handler::Handler;
 use crate::entity_handler::factory::Factory;
 use crate::entity_handler::spawn_manager::SpawnManager;
+use crate::entity_handler::projectile_store::ProjectileRef;
 use crate::entity_handler::triangle_assistant::TriangleAssistant;
-use crate::entity_handler::bullet_pool::BulletPool;
+use crate::entity_handler::update_context::{Difficulty, FrameContext, UpdateContext};
 use crate::event_system::event::Event;
-use crate::event_system::interface::{Drawable, Enemy, GameEntity, Object, Playable, Projectile, Updatable};
+use crate::event_system::interface::{Drawable, Enemy, GameEntity, Object, Playable, Updatable};
 use crate::actors::player::Player;
 use crate::event_system::{event::EventType, dispatcher::Dispatcher};
 use crate::grid_system::wall::Wall;
+use crate::grid_system::arena::Arena;
+use crate::grid_system::map::MapLayout;
 use crate::grid_system::grid::{EntityType, Grid};
-use crate::objects::bullet::ProjectileType;
 use crate::renderer::artist::{Artist, DrawCall};
 use crate::renderer::metal::MetalArtist;
 use crate::ui::uicontroller::UIController;
+use crate::ui::editor::{Editor, EditorAction};
+use crate::utils::clock::Clock;
 use crate::utils::globals::Global;
 use crate::utils::machine::StateType;
 use crate::utils::tinkerer::{AudioSettings, ScoreboardEntry, Tinkerer};
@@ -38,6 +44,7 @@ pub enum GameState{
     Settings,
     GameOver,
     GameOverMenu,
+    Editor,
     Quit
 }
 
@@ -59,6 +66,10 @@ pub struct GameManager{
     
     grid: Arc<Mutex<Grid>>,
     wall: Wall,
+    arena: Arena,
+    maps: Vec<MapLayout>,
+    map: MapLayout,
+    editor: Option<Editor>,
 
     detector: CollisionDetector,
 
@@ -111,7 +122,10 @@ impl GameManager{
         };
 
 
-        let map_bounds = (Global::get_cell_size() * Global::get_grid_size()) as f32;
+        //Maps are built on the new game screen, until then the default map stands in.
+        let maps = MapLayout::load_all();
+        let map = MapLayout::default();
+        let map_bounds = map.get_bounds();
 
         let mut dispatcher = Dispatcher::new();
         let spawner = Arc::new(Mutex::new(
@@ -124,35 +138,33 @@ impl GameManager{
             Factory::new(
                 dispatcher.create_sender(), 
                 Global::get_factory_size(),
-                dispatcher.create_sender()).await
+                dispatcher.create_sender())
             ));
         let grid = Arc::new(Mutex::new(
             Grid::new(
-                Global::get_grid_size(),
-                Global::get_cell_size(),
+                map.grid_size,
+                map.cell_size,
+                Global::get_hash_cell_size(),
                 Global::get_cell_capacity(),
                 dispatcher.create_sender())
             ));
-        let handler = Arc::new(Mutex::new(Handler::new(dispatcher.create_sender())));
+        let mut handler = Handler::new(dispatcher.create_sender());
+        let player_id = handler.allocate_id();
+        let handler = Arc::new(Mutex::new(handler));
         let detector = CollisionDetector::new(dispatcher.create_sender());
         let player = Arc::new(Mutex::new(Player::new(
-                map_bounds / 2.0,
-                map_bounds / 2.0,
+                player_id,
+                map.player_spawn.x,
+                map.player_spawn.y,
                 15.0,
                 BLACK,
                 dispatcher.create_sender(),
                 tinkerer.get_variables()
-        ).await));
-        let metal = Arc::new(Mutex::new(MetalArtist::new()));
-
-        let bullet_pool = Arc::new(Mutex::new(BulletPool::new(
-            1024, 
-            dispatcher.create_sender()
         )));
+        let metal = Arc::new(Mutex::new(MetalArtist::new()));
 
         let assistant = Arc::new(Mutex::new(TriangleAssistant::new(
             dispatcher.create_sender(), 
-            Global::get_triangle_assistant_pool_size(), 
             Global::get_triangle_bullet_amount()
         )));
 
@@ -162,7 +174,7 @@ impl GameManager{
         
         //Player events
         dispatcher.register_listener(EventType::PlayerHit, player.clone());
-        dispatcher.register_listener(EventType::ForwardCollectionToPlayer, player.clone());
+        dispatcher.register_listener(EventType::PlayerOnHazards, player.clone());
 
         //Grid events
         dispatcher.register_listener(EventType::InsertOrUpdateToGrid, grid.clone());
@@ -174,16 +186,21 @@ impl GameManager{
         dispatcher.register_listener(EventType::BatchEnemySpawn, handler.clone());
         dispatcher.register_listener(EventType::PlayerBulletSpawn, handler.clone());
         dispatcher.register_listener(EventType::PlayerBulletHit, handler.clone());
+        dispatcher.register_listener(EventType::PlayerLaserFire, handler.clone());
         dispatcher.register_listener(EventType::EnemyBulletSpawn, handler.clone());
         dispatcher.register_listener(EventType::EnemyBulletHit, handler.clone());
-        dispatcher.register_listener(EventType::CollidingEnemies, handler.clone());
         dispatcher.register_listener(EventType::DeflectBulletAndSwitch, handler.clone());
+        dispatcher.register_listener(EventType::EnemyExplosion, handler.clone());
+        dispatcher.register_listener(EventType::BubbleHit, handler.clone());
+        dispatcher.register_listener(EventType::EnemyStrike, handler.clone());
+        dispatcher.register_listener(EventType::DropTrail, handler.clone());
 
         //Factory events
         dispatcher.register_listener(EventType::QueueEnemy, factory.clone());
         dispatcher.register_listener(EventType::QueueTemplate, factory.clone());
         dispatcher.register_listener(EventType::ForwardEnemiesToHandler, factory.clone());
         dispatcher.register_listener(EventType::FactoryResize, factory.clone());
+        dispatcher.register_listener(EventType::SpawnChildren, factory.clone());
         dispatcher.register_listener(EventType::BatchRecycle, factory.clone());
 
         //MetalArtist events
@@ -191,14 +208,8 @@ impl GameManager{
         dispatcher.register_listener(EventType::UnregisterEmitterConf, metal.clone());
         dispatcher.register_listener(EventType::DrawEmitter, metal.clone());
 
-        //BulletPool events
-        dispatcher.register_listener(EventType::RecycleBullet, bullet_pool.clone());
-        dispatcher.register_listener(EventType::RequestBlankCollection, bullet_pool.clone());
-        dispatcher.register_listener(EventType::BatchBulletRecycle, bullet_pool.clone());
-
         //Triangle Assistant
         dispatcher.register_listener(EventType::TriangleBulletRequest, assistant.clone());
-        dispatcher.register_listener(EventType::ForwardCollectionToEntity, assistant.clone());
         dispatcher.register_listener(EventType::RemoveTriangle, assistant.clone());
         dispatcher.register_listener(EventType::BossBulletRequest, assistant.clone());
 
@@ -231,6 +242,10 @@ impl GameManager{
 
             grid: grid,
             wall: Wall::new(map_bounds, dispatcher.create_sender()),
+            arena: Arena::new(&map.obstacles, &map.hazards, dispatcher.create_sender()),
+            maps: maps,
+            map: map,
+            editor: None,
             
             detector: detector,
             
@@ -295,6 +310,11 @@ impl GameManager{
 
                 return StatusCode::Reset
             },
+            GameState::Editor => {
+                self.update_editor().await;
+
+                return StatusCode::Editor
+            },
             GameState::Quit => {
                 return StatusCode::Exit
             }
@@ -323,8 +343,16 @@ impl GameManager{
 
     async fn update_game(&mut self) {
         let mut player_pos = self.player.try_lock().unwrap().get_pos();
+        let mut player_bounds = Rect::new(player_pos.x, player_pos.y, 0.0, 0.0);
+        let mut player_velocity = Vec2::ZERO;
+        let mut player_shielding = false;
+        let mut difficulty = Difficulty::default();
         let mut camera_pos = player_pos;
 
+        // Camera leads the player by where they will be, stopped short by obstacles
+        let look_ahead_time = 0.35;
+        let max_look_ahead = 450.0;
+
         // Zoom variables
         let mut zoom_level = 0.0008;
         let zoom_speed = 0.000001; 
@@ -373,14 +401,21 @@ impl GameManager{
 
             // ======= Updates ========
             let delta = get_frame_time();
+            let mut camera_target = player_pos;
+            Clock::tick();
+            let mut frame = FrameContext::new(Clock::now(), difficulty);
 
             {
                 if let Ok(mut player) = self.player.try_lock(){
-                    player.update(delta, vec!()).await;
+                    player.update(delta, &UpdateContext::new(&frame));
                     player_pos = player.get_pos();
+                    player_bounds = player.get_collider().get_bounds();
+                    player_velocity = player.velocity;
+                    player_shielding = player.is_shielding();
 
-                    self.wall.update((player_pos, player.size)).await;
+                    self.wall.update((player_pos, player.size, player.velocity));
                     let wall_calls = self.wall.get_draw_calls(viewport);
+                    emitter_calls.extend(self.wall.get_emitter_calls());
 
                     //Queue players draw calls on highest layer
                     for call in player.get_all_draw_calls(){
@@ -388,6 +423,7 @@ impl GameManager{
                     }
 
                     draw_calls.extend(wall_calls);
+                    draw_calls.extend(self.arena.get_draw_calls(viewport));
 
                     if player.should_emit(){
                         let effect_pos;
@@ -402,9 +438,12 @@ impl GameManager{
                 }
             }
 
+            frame.set_player(player_pos, player_velocity, player_shielding);
+
             if let Ok(mut handler) = self.handler.try_lock(){
                 {
-                    handler.update(delta, player_pos).await;
+                    handler.update(delta, &frame);
+                    handler.confine_enemies(&self.wall);
                     
                     draw_calls.extend(handler.get_draw_calls(viewport));
                     emitter_calls.extend(handler.get_emitter_calls());
@@ -417,8 +456,10 @@ impl GameManager{
                                 handler.get_active_enemy_count(), 
                                 viewport,
                                 factory.get_queue_size(),
-                            factory.get_queue_capacity()).await;
+                            factory.get_queue_capacity());
                         }
+                        //Reaches the enemies on the next frame
+                        difficulty = spawner.get_difficulty();
                     }
                 }
             
@@ -426,105 +467,154 @@ impl GameManager{
                     //Phase 1. Detect Player collisions.
                     {
                         grid.update();
+                        handler.sync_projectiles(&mut grid);
                         draw_calls.extend(grid.get_draw_calls(viewport));
-                        //Retrieve enemy id's that are adjacent to the player in a -1..1 radius.
-                        let nearby_enemy_ids = grid.get_nearby_entities_by_type(player_pos, EntityType::Enemy);
+                        //Retrieve entities overlapping the players bounds, that the player can collide with.
+                        let nearby_entities = grid.query_rect(player_bounds, CollisionLayer::Player);
                         //Retrieve enemies based on Ids
-                        let nearby_enemies: Vec<Option<&dyn Enemy>> = nearby_enemy_ids
+                        let nearby_enemies: Vec<Option<&dyn Enemy>> = nearby_entities
                             .iter()
-                            .filter_map(|id| {
-                                Some(handler.get_enemy(id).filter(|enemy| enemy.is_alive()))
-                            })
+                            .filter(|(etype, _)| *etype == EntityType::Enemy)
+                            .map(|(_, id)| handler.get_enemy(id).filter(|enemy| enemy.is_alive()))
                             .collect();
                         
-                        //Retrieve projectile id's that are adjucent to the player in a -1..1 radius.
-                        let neaby_projectile_ids = grid.get_nearby_entities_by_type(player_pos, EntityType::Projectile);
-                        //Filter projectile so that we only keep active enemy projectiles.
-                        let nearby_projectiles: Vec<Option<&dyn Projectile>> = neaby_projectile_ids
+                        //Only enemy projectiles pass the collision matrix, so we only keep the active ones.
+                        let nearby_projectiles: Vec<Option<ProjectileRef>> = nearby_entities
                             .iter()
-                            .filter_map(|id| {
-                                Some(handler.get_projectile(id).filter(|projectile| {
-                                    projectile.is_active() && projectile.get_ptype() == ProjectileType::Enemy
-                                }))
-                            })
+                            .filter(|(etype, _)| *etype == EntityType::Projectile)
+                            .map(|(_, id)| handler.get_projectile(id).filter(|projectile| projectile.is_active))
+                            .collect();
+
+                        let nearby_hazards: Vec<u64> = nearby_entities
+                            .iter()
+                            .filter(|(etype, _)| *etype == EntityType::Hazard)
+                            .map(|(_, id)| *id)
+                            .collect();
+
+                        let nearby_obstacles: Vec<u64> = nearby_entities
+                            .iter()
+                            .filter(|(etype, _)| *etype == EntityType::Obstacle)
+                            .map(|(_, id)| *id)
                             .collect();
 
                         //Update collision detector
                         if let Ok(player) = self.player.try_lock(){
+                            //Called without nearby hazards too, so leaving them is noticed
+                            self.arena.apply_hazards(&nearby_hazards, player.get_pos());
+
+                            if !nearby_obstacles.is_empty(){
+                                self.arena.collide_player(&nearby_obstacles, (player.get_pos(), player.size, player.velocity));
+                            }
+
                             self.detector.detect_player_collision(
                                 player.get_id(),
+                                player.get_pos(),
+                                player.is_shielding(),
                                 player.get_collider(), 
                                 nearby_enemies
-                            ).await;
+                            );
                             
                             self.detector.detect_enemy_projectile_collision(
+                                player.get_pos(),
                                 player.get_collider(), 
                                 nearby_projectiles
-                            ).await;
+                            );
                         }
                     }
 
                     //Phase 2. Detect Players projectile collisions.
                     {
+                        //Bubbles absorb player projectiles before they reach the enemies inside
+                        let bubbles = handler.get_bubbles();
+
                         //Fetch all projectiles
                         for projectile in handler.get_projectiles(){
-                            //For each projectile, if approximate entities exist
-                            if let Some(approximate) = grid.get_approximate_entities(projectile.get_pos()){
-                                
-                                let player_projectiles = approximate.iter()
-                                    .filter(|(etype, _)| {
-                                        //Player origin, Enemy entities
-                                        projectile.get_ptype() == ProjectileType::Player && *etype == EntityType::Enemy
-                                    })
-                                    .map(|(etype, id)| (*etype, *id))
-                                    .collect::<Vec<(EntityType, u64)>>();
-
-                                //Collect enemies from handler
-                                let enemies: Vec<Option<&dyn Enemy>> = player_projectiles.iter()
-                                    .map(|(_, id)| {
-                                        handler.get_enemy(id)
-                                    })
-                                    .collect();
-
-                                //Check for collision on each enemy
-                                self.detector.detect_players_projectile_collision(projectile, enemies).await;
+                            if !bubbles.is_empty() && self.detector.detect_bubble_collision(projectile, &bubbles){
+                                continue;
+                            }
+
+                            let layer = projectile.collider.get_layer();
+
+                            //Bounds of the swept collider, so fast projectiles find what they passed through
+                            let nearby_entities = grid.query_rect(projectile.collider.get_bounds(), layer);
+
+                            //Obstacles stop both player and enemy projectiles
+                            let obstacles: Vec<u64> = nearby_entities
+                                .iter()
+                                .filter(|(etype, _)| *etype == EntityType::Obstacle)
+                                .map(|(_, id)| *id)
+                                .collect();
+
+                            if !obstacles.is_empty() && projectile.is_active{
+                                self.arena.collide_projectile(projectile, &obstacles);
+                            }
+
+                            //Collect enemies along the projectiles path, that its layer can collide with
+                            let enemies: Vec<Option<&dyn Enemy>> = nearby_entities
+                                .iter()
+                                .filter(|(etype, _)| *etype == EntityType::Enemy)
+                                .map(|(_, id)| handler.get_enemy(id))
+                                .collect();
+
+                            //Check for collision on each enemy
+                            if !enemies.is_empty(){
+                                self.detector.detect_players_projectile_collision(projectile, enemies);
                             }
                         }
                     }
                     
-                    //Phase 3. Detect inter-Enemy collisions.
+                    //Phase 3. Steer enemies away from their neighbours.
                     {
-                        //Get populated cells
-                        let populated_cells = grid.get_populated_cells();
-                        //Iterate ids and map to enemies
-                        for cell in populated_cells{
-                            let mut cell_enemies: Vec<&dyn Enemy> = Vec::new();
-
-                            for id in cell{
-                                if let Some(enemy) = handler.get_enemy(&id){
-                                    cell_enemies.push(enemy);
-                                }
-                            }
-                            //Trigger collision detector
-                            self.detector.detect_enemy_collision(cell_enemies).await;
+                        handler.steer_enemies(&grid, delta);
+                    }
+
+                    //Phase 4. Push enemies out of obstacles, and route the flow field around them.
+                    {
+                        handler.resolve_obstacles(&self.arena, &grid);
+
+                        if let Some(areas) = self.arena.take_blocked_areas(){
+                            handler.set_blocked_areas(areas);
                         }
+                        emitter_calls.extend(self.arena.get_emitter_calls());
+                    }
+
+                    //Phase 5. Raycasts and area queries. Triangles line of sight, lasers, explosions, elite trails and the cameras look ahead.
+                    {
+                        handler.update_line_of_sight(&grid, &self.arena, player_pos);
+                        handler.resolve_lasers(&grid, &self.arena);
+                        handler.resolve_explosions(&grid, player_bounds);
+                        handler.resolve_trails(player_bounds);
+
+                        let look_ahead = (player_velocity * look_ahead_time).clamp_length_max(max_look_ahead);
+
+                        let hits = grid.raycast(player_pos, look_ahead, look_ahead.length(), 0.0, CollisionLayer::Player, |etype, id| {
+                            match etype{
+                                EntityType::Obstacle => self.arena.get_obstacle_collider(id),
+                                _ => None
+                            }
+                        });
+
+                        camera_target = match hits.first(){
+                            Some(hit) => player_pos + look_ahead.normalize_or_zero() * hit.distance,
+                            None => player_pos + look_ahead,
+                        };
                     }
                 }
             }
     
             // Camera
-            camera_pos = camera_pos + (player_pos - camera_pos) * delta * 5.0;
+            camera_pos = camera_pos + (camera_target - camera_pos) * delta * 5.0;
             camera.target = camera_pos;
             set_camera(&camera);
     
-            self.dispatcher.dispatch().await;
+            self.dispatcher.dispatch();
             
             // ======== RENDERING ========
             {
                 self.last_draw_call = Some(draw_calls.clone());
 
                 self.artist.queue_calls(draw_calls.clone());
-                self.artist.draw_background(LIGHTGRAY);
+                self.artist.draw_background(self.map.get_background());
                 self.artist.draw();
             }
             {            
@@ -535,7 +625,7 @@ impl GameManager{
             }
             // {   
             //     if let Ok(controller) = self.uicontroller.lock(){
-            //         controller.draw_root_ui().await;
+            //         controller.draw_root_ui();
             //     }
             // }
 
@@ -623,6 +713,45 @@ impl GameManager{
         next_frame().await
     }
 
+    /*
+        Runs a single frame of the map editor. 
+        Edits are kept in memory until saved, play-testing starts a game on the edited map.
+    */
+    async fn update_editor(&mut self){
+        let maps = self.maps.clone();
+        let editor = self.editor.get_or_insert_with(|| Editor::new(maps));
+
+        let action = editor.update(get_frame_time());
+
+        set_camera(editor.get_camera());
+        self.artist.queue_calls(editor.get_draw_calls());
+        self.artist.draw_background(editor.get_map().get_background());
+        self.artist.draw();
+
+        set_default_camera();
+        editor.draw_overlay();
+
+        match action{
+            EditorAction::PlayTest => {
+                let map = editor.get_map().clone();
+                self.maps = editor.get_maps().clone();
+                self.editor = None;
+
+                self.load_map(map);
+                self.player_name = "EDITOR".to_string();
+                self.state = GameState::Playing;
+            },
+            EditorAction::Exit => {
+                self.maps = editor.get_maps().clone();
+                self.editor = None;
+                self.state = GameState::MainMenu;
+            },
+            EditorAction::None => {},
+        }
+
+        next_frame().await;
+    }
+
     async fn update_menu(&mut self){
         clear_background(WHITE);
 
@@ -648,6 +777,12 @@ impl GameManager{
 
                 ui.separator();
 
+                if ui.button(vec2(hwidth - 150.0, hheight - 300.0),  "Map Editor") {
+                    self.state = GameState::Editor;
+                }
+
+                ui.separator();
+
                 if ui.button(vec2(hwidth - 75.0, hheight), "Exit") {
                     self.state = GameState::Quit
                 }
@@ -725,8 +860,45 @@ impl GameManager{
         next_frame().await;
     }
 
+    /*
+        Builds the world from a map file. The `Wall` is resized, the `Grid`, obstacles, flow field
+        and spawn regions are replaced, and the player is moved to the maps spawn point.
+    */
+    fn load_map(&mut self, map: MapLayout){
+        self.wall.set_bounds(map.get_bounds());
+
+        if let Ok(mut grid) = self.grid.lock(){
+            *grid = Grid::new(
+                map.grid_size, 
+                map.cell_size, 
+                Global::get_hash_cell_size(),
+                Global::get_cell_capacity(), 
+                self.dispatcher.create_sender()
+            );
+            grid.set_colors(map.get_grid_colors());
+        }
+
+        //Obstacles and hazards register in the new grid, once the events are dispatched.
+        self.arena = Arena::new(&map.obstacles, &map.hazards, self.dispatcher.create_sender());
+
+        if let Ok(mut handler) = self.handler.lock(){
+            handler.set_map_size(map.grid_size, map.cell_size);
+        }
+
+        if let Ok(mut factory) = self.factory.lock(){
+            factory.set_spawn_area(map.get_bounds(), map.get_spawn_regions());
+        }
+
+        if let Ok(mut player) = self.player.lock(){
+            player.set_spawn(map.get_player_spawn());
+        }
+
+        self.map = map;
+    }
+
     async fn new_game(&mut self){
         let mut input = String::new();
+        let mut selected = 0;
 
         loop {
             clear_background(BLACK);
@@ -744,10 +916,19 @@ impl GameManager{
                     KeyCode::Backspace => {
                         input.pop();
                     },
+                    //Map select
+                    KeyCode::Left => {
+                        selected = (selected + self.maps.len() - 1) % self.maps.len();
+                    },
+                    KeyCode::Right => {
+                        selected = (selected + 1) % self.maps.len();
+                    },
                     KeyCode::Enter => {
                         self.player_name = input;
                         self.state = GameState::Playing;
 
+                        self.load_map(self.maps[selected].clone());
+
                         return;
                     },
                     // Limit to character keys
@@ -774,6 +955,15 @@ impl GameManager{
                 ..Default::default()
             });
 
+            draw_text("Map:", pos.x, pos.y + 50.0, 30.0, WHITE);
+            draw_text_ex(&format!("< {} >", self.maps[selected].name), pos.x + 100.0, pos.y + 50.0, TextParams{
+                font_size: 30.0 as u16,
+                color: RED,
+                ..Default::default()
+            });
+
+            let map_name = self.maps[selected].name.clone();
+
             widgets::Window::new(
             hash!(),
             vec2(0.0, 0.0),
@@ -786,6 +976,7 @@ impl GameManager{
                     ui.separator();
 
                     ui.label(vec2(hwidth - 300.0, hheight - 150.0),  format!("Enter name: {:?}", &input).as_str());
+                    ui.label(vec2(hwidth - 300.0, hheight - 90.0),  format!("Map: {} (Left/Right)", &map_name).as_str());
                     
                     if is_key_down(KeyCode::Enter){
                         self.player_name = input.clone();
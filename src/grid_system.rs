@@ -1,3 +1,9 @@
 
 pub mod grid;
-pub mod wall;
\ No newline at end of file
+pub mod flow_field;
+pub mod wall;
+pub mod obstacle;
+pub mod hazard;
+pub mod arena;
+pub mod raycast;
+pub mod map;
\ No newline at end of file
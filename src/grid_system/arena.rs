@@ -0,0 +1,213 @@
+use std::{collections::HashMap, sync::mpsc::Sender};
+
+use macroquad::math::{Rect, Vec2};
+
+use crate::{audio_system::audio_handler::{SoundRequest, SoundType}, collision_system::{collider::Collider, layer::CollisionLayer}, entity_handler::projectile_store::ProjectileRef, event_system::{event::{Event, EventType}, interface::Publisher}, objects::bullet::ProjectileType, renderer::artist::DrawCall, utils::{machine::StateType}};
+
+use super::{grid::EntityType, hazard::{Hazard, HazardKind, HazardLayout}, obstacle::{Obstacle, ObstacleLayout}, wall::{WallImpact, WALL_ID}};
+
+//Fixed ids. Allocated ids carry a generation in their upper half, so they never reach these.
+const OBSTACLE_ID_START: u64 = 8193;
+const HAZARD_ID_START: u64 = 12289;
+
+/*
+    Holds the static obstacles and hazards of the map.
+
+    Both are registered once in the `Grid`, and collisions against them
+    are resolved with the entities the `Grid` reports as nearby.
+*/
+pub struct Arena{
+    obstacles: HashMap<u64, Obstacle>,
+    hazards: HashMap<u64, Hazard>,
+    player_hazards: Vec<HazardKind>,
+    sender: Sender<Event>,
+    emitter_calls: Vec<(u64, StateType, Vec2)>,
+    has_changed: bool
+}
+
+impl Arena{
+    ///Builds the obstacles and hazards of a map, and registers them in the `Grid`.
+    pub fn new(obstacles: &[ObstacleLayout], hazards: &[HazardLayout], sender: Sender<Event>) -> Arena{
+        let mut arena = Arena{
+            obstacles: HashMap::new(),
+            hazards: HashMap::new(),
+            player_hazards: Vec::new(),
+            sender: sender,
+            emitter_calls: Vec::new(),
+            has_changed: true
+        };
+
+        for (index, entry) in obstacles.iter().enumerate(){
+            let obstacle = Obstacle::new(OBSTACLE_ID_START + index as u64, entry);
+
+            arena.publish(Event::new((obstacle.get_id(), EntityType::Obstacle, CollisionLayer::Wall, obstacle.get_pos(), obstacle.get_radius()), EventType::InsertOrUpdateToGrid));
+            arena.obstacles.insert(obstacle.get_id(), obstacle);
+        }
+
+        for (index, entry) in hazards.iter().enumerate(){
+            let hazard = Hazard::new(HAZARD_ID_START + index as u64, entry);
+
+            arena.publish(Event::new((hazard.get_id(), EntityType::Hazard, CollisionLayer::Hazard, hazard.get_center(), hazard.get_radius()), EventType::InsertOrUpdateToGrid));
+            arena.hazards.insert(hazard.get_id(), hazard);
+        }
+
+        return arena
+    }
+
+    /*
+        Pushes the player out of the obstacles in `ids` and publishes the impact.
+        Impacts behave like the `Wall`, scrapes slide and hard hits cost health.
+
+        Params: Players position, size and velocity.
+    */
+    pub fn collide_player(&mut self, ids: &[u64], params: (Vec2, f32, Vec2)){
+        let (pos, size, velocity) = params;
+
+        if let Some((normal, correction)) = self.get_correction(ids, pos, size){
+            let impact = WallImpact{
+                normal: normal,
+                correction: correction,
+                contact: pos - normal * size,
+                speed: (-velocity.dot(normal)).max(0.0)
+            };
+
+            if !impact.is_scrape(){
+                self.emitter_calls.push((WALL_ID, StateType::Hit, impact.contact));
+            }
+
+            self.publish(Event::new(impact, EventType::PlayerHit));
+        }
+    }
+
+    /*
+        Finds the hazards in `ids` the player stands in, and notifies the player when they change.
+        Overlapping hazards of the same kind count once.
+    */
+    pub fn apply_hazards(&mut self, ids: &[u64], pos: Vec2){
+        let mut kinds: Vec<HazardKind> = ids.iter()
+            .filter_map(|id| self.hazards.get(id))
+            .filter(|hazard| hazard.contains(pos))
+            .map(|hazard| hazard.get_kind())
+            .collect();
+
+        kinds.sort();
+        kinds.dedup();
+
+        if kinds != self.player_hazards{
+            self.player_hazards = kinds.clone();
+            self.publish(Event::new(kinds, EventType::PlayerOnHazards));
+        }
+    }
+
+    /*
+        Returns the averaged outward normal and the summed displacement,
+        that push the circle at `center` out of the obstacles in `ids`.
+    */
+    pub fn get_correction(&self, ids: &[u64], center: Vec2, radius: f32) -> Option<(Vec2, Vec2)>{
+        let mut normal = Vec2::ZERO;
+        let mut correction = Vec2::ZERO;
+
+        for id in ids{
+            if let Some(obstacle) = self.obstacles.get(id){
+                if let Some((obstacle_normal, depth)) = obstacle.get_penetration(center, radius){
+                    normal += obstacle_normal;
+                    correction += obstacle_normal * depth;
+                }
+            }
+        }
+
+        if normal == Vec2::ZERO{
+            return None
+        }
+        return Some((normal.normalize_or_zero(), correction))
+    }
+
+    ///Stops projectiles hitting obstacles in `ids`. Player bullets break crates.
+    pub fn collide_projectile(&mut self, projectile: ProjectileRef, ids: &[u64]){
+        let hit = ids.iter()
+            .copied()
+            .find(|id| self.obstacles.get(id)
+                .is_some_and(|obstacle| obstacle.get_collider().collides_with(projectile.collider)));
+
+        let id = match hit{
+            Some(id) => id,
+            None => return,
+        };
+
+        self.emitter_calls.push((WALL_ID, StateType::Hit, projectile.pos));
+
+        match projectile.ptype{
+            ProjectileType::Player => self.publish(Event::new(projectile.id, EventType::PlayerBulletHit)),
+            _ => {
+                self.publish(Event::new(projectile.id, EventType::EnemyBulletHit));
+                return
+            },
+        }
+
+        let is_destroyed = self.obstacles.get_mut(&id)
+            .is_some_and(|obstacle| obstacle.hit());
+
+        if is_destroyed{
+            self.obstacles.remove(&id);
+            self.has_changed = true;
+
+            self.publish(Event::new(id, EventType::RemoveEntityFromGrid));
+
+            let srequest = SoundRequest::new(true, false, 0.1);
+            self.publish(Event::new((SoundType::EnemyDeath, srequest), EventType::PlaySound));
+        }
+    }
+
+    ///Collider of an obstacle, for raycasts that obstacles stop.
+    #[inline(always)]
+    pub fn get_obstacle_collider(&self, id: u64) -> Option<&dyn Collider>{
+        return self.obstacles.get(&id).map(|obstacle| obstacle.get_collider())
+    }
+
+    ///Returns the areas covered by obstacles, if they changed since the last call.
+    pub fn take_blocked_areas(&mut self) -> Option<Vec<Rect>>{
+        if !self.has_changed{
+            return None
+        }
+
+        self.has_changed = false;
+
+        return Some(self.obstacles.values()
+            .map(|obstacle| {
+                let (min, max) = obstacle.get_bounds();
+                Rect::new(min.x, min.y, max.x - min.x, max.y - min.y)
+            })
+            .collect())
+    }
+
+    ///Returns spark emission calls of this frame.
+    #[inline(always)]
+    pub fn get_emitter_calls(&mut self) -> Vec<(u64, StateType, Vec2)>{
+        return std::mem::take(&mut self.emitter_calls)
+    }
+
+    ///Obstacles are drawn over the wall, hazards on the background above the grid.
+    #[inline(always)]
+    pub fn get_draw_calls(&self, viewport: Rect) -> Vec<(i32, DrawCall)>{
+        let hazards = self.hazards.values()
+            .filter(|hazard| viewport.overlaps(&hazard.get_area()))
+            .flat_map(|hazard| hazard.get_draw_calls())
+            .map(|call| (2, call));
+
+        return self.obstacles.values()
+            .filter(|obstacle| {
+                let (min, max) = obstacle.get_bounds();
+                viewport.overlaps(&Rect::new(min.x, min.y, max.x - min.x, max.y - min.y))
+            })
+            .flat_map(|obstacle| obstacle.get_draw_calls())
+            .map(|call| (3, call))
+            .chain(hazards)
+            .collect()
+    }
+}
+
+impl Publisher for Arena{
+    fn publish(&self, event: Event){
+        let _ = self.sender.send(event);
+    }
+}
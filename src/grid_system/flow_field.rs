@@ -0,0 +1,213 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use macroquad::math::{vec2, Rect, Vec2};
+
+use crate::utils::globals::Global;
+
+//Integer movement costs, so the open set can be ordered. Diagonals are ~sqrt(2) times the straight cost.
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+//Extra cost of nodes covered by enemies, so chasers spread around each other.
+const OCCUPIED_COST: u32 = 3;
+//Nodes that can't be traversed.
+const BLOCKED: u32 = u32::MAX;
+
+const NEIGHBOURS: [(i32, i32); 8] = [
+    (-1, 0), (1, 0), (0, -1), (0, 1),
+    (-1, -1), (1, -1), (-1, 1), (1, 1)
+];
+
+/*
+    Flow field towards a single goal, over a coarse grid that subdivides each `Grid` cell.
+
+    Each node points to its cheapest neighbour, found with Dijkstra starting from the goal.
+    Enemies sample it with their position, to get the direction that routes them around
+    anything costly on the way to the player.
+*/
+#[derive(Clone)]
+pub struct FlowField{
+    resolution: f32,
+    width: i32,
+    costs: Vec<u32>,
+    directions: Vec<Vec2>,
+    goal: Option<(i32, i32)>
+}
+
+impl FlowField{
+    pub fn new(grid_size: i32, cell_size: i32) -> FlowField{
+        let resolution = (cell_size / Global::get_flow_field_subdivisions()) as f32;
+        let width = grid_size * Global::get_flow_field_subdivisions();
+        let nodes = (width * width) as usize;
+
+        return FlowField{
+            resolution: resolution,
+            width: width,
+            costs: vec![1; nodes],
+            directions: vec![Vec2::ZERO; nodes],
+            goal: None
+        }
+    }
+
+    ///Blocks every node overlapped by `areas`, and unblocks the rest.
+    pub fn set_blocked(&mut self, areas: &[Rect]){
+        for index in 0..self.costs.len(){
+            let node = self.index_to_node(index);
+            let bounds = Rect::new(
+                node.0 as f32 * self.resolution, 
+                node.1 as f32 * self.resolution, 
+                self.resolution, 
+                self.resolution
+            );
+
+            self.costs[index] = match areas.iter().any(|area| area.overlaps(&bounds)){
+                true => BLOCKED,
+                false => 1,
+            };
+        }
+    }
+
+    ///Resets the cost of every traversable node.
+    pub fn clear_costs(&mut self){
+        self.costs.iter_mut()
+            .filter(|cost| **cost != BLOCKED)
+            .for_each(|cost| *cost = 1);
+    }
+
+    ///Marks every node covered by the circle at `center` as occupied.
+    pub fn add_cost(&mut self, center: Vec2, radius: f32){
+        let (min_x, min_y) = self.world_to_node(center - radius);
+        let (max_x, max_y) = self.world_to_node(center + radius);
+
+        for x in min_x..=max_x{
+            for y in min_y..=max_y{
+                if let Some(index) = self.get_index((x, y)){
+                    if self.costs[index] != BLOCKED{
+                        self.costs[index] += OCCUPIED_COST;
+                    }
+                }
+            }
+        }
+    }
+
+    ///Runs Dijkstra from the node that `goal` belongs to, and points every node towards its cheapest neighbour.
+    pub fn compute(&mut self, goal: Vec2){
+        let goal_node = self.world_to_node(goal);
+        let nodes = self.costs.len();
+
+        let mut distances = vec![u32::MAX; nodes];
+        let mut open = BinaryHeap::new();
+
+        self.goal = None;
+        self.directions.iter_mut().for_each(|direction| *direction = Vec2::ZERO);
+
+        let goal_index = match self.get_index(goal_node){
+            Some(index) => index,
+            None => return,
+        };
+
+        self.goal = Some(goal_node);
+        distances[goal_index] = 0;
+        open.push(Reverse((0, goal_index)));
+
+        while let Some(Reverse((distance, index))) = open.pop(){
+            if distance > distances[index]{
+                continue;
+            }
+
+            let node = self.index_to_node(index);
+
+            for (dx, dy) in NEIGHBOURS{
+                let next = match self.get_index((node.0 + dx, node.1 + dy)){
+                    Some(next) => next,
+                    None => continue,
+                };
+
+                if self.costs[next] == BLOCKED || self.cuts_corner(node, (dx, dy)){
+                    continue;
+                }
+
+                let step = match dx != 0 && dy != 0{
+                    true => DIAGONAL_COST,
+                    false => STRAIGHT_COST,
+                };
+                let next_distance = distance + step * self.costs[next];
+
+                if next_distance < distances[next]{
+                    distances[next] = next_distance;
+                    open.push(Reverse((next_distance, next)));
+                }
+            }
+        }
+
+        //Point each node towards the neighbour closest to the goal.
+        for index in 0..nodes{
+            if distances[index] == u32::MAX || index == goal_index{
+                continue;
+            }
+
+            let node = self.index_to_node(index);
+            let mut best = (distances[index], None);
+
+            for (dx, dy) in NEIGHBOURS{
+                if let Some(next) = self.get_index((node.0 + dx, node.1 + dy)){
+                    if distances[next] < best.0 && !self.cuts_corner(node, (dx, dy)){
+                        best = (distances[next], Some((dx, dy)));
+                    }
+                }
+            }
+
+            if let Some((dx, dy)) = best.1{
+                self.directions[index] = vec2(dx as f32, dy as f32).normalize();
+            }
+        }
+    }
+
+    /*
+        Direction to follow from `pos`.
+        Returns None next to the goal, or outside the field, where enemies should head straight for their target.
+    */
+    pub fn sample(&self, pos: Vec2) -> Option<Vec2>{
+        let node = self.world_to_node(pos);
+        let goal = self.goal?;
+
+        if (node.0 - goal.0).abs() <= 1 && (node.1 - goal.1).abs() <= 1{
+            return None
+        }
+
+        return self.get_index(node)
+            .map(|index| self.directions[index])
+            .filter(|direction| *direction != Vec2::ZERO)
+    }
+
+    ///Diagonal moves are not allowed to squeeze between two blocked nodes.
+    #[inline(always)]
+    fn cuts_corner(&self, node: (i32, i32), (dx, dy): (i32, i32)) -> bool{
+        if dx == 0 || dy == 0{
+            return false
+        }
+
+        let is_blocked = |pos: (i32, i32)| {
+            self.get_index(pos).map_or(true, |index| self.costs[index] == BLOCKED)
+        };
+
+        return is_blocked((node.0 + dx, node.1)) || is_blocked((node.0, node.1 + dy))
+    }
+
+    #[inline(always)]
+    fn world_to_node(&self, pos: Vec2) -> (i32, i32){
+        return ((pos.x / self.resolution).floor() as i32, (pos.y / self.resolution).floor() as i32)
+    }
+
+    #[inline(always)]
+    fn index_to_node(&self, index: usize) -> (i32, i32){
+        return (index as i32 % self.width, index as i32 / self.width)
+    }
+
+    #[inline(always)]
+    fn get_index(&self, node: (i32, i32)) -> Option<usize>{
+        if node.0 < 0 || node.1 < 0 || node.0 >= self.width || node.1 >= self.width{
+            return None
+        }
+        return Some((node.1 * self.width + node.0) as usize)
+    }
+}
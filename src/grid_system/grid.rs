@@ -1,18 +1,21 @@
 use std::{collections::{HashMap, HashSet}, sync::mpsc::Sender};
 
-use async_trait::async_trait;
 use macroquad::{color::{Color, DARKGRAY}, math::{Rect, Vec2}, time::get_time};
 
-use crate::{event_system::{event::{Event, EventType}, interface::{Publisher, Subscriber}}, renderer::artist::DrawCall, utils::timer::SimpleTimer};
+use crate::{collision_system::{collider::{CapsuleCollider, Collider}, layer::CollisionLayer}, event_system::{event::{Event, EventType}, interface::{Publisher, Subscriber}}, renderer::artist::DrawCall, utils::timer::SimpleTimer};
+
+use super::raycast::RayHit;
 
 type EntityId = u64;
 type CellPos = (i32, i32);
 
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum EntityType{
     Enemy,
-    Projectile
+    Projectile,
+    Obstacle,
+    Hazard
 }
 
 ///Entity represents the minimal information about an entity present in the game.
@@ -46,68 +49,119 @@ impl Cell{
         }
     }
  
+    /*
+        The set grows on its own, `capacity` only tracks how much the cell keeps
+        reserved after a cleanup. It doubles whenever the cell holds more entities than that.
+    */
     #[inline(always)]
     fn insert(&mut self, entity_type: EntityType, id: u64){
-        match self.entities.len() <= self.entities.capacity(){
-            true => {
-                let entity = Entity::new(entity_type, id);
+        self.entities.insert(Entity::new(entity_type, id));
 
-                if !self.entities.contains(&entity){
-                    self.entities.insert(entity);
-                }
-            },
-            //If maximum length is exceeded, double the capacity.
-            false => {
-                self.entities.reserve(self.capacity);
-                self.capacity *= 2;
-            },
+        if self.entities.len() > self.capacity{
+            self.capacity = (self.capacity * 2).max(self.entities.len());
         }
     }
+
+    #[inline(always)]
+    fn remove(&mut self, entity_type: EntityType, id: u64){
+        self.entities.remove(&Entity::new(entity_type, id));
+    }
+}
+
+///Inclusive range of cells an entity overlaps.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct CellRange{
+    min: CellPos,
+    max: CellPos
+}
+
+impl CellRange{
+    #[inline(always)]
+    fn contains(&self, cell: CellPos) -> bool{
+        return cell.0 >= self.min.0 && cell.0 <= self.max.0 
+            && cell.1 >= self.min.1 && cell.1 <= self.max.1
+    }
+
+    #[inline(always)]
+    fn cells(&self) -> impl Iterator<Item = CellPos>{
+        let (min, max) = (self.min, self.max);
+
+        return (min.0..=max.0).flat_map(move |x| (min.1..=max.1).map(move |y| (x, y)))
+    }
+}
+
+///Snapshot of the `Grid` occupancy.
+#[derive(Clone, Copy, Default)]
+pub struct GridStats{
+    pub entities: usize,
+    pub cells: usize,
+    //Entity entries summed over all cells, an entity counts once per cell it overlaps
+    pub entries: usize,
+    pub max_per_cell: usize,
+    pub avg_per_cell: f32,
+    //Approximate bytes reserved by the tables and cells
+    pub memory: usize
 }
 
 
 //Represents an operation to queue
 #[derive(Clone)]
 enum GridOperation{
-    Update(EntityId, EntityType, Vec2, f32),
+    Update(EntityId, EntityType, CollisionLayer, Vec2, f32),
     Remove(EntityId)
 }
 
 
 const CLEANUP: f64 = 10.0;
+//Steps used to narrow down where a ray enters a collider.
+const RAY_REFINE_STEPS: u32 = 10;
+
+/*
+    Spatial hash that keeps track of entities by having entries in a hashmap and which cell they belong to.
+    Each cell position has a cell that holds a set of entities. Where entity is entity_type and id.
+
+    Cells are created when first occupied, and their size is independent of the map cells,
+    which are only drawn. Range queries return entities sorted by distance.
 
-///Grid that keeps track of entities by having entries in a hashmap and which cell they belong to.
-/// Each cell position has a cell that holds a vec of entities. Where entity is entity_type and id.
+    Every entity is tracked with the exact range of cells it overlaps. Moves only touch
+    the cells it entered or left, and removal clears every cell it was in.
+*/
 pub struct Grid{
-    entity_table: HashMap<EntityId, CellPos>,
-    history: HashMap<EntityId, Vec<CellPos>>,
+    occupancy: HashMap<EntityId, (EntityType, CellRange)>,
+    layer_table: HashMap<EntityId, CollisionLayer>,
+    //Position and radius of the circle enclosing each entity
+    position_table: HashMap<EntityId, (Vec2, f32)>,
     cells: HashMap<CellPos, Cell>,
-    cell_size: i32,
+    cell_size: f32,
+    cell_capacity: usize,
     grid_size: i32,
+    line_spacing: i32,
+    colors: (Color, Color),
     sender: Sender<Event>,
     op_queue: Vec<GridOperation>,
     cleanup_timer: SimpleTimer
 }
 
 impl Grid{
-    pub fn new(grid_size: i32, cell_size: i32, cell_capacity: usize, sender: Sender<Event>) -> Self{
-        let mut cells = HashMap::new();
-
-        for dx in 0..grid_size{
-            for dy in 0..grid_size{
-                cells.insert((dx, dy), Cell::new(cell_capacity));
-            }
-        }
-        
+    /*
+        `grid_size` and `line_spacing` describe the map cells that get drawn,
+        `cell_size` is the size of the hash cells.
+    */
+    pub fn new(grid_size: i32, line_spacing: i32, cell_size: f32, cell_capacity: usize, sender: Sender<Event>) -> Self{
         return Grid{
-            entity_table: HashMap::new(),
-            history: HashMap::new(),
-            cells: cells,
+            occupancy: HashMap::new(),
+            layer_table: HashMap::new(),
+            position_table: HashMap::new(),
+            cells: HashMap::new(),
             cell_size: cell_size,
+            cell_capacity: cell_capacity,
             grid_size: grid_size,
+            line_spacing: line_spacing,
+            colors: (Color::from_rgba(227, 228, 225, 255), DARKGRAY),
             sender: sender,
             op_queue: Vec::new(),
-            cleanup_timer: SimpleTimer::new(CLEANUP)
+            //Set on the first update, so a grid can be built without a window
+            cleanup_timer: SimpleTimer::blank()
         }
     }
 
@@ -116,46 +170,31 @@ impl Grid{
         let now = get_time();
 
         let mut updates = Vec::new();
-        let mut to_skip = Vec::new();
-        let mut removals = Vec::new();
+        let mut removals = HashSet::new();
         
-        //Sort operation queue into two collections
+        //Sort operation queue into two collections, keeping the order of updates
         for op in self.op_queue.drain(..) {
             match op {
-                GridOperation::Update(id, etype, pos, size) => {
-                    updates.push((id, etype, pos, size));
+                GridOperation::Update(id, etype, layer, pos, size) => {
+                    updates.push((id, etype, layer, pos, size));
                 },
                 GridOperation::Remove(id) => {
-                    removals.push(id);
+                    removals.insert(id);
                 },
             }
         }
         
-        //First remove and add ids to collection
-        for id in removals {
-            self.remove_entity(id);
-            to_skip.push(id);
+        //Removals win over updates queued in the same frame
+        for id in removals.iter() {
+            self.remove_entity(*id);
         }
 
-        //Sort both lists by incrementing id
-        to_skip.sort();
-        updates.sort_by(|entry1, entry2| {
-            entry1.0.cmp(&entry2.0)
-        });
-
-        //If entity hasn't been removed, update it
-        for (id, etype, pos, size) in updates {
-            
-            //Both lists are sorted, and `to_skip` gets smaller every iteration
-            //So practically this is somehting similar to O(n*log(m))
-            if !to_skip.contains(&id){
-                self.update_entity(id, etype, pos, size);
-                to_skip.retain(|entity_id| entity_id != &id);
+        for (id, etype, layer, pos, size) in updates {
+            if !removals.contains(&id){
+                self.update_entity(id, etype, layer, pos, size);
             }
         }
 
-        drop(to_skip);
-
         if self.cleanup_timer.expired(now){
             self.cleanup();
             self.cleanup_timer.set(now, CLEANUP);
@@ -164,148 +203,323 @@ impl Grid{
         self.debug();
     }
 
-    /// Updates an entity by first checking if it present inside the grid.
-    /// Proceeds to insert it, if already present, removes old entry.
+    /*
+        Inserts an entity, or moves it if already present.
+        Only the cells the entity entered or left are touched, nothing happens if its range didn't change.
+    */
     #[inline(always)]
-    pub fn update_entity(&mut self, id: EntityId, entity_type: EntityType, pos: Vec2, size: f32) {
-        let center_cell = self.world_to_cell(pos.into());
+    pub fn update_entity(&mut self, id: EntityId, entity_type: EntityType, layer: CollisionLayer, pos: Vec2, size: f32) {
+        let range = self.get_range(pos, size);
         
-        // Check if center of entity has changed
-        if let Some(&old_center) = self.entity_table.get(&id) {
-            if old_center == center_cell {
+        //Layer can change without the entity moving (deflected bullets)
+        self.layer_table.insert(id, layer);
+        self.position_table.insert(id, (pos, size));
+
+        let previous = self.occupancy.insert(id, (entity_type, range));
+
+        if let Some((old_type, old_range)) = previous{
+            if old_type == entity_type && old_range == range{
                 return;
             }
-        }
 
-        // Determine which cells the entity overlaps
-        let min_x = ((pos.x - size) / self.cell_size as f32).floor() as i32;
-        let max_x = ((pos.x + size) / self.cell_size as f32).ceil() as i32;
-        let min_y = ((pos.y - size) / self.cell_size as f32).floor() as i32;
-        let max_y = ((pos.y + size) / self.cell_size as f32).ceil() as i32;
-
-        // Add to all overlapping cells
-        for x in min_x..=max_x {
-            for y in min_y..=max_y {
-                if let Some(cell) = self.cells.get_mut(&(x, y)) {
-                    cell.insert(entity_type, id);
+            //Cells left behind. All of them, if the id now belongs to another type of entity.
+            for cell in old_range.cells(){
+                if old_type != entity_type || !range.contains(cell){
+                    self.leave(cell, old_type, id);
                 }
             }
         }
-        
-        // Update entity table with center cell
-        self.entity_table.insert(id, center_cell);
-        //Update history with center
-        self.history.entry(id)
-            .or_default()
-            .push(center_cell);
+
+        //Cells entered
+        for cell in range.cells(){
+            let is_new = previous.map_or(true, |(old_type, old_range)| {
+                old_type != entity_type || !old_range.contains(cell)
+            });
+
+            if is_new{
+                self.cells.entry(cell)
+                    .or_insert_with(|| Cell::new(self.cell_capacity))
+                    .insert(entity_type, id);
+            }
+        }
     }
     
     
     #[inline(always)]
     pub fn remove_entity(&mut self, id: EntityId) {
-        self.entity_table.remove(&id);
+        self.layer_table.remove(&id);
+        self.position_table.remove(&id);
         
-        //If entity exists remove it from history
-        if let Some(entry) = self.history.remove(&id){
-            //Iterate all previous entries
-            for e in entry.clone(){
-                //Expand search around center by a -1, +1 radius
-                for x in -1..=1 {
-                    for y in -1..=1 {
-                        //And if the coordinates aren't in the next searches
-                        let new_pos = (e.0 + x, e.1 + y);
-                        
-                        if !entry.contains(&new_pos){
-                            //Retrieve cell and remove entity
-                            if let Some(cell) = self.cells.get_mut(&new_pos) {
-                                cell.entities.retain(|entity| entity.entity_id != id);
-                            }
-                        }
-                    }
-                }
+        if let Some((entity_type, range)) = self.occupancy.remove(&id){
+            for cell in range.cells(){
+                self.leave(cell, entity_type, id);
             }
         }
     }
 
+    ///Removes an entity from a cell. Emptied cells are kept until the next cleanup, since they are likely to be reused.
+    #[inline(always)]
+    fn leave(&mut self, cell: CellPos, entity_type: EntityType, id: EntityId){
+        if let Some(entry) = self.cells.get_mut(&cell){
+            entry.remove(entity_type, id);
+        }
+    }
 
-    ///Returns entities in the cell that `pos` belongs to.
+    ///Cells the circle at `pos` with radius `size` overlaps.
     #[inline(always)]
-    pub fn get_approximate_entities(&self, pos: Vec2) -> Option<Vec<(EntityType, EntityId)>>{
-        let cell_pos = self.world_to_cell(pos.into());
-        
-        if let Some(cell) = self.cells.get(&(cell_pos.0, cell_pos.1)){
-            return Some(cell.entities.
-                    iter()
-                    .map(|entity| (entity.entity_type.clone(), entity.entity_id))
-                    .collect::<Vec<(EntityType, EntityId)>>()
-                );
+    fn get_range(&self, pos: Vec2, size: f32) -> CellRange{
+        return CellRange{
+            min: self.world_to_cell((pos.x - size, pos.y - size)),
+            max: self.world_to_cell((pos.x + size, pos.y + size))
+        }
+    }
+
+    ///Entities per cell and approximate memory of the `Grid`.
+    pub fn get_stats(&self) -> GridStats{
+        let occupied = self.cells.values()
+            .map(|cell| cell.entities.len())
+            .filter(|len| *len > 0);
+
+        let (cells, entries, max_per_cell) = occupied.fold((0, 0, 0), |(cells, entries, max), len| {
+            (cells + 1, entries + len, max.max(len))
+        });
+
+        let tables = self.occupancy.capacity() * size_of::<(EntityId, (EntityType, CellRange))>()
+            + self.layer_table.capacity() * size_of::<(EntityId, CollisionLayer)>()
+            + self.position_table.capacity() * size_of::<(EntityId, (Vec2, f32))>()
+            + self.cells.capacity() * size_of::<(CellPos, Cell)>()
+            + self.op_queue.capacity() * size_of::<GridOperation>();
+
+        let cell_sets = self.cells.values()
+            .map(|cell| cell.entities.capacity() * size_of::<Entity>())
+            .sum::<usize>();
+
+        return GridStats{
+            entities: self.occupancy.len(),
+            cells: cells,
+            entries: entries,
+            max_per_cell: max_per_cell,
+            avg_per_cell: if cells > 0 { entries as f32 / cells as f32 } else { 0.0 },
+            memory: tables + cell_sets
+        }
+    }
+
+
+    ///Returns entities whose bounds lie within `radius` of `center`, sorted by distance.
+    pub fn query_radius(&self, center: Vec2, radius: f32, layer: CollisionLayer) -> Vec<(EntityType, EntityId)>{
+        let area = Rect::new(center.x - radius, center.y - radius, radius * 2.0, radius * 2.0);
+
+        let found = self.get_candidates(&self.cells_in_rect(area), layer)
+            .into_iter()
+            .filter(|(_, _, pos, size)| pos.distance(center) - size <= radius)
+            .map(|(etype, id, pos, _)| (pos.distance_squared(center), etype, id))
+            .collect();
+
+        return Grid::sort_by_distance(found)
+    }
+
+    ///Returns entities whose bounds overlap `area`, sorted by distance from its center.
+    pub fn query_rect(&self, area: Rect, layer: CollisionLayer) -> Vec<(EntityType, EntityId)>{
+        let center = area.center();
+        let (min, max) = (area.point(), area.point() + area.size());
+
+        let found = self.get_candidates(&self.cells_in_rect(area), layer)
+            .into_iter()
+            .filter(|(_, _, pos, size)| pos.clamp(min, max).distance(*pos) <= *size)
+            .map(|(etype, id, pos, _)| (pos.distance_squared(center), etype, id))
+            .collect();
+
+        return Grid::sort_by_distance(found)
+    }
+
+    /*
+        Casts a ray of `thickness` radius, and returns what it hits within `length`, ordered by distance.
+
+        Cells are walked with a DDA, and the entities in them are tested against `lookup`.
+        Entities `lookup` returns no collider for are skipped, so callers choose what can be hit.
+    */
+    pub fn raycast<'a, F>(&self, origin: Vec2, direction: Vec2, length: f32, thickness: f32, layer: CollisionLayer, lookup: F) -> Vec<RayHit>
+    where F: Fn(EntityType, EntityId) -> Option<&'a dyn Collider>{
+        let direction = direction.normalize_or_zero();
+
+        if direction == Vec2::ZERO || length <= 0.0{
+            return Vec::new()
+        }
+
+        let end = origin + direction * length;
+        let ray = CapsuleCollider::new(origin, end, thickness, layer);
+        let cells = self.traverse(origin, direction, length, thickness);
+
+        let mut hits = self.get_candidates(&cells, layer)
+            .into_iter()
+            .filter_map(|(etype, id, pos, size)| {
+                //Closest approach of the ray to the entities bounding circle
+                let along = (pos - origin).dot(direction).clamp(0.0, length);
+                let offset = (origin + direction * along).distance(pos);
+                let reach = size + thickness;
+
+                if offset > reach{
+                    return None
+                }
+
+                let collider = lookup(etype, id)?;
+
+                if !ray.collides_with(collider){
+                    return None
+                }
+
+                //The bounding circle is entered first, the collider is somewhere before the ray ends
+                let mut low = (along - (reach * reach - offset * offset).sqrt()).max(0.0);
+                let mut high = length;
+
+                for _ in 0..RAY_REFINE_STEPS{
+                    let mid = (low + high) / 2.0;
+
+                    match CapsuleCollider::new(origin, origin + direction * mid, thickness, layer).collides_with(collider){
+                        true => high = mid,
+                        false => low = mid,
+                    }
+                }
+
+                Some(RayHit{
+                    entity_type: etype,
+                    id: id,
+                    distance: high
+                })
+            })
+            .collect::<Vec<RayHit>>();
+
+        hits.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+
+        return hits
+    }
+
+    /*
+        Walks the cells the ray passes through in order (Amanatides & Woo).
+        Thick rays also take the cells around the path, that their radius reaches into.
+    */
+    fn traverse(&self, origin: Vec2, direction: Vec2, length: f32, thickness: f32) -> Vec<CellPos>{
+        let (mut x, mut y) = self.world_to_cell((origin.x, origin.y));
+        let (end_x, end_y) = self.world_to_cell((origin.x + direction.x * length, origin.y + direction.y * length));
+
+        let step_x = direction.x.signum() as i32;
+        let step_y = direction.y.signum() as i32;
+
+        //Distance along the ray to the first cell boundary, and between boundaries, on each axis
+        let boundary = |cell: i32, step: i32, start: f32, dir: f32| -> (f32, f32){
+            if dir == 0.0{
+                return (f32::INFINITY, f32::INFINITY)
+            }
+
+            let edge = (cell + (step > 0) as i32) as f32 * self.cell_size;
+            return ((edge - start) / dir, self.cell_size / dir.abs())
+        };
+
+        let (mut max_x, delta_x) = boundary(x, step_x, origin.x, direction.x);
+        let (mut max_y, delta_y) = boundary(y, step_y, origin.y, direction.y);
+
+        let reach = (thickness / self.cell_size).ceil() as i32;
+        let limit = (end_x - x).abs() + (end_y - y).abs() + 1;
+
+        let mut seen = HashSet::new();
+        let mut cells = Vec::new();
+
+        for _ in 0..=limit{
+            for offset_x in -reach..=reach{
+                for offset_y in -reach..=reach{
+                    let cell = (x + offset_x, y + offset_y);
+
+                    if seen.insert(cell){
+                        cells.push(cell);
+                    }
+                }
+            }
+
+            if (x, y) == (end_x, end_y){
+                break;
+            }
+
+            if max_x < max_y{
+                max_x += delta_x;
+                x += step_x;
+            }
+            else{
+                max_y += delta_y;
+                y += step_y;
+            }
         }
-        // else{
-        //     eprintln!("|Grid|get_nearby_enemies()| Cell not found for pos {:?}", cell_pos);
-        // }
 
-        return None
+        return cells
     }
 
+    ///Cells `area` touches.
+    #[inline(always)]
+    fn cells_in_rect(&self, area: Rect) -> Vec<CellPos>{
+        let (min_x, min_y) = self.world_to_cell((area.x, area.y));
+        let (max_x, max_y) = self.world_to_cell((area.x + area.w, area.y + area.h));
 
-    ///Returns entities in the current and adjusent cells in range of -1..1.
-    pub fn get_nearby_entities(&self, pos: Vec2) -> Vec<(EntityType, EntityId)> {
-        let mut entities: Vec<(EntityType, EntityId)> = Vec::new();
+        return (min_x..=max_x)
+            .flat_map(|x| (min_y..=max_y).map(move |y| (x, y)))
+            .collect()
+    }
 
-        let cell_pos = self.world_to_cell(pos.into());
+    /*
+        Collects entities from `cells`, that the collision matrix allows `layer` to collide with.
+        Entities spanning several cells are returned once, with their position and radius.
+    */
+    fn get_candidates(&self, cells: &[CellPos], layer: CollisionLayer) -> Vec<(EntityType, EntityId, Vec2, f32)>{
+        let mut seen = HashSet::new();
+        let mut candidates = Vec::new();
+
+        for pos in cells{
+            if let Some(cell) = self.cells.get(pos){
+                for entity in cell.entities.iter(){
+                    if !seen.insert(entity.entity_id) || !self.interacts(layer, &entity.entity_id){
+                        continue;
+                    }
 
-        for dx in -1..=1 {
-            for dy in -1..=1 {
-                if let Some(cell) = self.cells.get(&(cell_pos.0 + dx, cell_pos.1 + dy)){
-                    entities.extend(cell.entities.iter().map(|entity| (entity.entity_type.clone(), entity.entity_id)));
+                    if let Some((pos, size)) = self.position_table.get(&entity.entity_id){
+                        candidates.push((entity.entity_type, entity.entity_id, *pos, *size));
+                    }
                 }
-                // else{
-                //     eprintln!("|Grid|get_nearby_enemies()| Cell not found for pos {:?} and offset {:?}", cell_pos, (dx, dy));
-                // }
             }
         }
-        return entities
+
+        return candidates
     }
 
+    #[inline(always)]
+    fn sort_by_distance(mut found: Vec<(f32, EntityType, EntityId)>) -> Vec<(EntityType, EntityId)>{
+        found.sort_by(|a, b| a.0.total_cmp(&b.0));
 
-    ///Returns entities in the current and adjusent cells that are of type `entity_type`.
-    pub fn get_nearby_entities_by_type(&self, pos: Vec2, entity_type: EntityType) -> Vec<EntityId> {
-        self.get_nearby_entities(pos)
-            .into_iter()
-            .filter(|(ent_t, _)| *ent_t == entity_type)
-            .map(|(_, id)| id)
+        return found.into_iter()
+            .map(|(_, etype, id)| (etype, id))
             .collect()
     }
 
+    #[inline(always)]
+    fn interacts(&self, layer: CollisionLayer, id: &EntityId) -> bool{
+        return self.layer_table
+            .get(id)
+            .map_or(false, |other| layer.interacts(*other))
+    }
+
     ///Translates a (f32, f32) pair into a cell position.
     #[inline(always)]
     fn world_to_cell(&self, coord: (f32, f32)) -> CellPos{
         let epsilon = 0.0001;
     
         // Use simple floor operation for more predictable behavior
-        let x = (coord.0 / self.cell_size as f32 + epsilon).floor() as i32;
-        let y = (coord.1 / self.cell_size as f32 + epsilon).floor() as i32;
+        let x = (coord.0 / self.cell_size + epsilon).floor() as i32;
+        let y = (coord.1 / self.cell_size + epsilon).floor() as i32;
         
         return (x, y)
     }
 
-    ///Returns a vector that represents cells
-    /// Each inner vector represents entities inside a single cell
-    /// mapped to the entities id
-    #[inline]
-    pub fn get_populated_cells(&self) -> Vec<Vec<u64>>{
-        return self.cells
-            .iter()
-            .map(|(_, cell)| cell)
-            .filter(|cell| !cell.entities.is_empty())
-            .map(|cell| {
-                cell.entities
-                    .iter()
-                    .map(|entity| &entity.entity_id)
-                    .cloned()
-                    .collect()
-            })
-            .collect()
+    ///Background and line colors.
+    #[inline(always)]
+    pub fn set_colors(&mut self, colors: (Color, Color)){
+        self.colors = colors;
     }
 
     #[inline(always)]
@@ -318,13 +532,13 @@ impl Grid{
             viewport.y, 
             viewport.w, 
             viewport.h, 
-            Color::from_rgba(227, 228, 225, 255))));
+            self.colors.0)));
         
         // Calculate visible cell range
-        let start_x = (viewport.x / self.cell_size as f32).floor() as i32;
-        let start_y = (viewport.y / self.cell_size as f32).floor() as i32;
-        let end_x = ((viewport.x + viewport.w) / self.cell_size as f32).ceil() as i32;
-        let end_y = ((viewport.y + viewport.h) / self.cell_size as f32).ceil() as i32;
+        let start_x = (viewport.x / self.line_spacing as f32).floor() as i32;
+        let start_y = (viewport.y / self.line_spacing as f32).floor() as i32;
+        let end_x = ((viewport.x + viewport.w) / self.line_spacing as f32).ceil() as i32;
+        let end_y = ((viewport.y + viewport.h) / self.line_spacing as f32).ceil() as i32;
         
         // Clamp to grid boundaries
         let start_x = start_x.max(0).min(self.grid_size);
@@ -332,7 +546,7 @@ impl Grid{
         let end_x = end_x.max(0).min(self.grid_size);
         let end_y = end_y.max(0).min(self.grid_size);
         
-        let cell_size = self.cell_size as f32;
+        let cell_size = self.line_spacing as f32;
         
         // Draw only visible vertical lines
         for x in start_x..=end_x {
@@ -342,7 +556,7 @@ impl Grid{
                 x as f32 * cell_size, 
                 viewport.y + viewport.h, 
                 1.0, 
-                DARKGRAY)));
+                self.colors.1)));
         }
 
         // Draw only visible horizontal lines
@@ -353,7 +567,7 @@ impl Grid{
                 viewport.x + viewport.w, 
                 y as f32 * cell_size, 
                 1.0, 
-                DARKGRAY)));
+                self.colors.1)));
         }
 
         return draw_calls
@@ -364,8 +578,10 @@ impl Grid{
         let debug = std::env::var("DEBUG:GRID").unwrap_or("false".to_string());
 
         if debug.eq("true"){
-            println!("SIZE| Entity table: {:?}, history: {:?}", self.entity_table.len(), self.history.len());
-            println!("CAPACITY| Entity table: {:?}, history: {:?}", self.entity_table.capacity(), self.history.capacity());
+            let stats = self.get_stats();
+
+            println!("SIZE| Entities: {:?}, cells: {:?}/{:?}, entries: {:?}", stats.entities, stats.cells, self.cells.len(), stats.entries);
+            println!("CELLS| Max per cell: {:?}, avg per cell: {:.2}, memory: {:?} bytes", stats.max_per_cell, stats.avg_per_cell, stats.memory);
         }
 
         let debug_cells = std::env::var("DEBUG:GRID_CELL").unwrap_or("false".to_string());
@@ -382,8 +598,13 @@ impl Grid{
     }
 
     fn cleanup(&mut self){
-        self.entity_table.shrink_to_fit();
-        self.history.shrink_to_fit();
+        self.occupancy.shrink_to_fit();
+        self.layer_table.shrink_to_fit();
+        self.position_table.shrink_to_fit();
+        self.op_queue.shrink_to_fit();
+
+        //Drop cells nothing occupies anymore
+        self.cells.retain(|_, cell| !cell.entities.is_empty());
 
         self.cells.iter_mut()
             .map(|(_, cell)| cell)
@@ -394,21 +615,19 @@ impl Grid{
 }
 
 
-#[async_trait]
 impl Publisher for Grid{
-    async fn publish(&self, event: Event) {
+    fn publish(&self, event: Event) {
         let _ = self.sender.send(event.clone());
     }
 }
 
-#[async_trait]
 impl Subscriber for Grid{
-    async fn notify(&mut self, event: &Event) {
+    fn notify(&mut self, event: &Event) {
         match &event.event_type{
             EventType::InsertOrUpdateToGrid => {
                 if let Ok(result) = event.data.lock(){
-                    if let Some(data) = result.downcast_ref::<(EntityId, EntityType, Vec2, f32)>(){
-                        self.op_queue.push(GridOperation::Update(data.0, data.1, data.2, data.3));
+                    if let Some(data) = result.downcast_ref::<(EntityId, EntityType, CollisionLayer, Vec2, f32)>(){
+                        self.op_queue.push(GridOperation::Update(data.0, data.1, data.2, data.3, data.4));
                     }
                 }
             },
@@ -424,4 +643,179 @@ impl Subscriber for Grid{
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests{
+    use std::sync::mpsc::channel;
+
+    use ::rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+
+    const FRAMES: usize = 10_000;
+    const DELTA: f32 = 1.0 / 60.0;
+    const MAP_SIZE: f32 = 5760.0;
+    //Entities alive at once, at most
+    const POPULATION: usize = 400;
+
+    struct Mover{
+        id: EntityId,
+        entity_type: EntityType,
+        layer: CollisionLayer,
+        pos: Vec2,
+        velocity: Vec2,
+        size: f32,
+        despawn_at: usize
+    }
+
+    fn new_grid() -> Grid{
+        let (sender, _receiver) = channel();
+
+        return Grid::new(8, 720, 240.0, 0, sender)
+    }
+
+    fn spawn(rng: &mut StdRng, id: EntityId, frame: usize) -> Mover{
+        let (entity_type, layer, size, speed) = match rng.gen_bool(0.7){
+            true => (EntityType::Projectile, CollisionLayer::PlayerBullet, rng.gen_range(4.0..12.0), rng.gen_range(400.0..1200.0)),
+            false => (EntityType::Enemy, CollisionLayer::Enemy, rng.gen_range(30.0..300.0), rng.gen_range(50.0..250.0)),
+        };
+
+        let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+
+        return Mover {
+            id: id,
+            entity_type: entity_type,
+            layer: layer,
+            pos: Vec2::new(rng.gen_range(0.0..MAP_SIZE), rng.gen_range(0.0..MAP_SIZE)),
+            velocity: Vec2::from_angle(angle) * speed,
+            size: size,
+            despawn_at: frame + rng.gen_range(30..600)
+        }
+    }
+
+    ///Entries an entity should have, one per cell its range overlaps.
+    fn expected_entries(grid: &Grid) -> usize{
+        return grid.occupancy.values()
+            .map(|(_, range)| range.cells().count())
+            .sum()
+    }
+
+    /*
+        Simulates a long session of entities moving around, leaving the map, being despawned and respawned
+        with fresh ids. Once everything is gone the `Grid` must be back to the static obstacles it started with.
+    */
+    #[test]
+    fn long_session_returns_to_baseline(){
+        let mut grid = new_grid();
+        let mut rng = StdRng::seed_from_u64(39);
+
+        //Static obstacles, present for the whole session
+        for id in 0..16{
+            let pos = Vec2::new(rng.gen_range(0.0..MAP_SIZE), rng.gen_range(0.0..MAP_SIZE));
+            grid.update_entity(1_000_000 + id, EntityType::Obstacle, CollisionLayer::Wall, pos, 200.0);
+        }
+        grid.cleanup();
+
+        let baseline = grid.get_stats();
+        let baseline_cells = grid.cells.len();
+
+        let mut movers: Vec<Mover> = Vec::new();
+        let mut next_id = 0;
+        let mut peak_entities = 0;
+
+        for frame in 0..FRAMES{
+            while movers.len() < POPULATION && rng.gen_bool(0.5){
+                movers.push(spawn(&mut rng, next_id, frame));
+                next_id += 1;
+            }
+
+            for mover in movers.iter_mut(){
+                mover.pos += mover.velocity * DELTA;
+                //Some drift well outside the map, into cells that are never used again
+                if rng.gen_bool(0.01){
+                    mover.velocity = -mover.velocity;
+                }
+                grid.update_entity(mover.id, mover.entity_type, mover.layer, mover.pos, mover.size);
+            }
+
+            movers.retain(|mover| {
+                let expired = frame >= mover.despawn_at;
+
+                if expired{
+                    grid.remove_entity(mover.id);
+                }
+                !expired
+            });
+
+            //Bullets that get deflected change layer, and ids can be reused by another type of entity
+            if let Some(mover) = movers.iter_mut().find(|mover| mover.entity_type == EntityType::Projectile){
+                mover.layer = CollisionLayer::EnemyBullet;
+            }
+
+            if frame % 600 == 0{
+                grid.cleanup();
+            }
+
+            if frame % 10 != 0{
+                continue;
+            }
+
+            let stats = grid.get_stats();
+            peak_entities = peak_entities.max(stats.entities);
+
+            assert_eq!(stats.entities, baseline.entities + movers.len(), "frame {frame}: entity count drifted");
+            assert_eq!(stats.entries, expected_entries(&grid), "frame {frame}: stale cell entries");
+            assert_eq!(grid.layer_table.len(), stats.entities);
+            assert_eq!(grid.position_table.len(), stats.entities);
+        }
+
+        assert!(peak_entities > baseline.entities + POPULATION / 2, "session never got busy");
+
+        for mover in movers.drain(..){
+            grid.remove_entity(mover.id);
+        }
+
+        let stats = grid.get_stats();
+
+        assert_eq!(stats.entities, baseline.entities);
+        assert_eq!(stats.entries, baseline.entries);
+        assert_eq!(stats.cells, baseline.cells);
+        assert_eq!(stats.max_per_cell, baseline.max_per_cell);
+
+        //Empty cells left behind are dropped by the cleanup
+        grid.cleanup();
+        assert_eq!(grid.cells.len(), baseline_cells);
+    }
+
+    #[test]
+    fn reused_id_with_another_type_leaves_no_entries(){
+        let mut grid = new_grid();
+        let baseline = grid.get_stats();
+
+        for frame in 0..5_000{
+            let pos = Vec2::new(frame as f32 * 7.0 % MAP_SIZE, frame as f32 * 3.0 % MAP_SIZE);
+
+            let (entity_type, layer) = match frame % 3 == 0{
+                true => (EntityType::Enemy, CollisionLayer::Enemy),
+                false => (EntityType::Projectile, CollisionLayer::PlayerBullet),
+            };
+
+            grid.update_entity(7, entity_type, layer, pos, 250.0);
+
+            let stats = grid.get_stats();
+            assert_eq!(stats.entities, 1);
+            assert_eq!(stats.entries, expected_entries(&grid));
+        }
+
+        grid.remove_entity(7);
+        grid.cleanup();
+
+        let stats = grid.get_stats();
+
+        assert_eq!(stats.entities, baseline.entities);
+        assert_eq!(stats.entries, baseline.entries);
+        assert_eq!(stats.cells, baseline.cells);
+        assert!(grid.cells.is_empty());
+    }
+}
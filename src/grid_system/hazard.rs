@@ -0,0 +1,122 @@
+use macroquad::{color::Color, math::{vec2, Rect, Vec2}};
+use serde::{Deserialize, Serialize};
+
+use crate::renderer::artist::DrawCall;
+
+use super::map::MapArea;
+
+//Friction multipliers of the surfaces the player drives on.
+const OIL_FRICTION: f32 = 0.25;
+const MUD_FRICTION: f32 = 3.0;
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HazardKind{
+    Oil,        //Lowers friction
+    Mud,        //Raises friction
+    BoostPad,   //Boosts the player on entry, without using a charge
+    Damage      //Costs health while inside
+}
+
+impl HazardKind{
+    ///Multiplier applied to the players friction while inside the hazard.
+    #[inline(always)]
+    pub fn get_friction(&self) -> f32{
+        return match self{
+            HazardKind::Oil => OIL_FRICTION,
+            HazardKind::Mud => MUD_FRICTION,
+            _ => 1.0,
+        }
+    }
+}
+
+///Hazard entry of a map file.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct HazardLayout{
+    pub kind: HazardKind,
+    pub area: MapArea
+}
+
+///Area of the map that affects the player while inside it.
+pub struct Hazard{
+    id: u64,
+    kind: HazardKind,
+    area: Rect
+}
+
+impl Hazard{
+    pub fn new(id: u64, layout: &HazardLayout) -> Hazard{
+        return Hazard{
+            id: id,
+            kind: layout.kind,
+            area: layout.area.to_rect()
+        }
+    }
+
+    #[inline(always)]
+    pub fn get_id(&self) -> u64{
+        return self.id
+    }
+
+    #[inline(always)]
+    pub fn get_kind(&self) -> HazardKind{
+        return self.kind
+    }
+
+    #[inline(always)]
+    pub fn get_area(&self) -> Rect{
+        return self.area
+    }
+
+    #[inline(always)]
+    pub fn get_center(&self) -> Vec2{
+        return self.area.center()
+    }
+
+    ///Radius of the circle that encloses the hazard, used for `Grid` registration.
+    #[inline(always)]
+    pub fn get_radius(&self) -> f32{
+        return vec2(self.area.w, self.area.h).length() / 2.0
+    }
+
+    #[inline(always)]
+    pub fn contains(&self, point: Vec2) -> bool{
+        return self.area.contains(point)
+    }
+
+    pub fn get_draw_calls(&self) -> Vec<DrawCall>{
+        let Rect { x, y, w, h } = self.area;
+
+        match self.kind{
+            HazardKind::Oil => {
+                return vec![
+                    DrawCall::Rectangle(x, y, w, h, Color::from_rgba(30, 25, 45, 170)),
+                    DrawCall::RectangleLines(x, y, w, h, 4.0, Color::from_rgba(90, 70, 130, 200))
+                ]
+            },
+            HazardKind::Mud => {
+                return vec![
+                    DrawCall::Rectangle(x, y, w, h, Color::from_rgba(110, 80, 45, 170)),
+                    DrawCall::RectangleLines(x, y, w, h, 4.0, Color::from_rgba(80, 55, 30, 200))
+                ]
+            },
+            HazardKind::BoostPad => {
+                let color = Color::from_rgba(0, 200, 220, 200);
+                let center = self.area.center();
+                let arrow = w.min(h) / 4.0;
+
+                return vec![
+                    DrawCall::Rectangle(x, y, w, h, Color::from_rgba(0, 200, 220, 60)),
+                    DrawCall::RectangleLines(x, y, w, h, 6.0, color),
+                    DrawCall::Line(center.x - arrow, center.y + arrow / 2.0, center.x, center.y - arrow / 2.0, 8.0, color),
+                    DrawCall::Line(center.x, center.y - arrow / 2.0, center.x + arrow, center.y + arrow / 2.0, 8.0, color)
+                ]
+            },
+            HazardKind::Damage => {
+                return vec![
+                    DrawCall::Rectangle(x, y, w, h, Color::from_rgba(220, 30, 30, 70)),
+                    DrawCall::RectangleLines(x, y, w, h, 6.0, Color::from_rgba(220, 30, 30, 200))
+                ]
+            },
+        }
+    }
+}
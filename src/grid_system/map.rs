@@ -0,0 +1,172 @@
+use std::fs;
+
+use macroquad::{color::Color, math::{vec2, Rect, Vec2}};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{globals::Global, tinkerer::TinkererError};
+
+use super::{hazard::HazardLayout, obstacle::ObstacleLayout};
+
+const MAPS_PATH: &str = "assets\\maps";
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub struct MapPoint{
+    pub x: f32,
+    pub y: f32
+}
+
+///Axis aligned area of a map file. Position is the top left corner.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub struct MapArea{
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32
+}
+
+impl MapArea{
+    #[inline(always)]
+    pub fn to_rect(&self) -> Rect{
+        return Rect::new(self.x, self.y, self.w, self.h)
+    }
+}
+
+/*
+    Map file. Describes everything about the world that is not spawned at runtime.
+
+    The map is a square of `grid_size` x `grid_size` cells, each `cell_size` wide.
+    Colors are RGBA. Without spawn regions, enemies spawn around the viewport.
+*/
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct MapLayout{
+    pub name: String,
+    pub grid_size: i32,
+    pub cell_size: i32,
+    pub background: [u8; 4],
+    pub grid_background: [u8; 4],
+    pub grid_lines: [u8; 4],
+    pub player_spawn: MapPoint,
+    #[serde(default)]
+    pub spawn_regions: Vec<MapArea>,
+    #[serde(default)]
+    pub hazards: Vec<HazardLayout>,
+    #[serde(default)]
+    pub obstacles: Vec<ObstacleLayout>
+}
+
+impl Default for MapLayout{
+    fn default() -> Self {
+        let bounds = (Global::get_grid_size() * Global::get_cell_size()) as f32;
+
+        return MapLayout{
+            name: "Open Field".to_string(),
+            grid_size: Global::get_grid_size(),
+            cell_size: Global::get_cell_size(),
+            background: [199, 199, 199, 255],
+            grid_background: [227, 228, 225, 255],
+            grid_lines: [80, 80, 80, 255],
+            player_spawn: MapPoint{ x: bounds / 2.0, y: bounds / 2.0 },
+            spawn_regions: Vec::new(),
+            hazards: Vec::new(),
+            obstacles: Vec::new()
+        }
+    }
+}
+
+impl MapLayout{
+    /*
+        Reads every map in the maps folder, sorted by name.
+        Invalid files are skipped, and the default map is used if none are found.
+    */
+    pub fn load_all() -> Vec<MapLayout>{
+        let mut maps = Vec::new();
+
+        match fs::read_dir(MAPS_PATH){
+            Ok(entries) => {
+                for entry in entries.flatten(){
+                    let path = entry.path();
+
+                    if path.extension().is_some_and(|ext| ext == "yaml"){
+                        match MapLayout::read(&path.to_string_lossy()){
+                            Ok(map) => maps.push(map),
+                            Err(err) => eprintln!("Failed loading map {:?}: {}", path, err),
+                        }
+                    }
+                }
+            },
+            Err(err) => eprintln!("Failed reading maps: {}", err),
+        }
+
+        if maps.is_empty(){
+            maps.push(MapLayout::default());
+        }
+
+        maps.sort_by(|a, b| a.name.cmp(&b.name));
+
+        return maps
+    }
+
+    pub fn read(path: &str) -> Result<MapLayout, TinkererError>{
+        let contents = fs::read_to_string(path)
+            .map_err(TinkererError::IOError)?;
+
+        let map: MapLayout = serde_yaml::from_str(&contents)
+            .map_err(TinkererError::InvalidFormat)?;
+
+        Ok(map)
+    }
+
+    pub fn write(&self) -> Result<(), TinkererError>{
+        let contents = serde_yaml::to_string(self)
+            .map_err(TinkererError::InvalidFormat)?;
+
+        fs::create_dir_all(MAPS_PATH)
+            .map_err(TinkererError::IOError)?;
+
+        fs::write(self.get_path(), contents)
+            .map_err(TinkererError::IOError)?;
+
+        Ok(())
+    }
+
+    ///Maps are saved under their name, in snake case.
+    pub fn get_path(&self) -> String{
+        let file_name = self.name
+            .trim()
+            .to_lowercase()
+            .replace(' ', "_");
+
+        return format!("{}\\{}.yaml", MAPS_PATH, file_name)
+    }
+
+    ///Side length of the map.
+    #[inline(always)]
+    pub fn get_bounds(&self) -> f32{
+        return (self.grid_size * self.cell_size) as f32
+    }
+
+    #[inline(always)]
+    pub fn get_player_spawn(&self) -> Vec2{
+        return vec2(self.player_spawn.x, self.player_spawn.y)
+    }
+
+    #[inline(always)]
+    pub fn get_spawn_regions(&self) -> Vec<Rect>{
+        return self.spawn_regions.iter().map(|area| area.to_rect()).collect()
+    }
+
+    #[inline(always)]
+    pub fn get_background(&self) -> Color{
+        return to_color(self.background)
+    }
+
+    #[inline(always)]
+    pub fn get_grid_colors(&self) -> (Color, Color){
+        return (to_color(self.grid_background), to_color(self.grid_lines))
+    }
+}
+
+#[inline(always)]
+fn to_color(rgba: [u8; 4]) -> Color{
+    return Color::from_rgba(rgba[0], rgba[1], rgba[2], rgba[3])
+}
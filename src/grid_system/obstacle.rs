@@ -0,0 +1,190 @@
+use macroquad::{color::{Color, BROWN, DARKBROWN, DARKGRAY, GRAY}, math::{vec2, Vec2}};
+use serde::{Deserialize, Serialize};
+
+use crate::{collision_system::{collider::{CircleCollider, Collider, RectCollider}, layer::CollisionLayer}, renderer::artist::DrawCall};
+
+const CRATE_HEALTH: i32 = 3;
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum ObstacleKind{
+    Pillar,     //Circle, indestructible
+    Barrier,    //Rect, indestructible
+    Crate       //Rect, destroyed by player bullets
+}
+
+///Obstacle entry of an arena layout file. Position is the center of the obstacle.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct ObstacleLayout{
+    pub kind: ObstacleKind,
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32
+}
+
+impl ObstacleLayout{
+    ///True if `point` lies inside the obstacle.
+    pub fn contains(&self, point: Vec2) -> bool{
+        let offset = point - vec2(self.x, self.y);
+
+        return match self.kind{
+            ObstacleKind::Pillar => offset.length() <= self.w / 2.0,
+            _ => offset.x.abs() <= self.w / 2.0 && offset.y.abs() <= self.h / 2.0,
+        }
+    }
+}
+
+/*
+    Static piece of the arena.
+    Obstacles sit on the `Wall` collision layer, so they block the player, enemies and bullets.
+*/
+pub struct Obstacle{
+    id: u64,
+    kind: ObstacleKind,
+    pos: Vec2,
+    size: Vec2,
+    health: i32,
+    collider: Box<dyn Collider + Send + Sync>
+}
+
+impl Obstacle{
+    pub fn new(id: u64, layout: &ObstacleLayout) -> Obstacle{
+        let pos = vec2(layout.x, layout.y);
+        let size = vec2(layout.w, layout.h);
+
+        let collider: Box<dyn Collider + Send + Sync> = match layout.kind{
+            ObstacleKind::Pillar => Box::new(CircleCollider::new(pos.x, pos.y, size.x / 2.0, CollisionLayer::Wall)),
+            _ => Box::new(RectCollider::new(pos.x - size.x / 2.0, pos.y - size.y / 2.0, size.x, size.y, CollisionLayer::Wall)),
+        };
+
+        return Obstacle{
+            id: id,
+            kind: layout.kind,
+            pos: pos,
+            size: size,
+            health: CRATE_HEALTH,
+            collider: collider
+        }
+    }
+
+    #[inline(always)]
+    pub fn get_id(&self) -> u64{
+        return self.id
+    }
+
+    #[inline(always)]
+    pub fn get_pos(&self) -> Vec2{
+        return self.pos
+    }
+
+    ///Radius of the circle that encloses the obstacle, used for `Grid` registration.
+    #[inline(always)]
+    pub fn get_radius(&self) -> f32{
+        return match self.kind{
+            ObstacleKind::Pillar => self.size.x / 2.0,
+            _ => self.size.length() / 2.0,
+        }
+    }
+
+    ///Top left and bottom right corner of the obstacle.
+    #[inline(always)]
+    pub fn get_bounds(&self) -> (Vec2, Vec2){
+        return (self.pos - self.size / 2.0, self.pos + self.size / 2.0)
+    }
+
+    pub fn get_collider(&self) -> &dyn Collider{
+        return self.collider.as_ref()
+    }
+
+    ///Applies a bullet hit. Returns true if the obstacle got destroyed.
+    pub fn hit(&mut self) -> bool{
+        if self.kind != ObstacleKind::Crate{
+            return false
+        }
+
+        self.health -= 1;
+        return self.health <= 0
+    }
+
+    /*
+        Returns the outward normal and depth, of the circle at `center` overlapping the obstacle.
+        Centers inside rects are pushed out through the closest side.
+    */
+    pub fn get_penetration(&self, center: Vec2, radius: f32) -> Option<(Vec2, f32)>{
+        match self.kind{
+            ObstacleKind::Pillar => {
+                let offset = center - self.pos;
+                let distance = offset.length();
+                let total_radius = radius + self.size.x / 2.0;
+
+                if distance >= total_radius{
+                    return None
+                }
+
+                let normal = match distance > 0.0{
+                    true => offset / distance,
+                    false => Vec2::X,
+                };
+                return Some((normal, total_radius - distance))
+            },
+            _ => {
+                let (min, max) = self.get_bounds();
+                let closest = center.clamp(min, max);
+                let offset = center - closest;
+                let distance = offset.length();
+
+                if distance > 0.0{
+                    if distance >= radius{
+                        return None
+                    }
+                    return Some((offset / distance, radius - distance))
+                }
+
+                //Center inside the rect
+                let exits = [
+                    (vec2(-1.0, 0.0), center.x - min.x),
+                    (vec2(1.0, 0.0), max.x - center.x),
+                    (vec2(0.0, -1.0), center.y - min.y),
+                    (vec2(0.0, 1.0), max.y - center.y)
+                ];
+
+                let (normal, depth) = exits.iter()
+                    .copied()
+                    .fold(exits[0], |best, exit| if exit.1 < best.1 { exit } else { best });
+
+                return Some((normal, depth + radius))
+            },
+        }
+    }
+
+    pub fn get_draw_calls(&self) -> Vec<DrawCall>{
+        let (min, _) = self.get_bounds();
+
+        match self.kind{
+            ObstacleKind::Pillar => {
+                return vec![
+                    DrawCall::Circle(self.pos.x, self.pos.y, self.size.x / 2.0, DARKGRAY),
+                    DrawCall::CircleLines(self.pos.x, self.pos.y, self.size.x / 2.0, 4.0, GRAY)
+                ]
+            },
+            ObstacleKind::Barrier => {
+                return vec![
+                    DrawCall::Rectangle(min.x, min.y, self.size.x, self.size.y, GRAY),
+                    DrawCall::RectangleLines(min.x, min.y, self.size.x, self.size.y, 4.0, DARKGRAY)
+                ]
+            },
+            ObstacleKind::Crate => {
+                //Crates fade as they get damaged
+                let alpha = 0.4 + (self.health as f32 / CRATE_HEALTH as f32) * 0.6;
+                let color = Color::new(BROWN.r, BROWN.g, BROWN.b, alpha);
+
+                return vec![
+                    DrawCall::Rectangle(min.x, min.y, self.size.x, self.size.y, color),
+                    DrawCall::RectangleLines(min.x, min.y, self.size.x, self.size.y, 4.0, DARKBROWN),
+                    DrawCall::Line(min.x, min.y, min.x + self.size.x, min.y + self.size.y, 3.0, DARKBROWN),
+                    DrawCall::Line(min.x + self.size.x, min.y, min.x, min.y + self.size.y, 3.0, DARKBROWN)
+                ]
+            },
+        }
+    }
+}
@@ -0,0 +1,9 @@
+use super::grid::EntityType;
+
+///Entity a ray passed through, and how far along the ray it was entered.
+#[derive(Debug, Clone, Copy)]
+pub struct RayHit{
+    pub entity_type: EntityType,
+    pub id: u64,
+    pub distance: f32
+}
@@ -1,55 +1,191 @@
 use std::sync::mpsc::Sender;
 
-use async_trait::async_trait;
-use macroquad::{color::RED, math::{Rect, Vec2}};
+use macroquad::{color::RED, math::{Rect, Vec2}, time::get_time};
 
-use crate::{event_system::{event::{Event, EventType}, interface::Publisher}, renderer::artist::DrawCall};
+use crate::{event_system::{event::{Event, EventType}, interface::Publisher}, renderer::{artist::DrawCall, metal::ConfigType}, utils::{machine::StateType, timer::SimpleTimer}};
 
 
+//Allocated ids start at generation 1, so they sit above 1 << 32 and never reach the fixed ids.
+pub const WALL_ID: u64 = 1;
+//Impacts slower than this are scrapes and cost no health.
+const SCRAPE_SPEED: f32 = 250.0;
+//Every additional step of impact speed above a scrape costs one more health.
+const DAMAGE_STEP: f32 = 400.0;
+const MAX_DAMAGE: i32 = 3;
+//Energy kept after bouncing off the wall.
+const RESTITUTION: f32 = 0.6;
+//Speed kept along the wall while scraping.
+const SCRAPE_FRICTION: f32 = 0.97;
+//Scrapes happen every frame, throttle the sparks they emit.
+const SPARK_INTERVAL: f64 = 0.08;
+
+/* 
+    Describes a single contact of an entity with the wall.
+
+    The normal always points inwards, towards the map, and the 
+    correction is the displacement that places the entity back inside.
+*/
+#[derive(Clone, Copy, Debug)]
+pub struct WallImpact{
+    pub normal: Vec2,
+    pub correction: Vec2,
+    pub contact: Vec2,
+    pub speed: f32
+}
+
+impl WallImpact{
+    ///Light impacts only scrape the wall.
+    pub fn is_scrape(&self) -> bool{
+        return self.speed < SCRAPE_SPEED
+    }
+
+    ///Health lost due to the impact, scaled by the impact speed.
+    pub fn get_damage(&self) -> i32{
+        if self.is_scrape(){
+            return 0
+        }
+
+        let damage = 1 + ((self.speed - SCRAPE_SPEED) / DAMAGE_STEP) as i32;
+        
+        return damage.min(MAX_DAMAGE)
+    }
+
+    /* 
+        Reflects the velocity around the wall normal.
+        Scrapes lose the normal component and slide along the wall,
+        while harder impacts bounce off based on the angle of impact.
+    */
+    pub fn reflect(&self, velocity: Vec2) -> Vec2{
+        let into_wall = velocity.dot(self.normal);
+
+        //Already moving away from the wall
+        if into_wall >= 0.0{
+            return velocity
+        }
+
+        if self.is_scrape(){
+            return (velocity - self.normal * into_wall) * SCRAPE_FRICTION
+        }
+        
+        return velocity - self.normal * into_wall * (1.0 + RESTITUTION)
+    }
+}
+
 
 pub struct Wall{
     bounds: Rect,
-    sender: Sender<Event>
+    sender: Sender<Event>,
+    spark_timer: SimpleTimer,
+    emitter_calls: Vec<(u64, StateType, Vec2)>
 }
 
 impl Wall{
     
     pub fn new(bounds: f32, sender: Sender<Event>) -> Wall{
-        return Wall{
+        let wall = Wall{
             bounds: Rect { 
                 x: 0.0, 
                 y: 0.0, 
                 w: bounds, 
                 h: bounds,
             },
-            sender: sender
+            sender: sender,
+            spark_timer: SimpleTimer::blank(),
+            emitter_calls: Vec::new()
+        };
+
+        wall.publish(Event::new((WALL_ID, vec![(StateType::Hit, ConfigType::WallSparks)]), EventType::RegisterEmitterConf));
+
+        return wall
+    }
+
+    ///Moves the wall to new bounds. The sparks emitter stays registered, so a wall is only built once.
+    pub fn set_bounds(&mut self, bounds: f32){
+        self.bounds = Rect::new(0.0, 0.0, bounds, bounds);
+        self.emitter_calls.clear();
+    }
+
+    ///Params: Players position, size and velocity.
+    pub fn update(&mut self, params: (Vec2, f32, Vec2)){
+        let (pos, size, velocity) = params;
+
+        if let Some(impact) = self.check_boundaries(pos, size, velocity){
+            let now = get_time();
+
+            if !impact.is_scrape() || self.spark_timer.expired(now){
+                self.emitter_calls.push((WALL_ID, StateType::Hit, impact.contact));
+                self.spark_timer.set(now, SPARK_INTERVAL);
+            }
+
+            let _ = self.publish(Event::new(impact, EventType::PlayerHit));
         }
     }
 
-    pub async fn update(&self, params: (Vec2, f32)){
-        if self.check_boundaries(params.0, params.1){
-            let _ = self.publish(Event::new(true, EventType::PlayerHit)).await;
+    ///Returns the displacement that places the bounding box `min`-`max` back inside the bounds.
+    #[inline(always)]
+    pub fn confine(&self, min: Vec2, max: Vec2) -> Option<Vec2>{
+        let (normal, correction) = self.get_penetration(min, max);
+
+        if normal == Vec2::ZERO{
+            return None
         }
+        return Some(correction)
     }
 
-    //Checks if entity is touching any of 4 boundaries
+    //Checks if entity is touching any of 4 boundaries. The player rect is `size` wide, so it reaches half of it around its position.
     #[inline(always)]
-    fn check_boundaries(&self, pos: Vec2, size: f32) -> bool{
-        let mut collided = false;
+    fn check_boundaries(&self, pos: Vec2, size: f32, velocity: Vec2) -> Option<WallImpact>{
+        let half = size / 2.0;
+        let (normal, correction) = self.get_penetration(pos - half, pos + half);
         
-        // Left and top boundary
-        if pos.x < 0.0 || pos.y < 0.0{
-            collided = true;
+        if normal == Vec2::ZERO{
+            return None
         }
-        // Right and bottom boundary
-        if pos.x + size > self.bounds.w || pos.y + size > self.bounds.h{
-            collided = true;
+
+        //Corners have both normals, normalize to keep impact speed consistent
+        let normal = normal.normalize();
+
+        return Some(WallImpact{
+            normal: normal,
+            correction: correction,
+            contact: pos - normal * half,
+            speed: (-velocity.dot(normal)).max(0.0)
+        })
+    }
+
+    ///Returns the sum of inward normals of the walls touched and the correction needed.
+    #[inline(always)]
+    fn get_penetration(&self, min: Vec2, max: Vec2) -> (Vec2, Vec2){
+        let mut normal = Vec2::ZERO;
+        let mut correction = Vec2::ZERO;
+        
+        // Left and right boundary
+        if min.x < self.bounds.x{
+            normal.x = 1.0;
+            correction.x = self.bounds.x - min.x;
+        }
+        else if max.x > self.bounds.x + self.bounds.w{
+            normal.x = -1.0;
+            correction.x = self.bounds.x + self.bounds.w - max.x;
+        }
+
+        // Top and bottom boundary
+        if min.y < self.bounds.y{
+            normal.y = 1.0;
+            correction.y = self.bounds.y - min.y;
+        }
+        else if max.y > self.bounds.y + self.bounds.h{
+            normal.y = -1.0;
+            correction.y = self.bounds.y + self.bounds.h - max.y;
         }
 
-        if collided {
-            return true
-        } 
-        return false
+        return (normal, correction)
+    }
+
+    ///Returns spark emission calls of this frame.
+    #[inline(always)]
+    pub fn get_emitter_calls(&mut self) -> Vec<(u64, StateType, Vec2)>{
+        return std::mem::take(&mut self.emitter_calls)
     }
 
     #[inline(always)]
@@ -96,9 +232,8 @@ impl Wall{
     }
 }
 
-#[async_trait]
 impl Publisher for Wall{
-    async fn publish(&self, event: Event){
+    fn publish(&self, event: Event){
         let _ = self.sender.send(event);
     }
 }
\ No newline at end of file
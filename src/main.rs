@@ -14,14 +14,21 @@ mod input_handler;
 use std::{env, process::{exit, Command}};
 use macroquad::{miniquad::conf::Platform, prelude::*};
 use game_manager::GameManager;
+#[cfg(not(test))]
 use mimalloc::MiMalloc;
 
 use crate::utils::tinkerer::{Tinkerer, WindowConf};
 
-//Mimalloc is used because heap allocation is very frequent due to futures and Box-es
+//Mimalloc is used because heap allocation is very frequent due to events and Box-es
+#[cfg(not(test))]
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
 
+//Tests count the allocations on top of it, for the harness
+#[cfg(test)]
+#[global_allocator]
+static GLOBAL: utils::harness::CountingAllocator = utils::harness::CountingAllocator;
+
 pub fn window_conf() -> Conf{
     let mut windowconf = WindowConf::default();
 
@@ -94,5 +101,6 @@ enum StatusCode{
     Paused, 
     Playing,
     NewGame,
-    Settings
+    Settings,
+    Editor
 }
@@ -1,2 +1,4 @@
 pub mod bullet;
-pub mod shield;
\ No newline at end of file
+pub mod shield;
+pub mod laser;
+pub mod bubble;
\ No newline at end of file
@@ -0,0 +1,80 @@
+use macroquad::{color::Color, math::Vec2};
+
+use crate::{collision_system::{collider::{CircleCollider, Collider}, layer::CollisionLayer}, entity_handler::archetype::BubbleConfig, renderer::artist::DrawCall};
+
+///Fixed id the bubble break emitter is registered under.
+pub const BUBBLE_ID: u64 = 2;
+//Radius the break particles spread over, the one of the shipped shield bearer
+pub const BUBBLE_RADIUS: f32 = 320.0;
+
+/*
+    Shield bubble an enemy projects around itself, when its archetype has a `bubble`.
+    Player bullets that touch it are absorbed, protecting everything inside, until its charges run out and it breaks.
+*/
+#[derive(Clone)]
+pub struct Bubble{
+    pub owner: u64,
+    pos: Vec2,
+    config: BubbleConfig,
+    charges: u32,
+    //Time a broken bubble comes back at
+    regrow_at: Option<f64>,
+    collider: CircleCollider
+}
+
+impl Bubble{
+    pub fn new(owner: u64, pos: Vec2, config: BubbleConfig) -> Bubble{
+        return Bubble {
+            owner: owner,
+            pos: pos,
+            config: config,
+            charges: config.charges,
+            regrow_at: None,
+            collider: CircleCollider::new(pos.x, pos.y, config.radius, CollisionLayer::Enemy)
+        }
+    }
+
+    ///Keeps the bubble on its owner, and brings it back once it had time to regrow.
+    #[inline(always)]
+    pub fn follow(&mut self, pos: Vec2, now: f64){
+        self.pos = pos;
+        self.collider.update(pos);
+
+        if self.regrow_at.is_some_and(|at| now >= at){
+            self.regrow_at = None;
+            self.charges = self.config.charges;
+        }
+    }
+
+    #[inline(always)]
+    pub fn is_active(&self) -> bool{
+        return self.charges > 0
+    }
+
+    pub fn collides(&self, other: &dyn Collider) -> bool{
+        return self.is_active() && other.collide_with_circle(&self.collider)
+    }
+
+    ///Takes a bullet. Returns true if it was the one that broke the bubble.
+    pub fn absorb(&mut self, now: f64) -> bool{
+        if !self.is_active(){
+            return false
+        }
+
+        self.charges -= 1;
+
+        if self.charges == 0{
+            self.regrow_at = Some(now + self.config.regrow);
+            return true
+        }
+        return false
+    }
+
+    ///Outline fading out as the charges run out.
+    pub fn get_draw_call(&self) -> DrawCall{
+        let remaining = self.charges as f32 / self.config.charges.max(1) as f32;
+        let color = Color::new(0.4, 0.8, 1.0, 0.25 + remaining * 0.65);
+
+        return DrawCall::CircleLines(self.pos.x, self.pos.y, self.config.radius, 3.0 + remaining * 5.0, color)
+    }
+}
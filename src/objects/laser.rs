@@ -0,0 +1,44 @@
+use macroquad::{color::Color, math::Vec2, time::get_time};
+
+use crate::renderer::artist::DrawCall;
+
+///Radius of the beam, hits are tested with it.
+pub const LASER_WIDTH: f32 = 10.0;
+
+///Beam left behind by a laser shot. Only drawn, the hits are resolved once when it is fired.
+pub struct Laser{
+    origin: Vec2,
+    end: Vec2,
+    fired_at: f64,
+    duration: f64
+}
+
+impl Laser{
+    pub fn new(origin: Vec2, end: Vec2, duration: f64) -> Laser{
+        return Laser{
+            origin: origin,
+            end: end,
+            fired_at: get_time(),
+            duration: duration
+        }
+    }
+
+    #[inline(always)]
+    pub fn is_active(&self, now: f64) -> bool{
+        return now < self.fired_at + self.duration
+    }
+
+    ///The beam narrows and fades out over its duration.
+    pub fn get_draw_call(&self, now: f64) -> DrawCall{
+        let remaining = (1.0 - (now - self.fired_at) / self.duration).clamp(0.0, 1.0) as f32;
+
+        return DrawCall::Line(
+            self.origin.x,
+            self.origin.y,
+            self.end.x,
+            self.end.y,
+            LASER_WIDTH * 2.0 * remaining,
+            Color::new(1.0, 0.15, 0.35, remaining)
+        )
+    }
+}
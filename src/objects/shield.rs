@@ -1,9 +1,6 @@
-use std::any::Any;
-
-use async_trait::async_trait;
 use macroquad::{color::Color, math::Vec2};
 
-use crate::{collision_system::collider::{CircleCollider, Collider}, event_system::interface::{Drawable, Object, Updatable}, renderer::artist::DrawCall};
+use crate::{collision_system::{collider::{CircleCollider, Collider}, layer::CollisionLayer}, event_system::interface::{Drawable, Object}, renderer::artist::DrawCall};
 
 
 pub struct Shield{
@@ -18,7 +15,7 @@ impl Shield{
         return Shield { 
             pos: pos, 
             size: size, 
-            collider: CircleCollider::new(pos.x, pos.y, size as f32), 
+            collider: CircleCollider::new(pos.x, pos.y, size as f32, CollisionLayer::Player), 
             active: false,
             color: color
         }
@@ -35,6 +32,13 @@ impl Shield{
     pub fn collides(&self, other: &dyn Collider) -> bool{
         return other.collide_with_circle(&self.collider)
     }
+
+    ///Keeps the shield on its owner, in the color of its remaining charges.
+    pub fn follow(&mut self, pos: Vec2, color: Color){
+        self.pos = pos;
+        self.color = color;
+        self.collider.update(pos);
+    }
 }
 
 impl Object for Shield{
@@ -51,22 +55,6 @@ impl Object for Shield{
     }
 }
 
-#[async_trait]
-impl Updatable for Shield{
-    async fn update(&mut self, _delta: f32, mut _params: Vec<Box<dyn Any + Send>>){
-        
-        while let Some(data) = _params.pop(){
-            if let Some(new_pos) = data.downcast_ref::<Vec2>(){
-                self.pos = *new_pos;
-                self.collider.update(*new_pos);
-            }
-            if let Some(color) = data.downcast_ref::<Color>(){
-                self.color = color.to_owned();
-            }
-        }
-    }
-}
-
 impl Drawable for Shield{
     fn get_draw_call(&self) -> DrawCall {
         return DrawCall::Circle(
@@ -122,6 +122,7 @@ impl DrawCall{
     Background: 0
     Grid Background: 1
     Grid Lines: 2
+    Hazards: 2
     Wall: 3
     Enemies: 4
     Boss Outline: 6
@@ -1,10 +1,10 @@
 use std::collections::{HashMap, HashSet, VecDeque};
 
-use async_trait::async_trait;
 use macroquad::{color::Color, math::{vec2, Vec2}};
 use macroquad_particles::{AtlasConfig, BlendMode, ColorCurve, Curve, EmissionShape, Emitter, EmitterConfig, EmittersCache, ParticleShape};
+use serde::{Deserialize, Serialize};
 
-use crate::{event_system::{event::{Event, EventType}, interface::Subscriber}, utils::machine::StateType};
+use crate::{event_system::{event::{Event, EventType}, interface::Subscriber}, objects::bubble::BUBBLE_RADIUS, utils::machine::StateType};
 
 /* 
     MetalArist is also a Batch rendering component, however
@@ -22,13 +22,16 @@ use crate::{event_system::{event::{Event, EventType}, interface::Subscriber}, ut
 
     Permanent emittes are removed upon request from an entity.
 */
-#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Deserialize, Serialize)]
 pub enum ConfigType{
     PlayerDrifting,
     PlayerHit,
     PlayerMove,
     EnemyDeath,
-    RectHit
+    RectHit,
+    WallSparks,
+    PentagonExplosion,
+    BubbleBreak
 }
 impl ConfigType{
     pub fn get_conf(&self) -> EmitterConfig{
@@ -171,6 +174,84 @@ impl ConfigType{
                     },
                     ..Default::default()
                 }
+            },
+            ConfigType::WallSparks => {
+                return EmitterConfig {
+                    local_coords: false,
+                    one_shot: true,
+                    emitting: false,
+                    lifetime: 0.35,
+                    lifetime_randomness: 0.4,
+                    explosiveness: 1.0,
+                    amount: 25,
+                    initial_direction_spread: 2.0 * std::f32::consts::PI,
+                    initial_velocity: 450.0,
+                    initial_velocity_randomness: 0.6,
+                    linear_accel: -600.0,
+                    size: 4.0,
+                    size_randomness: 0.5,
+                    blend_mode: BlendMode::Additive,
+                    colors_curve: ColorCurve {
+                        start: Color::from_rgba(255, 255, 200, 255),  // White hot
+                        mid: Color::from_rgba(255, 180, 40, 220),     // Orange
+                        end: Color::from_rgba(255, 60, 0, 0),         // Fades out red
+                    },
+                    ..Default::default()
+                }
+            },
+            ConfigType::PentagonExplosion => {
+                return EmitterConfig {
+                    local_coords: false,
+                    one_shot: true,
+                    emitting: false,
+                    lifetime: 0.9,
+                    lifetime_randomness: 0.3,
+                    explosiveness: 1.0,
+                    amount: 160,
+                    initial_direction_spread: 2.0 * std::f32::consts::PI,
+                    initial_velocity: 600.0,
+                    initial_velocity_randomness: 0.7,
+                    linear_accel: -650.0,
+                    size: 14.0,
+                    size_randomness: 0.6,
+                    size_curve: Some(Curve {
+                        points: vec![(0.0, 1.0), (0.4, 0.8), (1.0, 0.0)],
+                        ..Default::default()
+                    }),
+                    blend_mode: BlendMode::Additive,
+                    colors_curve: ColorCurve {
+                        start: Color::from_rgba(255, 240, 180, 255),  // Flash
+                        mid: Color::from_rgba(255, 110, 20, 200),     // Fire
+                        end: Color::from_rgba(60, 60, 60, 0),         // Smoke, fading out
+                    },
+                    ..Default::default()
+                }
+            },
+            ConfigType::BubbleBreak => {
+                return EmitterConfig {
+                    local_coords: false,
+                    emission_shape: EmissionShape::Sphere { radius: BUBBLE_RADIUS },
+                    one_shot: true,
+                    emitting: false,
+                    lifetime: 0.8,
+                    lifetime_randomness: 0.3,
+                    explosiveness: 1.0,
+                    amount: 120,
+                    shape: ParticleShape::Circle { subdivisions: 8 },
+                    initial_direction_spread: 2.0 * std::f32::consts::PI,
+                    initial_velocity: 120.0,
+                    initial_velocity_randomness: 0.6,
+                    linear_accel: -100.0,
+                    size: 6.0,
+                    size_randomness: 0.5,
+                    blend_mode: BlendMode::Additive,
+                    colors_curve: ColorCurve {
+                        start: Color::from_rgba(200, 240, 255, 255),  // Bubble white
+                        mid: Color::from_rgba(100, 200, 255, 180),    // Light blue
+                        end: Color::from_rgba(100, 200, 255, 0),      // Fades out
+                    },
+                    ..Default::default()
+                }
             }
         }
     }
@@ -208,6 +289,12 @@ impl MetalArtist{
             EmitterType::Cache(EmittersCache::new(ConfigType::EnemyDeath.get_conf())));
         cache_map.insert(ConfigType::RectHit, 
             EmitterType::Cache(EmittersCache::new(ConfigType::RectHit.get_conf())));
+        cache_map.insert(ConfigType::WallSparks, 
+            EmitterType::Cache(EmittersCache::new(ConfigType::WallSparks.get_conf())));
+        cache_map.insert(ConfigType::PentagonExplosion, 
+            EmitterType::Cache(EmittersCache::new(ConfigType::PentagonExplosion.get_conf())));
+        cache_map.insert(ConfigType::BubbleBreak, 
+            EmitterType::Cache(EmittersCache::new(ConfigType::BubbleBreak.get_conf())));
         cache_map.insert(ConfigType::PlayerDrifting, 
             EmitterType::Emitter(Emitter::new(ConfigType::PlayerDrifting.get_conf())));
         cache_map.insert(ConfigType::PlayerHit, 
@@ -297,9 +384,8 @@ impl MetalArtist{
     }
 }
 
-#[async_trait]
 impl Subscriber for MetalArtist{
-    async fn notify(&mut self, event: &Event){
+    fn notify(&mut self, event: &Event){
         match event.event_type{
             EventType::RegisterEmitterConf => {
                 if let Ok(data) = event.data.try_lock(){
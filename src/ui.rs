@@ -1 +1,2 @@
-pub mod uicontroller;
\ No newline at end of file
+pub mod uicontroller;
+pub mod editor;
\ No newline at end of file
@@ -0,0 +1,384 @@
+use macroquad::{camera::Camera2D, color::{Color, BLACK, DARKGRAY, GREEN, RED, YELLOW}, input::{is_key_down, is_key_pressed, is_mouse_button_down, is_mouse_button_pressed, mouse_position, mouse_wheel, KeyCode, MouseButton}, math::{vec2, Rect, Vec2}, text::draw_text};
+
+use crate::{grid_system::{hazard::Hazard, map::{MapArea, MapLayout, MapPoint}, obstacle::{Obstacle, ObstacleKind, ObstacleLayout}}, renderer::artist::DrawCall, utils::globals::Global};
+
+const PAN_SPEED: f32 = 1500.0;
+const ZOOM_STEP: f32 = 0.00005;
+const MIN_ZOOM: f32 = 0.0001;
+const MAX_ZOOM: f32 = 0.004;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Tool{
+    Obstacle(ObstacleKind),
+    SpawnRegion,
+    PlayerSpawn
+}
+
+impl Tool{
+    fn get_name(&self) -> &str{
+        match self{
+            Tool::Obstacle(ObstacleKind::Pillar) => "Pillar",
+            Tool::Obstacle(ObstacleKind::Barrier) => "Barrier",
+            Tool::Obstacle(ObstacleKind::Crate) => "Crate",
+            Tool::SpawnRegion => "Spawn region",
+            Tool::PlayerSpawn => "Player spawn",
+        }
+    }
+}
+
+///Piece of the map under the mouse.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Item{
+    Obstacle(usize),
+    SpawnRegion(usize)
+}
+
+///What the `GameManager` should do after an editor frame.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EditorAction{
+    None,
+    PlayTest,
+    Exit
+}
+
+/*
+    In-game map editor. Places, moves and deletes obstacles and spawn regions
+    of a `MapLayout` with the mouse, on its own world `Camera2D`.
+
+    Controls:
+        1..5        Pillar, Barrier, Crate, Spawn region and Player spawn tools
+        LMB         Place, or drag the hovered item
+        RMB         Delete the hovered item
+        R           Swap width and height of the hovered item
+        G           Toggle snapping to grid cells
+        WASD/Wheel  Pan and zoom
+        Left/Right  Switch map
+        F5          Save, P play-test, Escape back to the menu
+*/
+pub struct Editor{
+    maps: Vec<MapLayout>,
+    selected: usize,
+    tool: Tool,
+    is_snapping: bool,
+    dragging: Option<(Item, Vec2)>,
+    camera: Camera2D,
+    zoom: f32,
+    status: String
+}
+
+impl Editor{
+    pub fn new(maps: Vec<MapLayout>) -> Editor{
+        let mut editor = Editor{
+            maps: maps,
+            selected: 0,
+            tool: Tool::Obstacle(ObstacleKind::Pillar),
+            is_snapping: true,
+            dragging: None,
+            camera: Camera2D::default(),
+            zoom: 0.0003,
+            status: String::new()
+        };
+        editor.focus();
+
+        return editor
+    }
+
+    ///Maps with the edits made so far.
+    #[inline(always)]
+    pub fn get_maps(&self) -> &Vec<MapLayout>{
+        return &self.maps
+    }
+
+    #[inline(always)]
+    pub fn get_map(&self) -> &MapLayout{
+        return &self.maps[self.selected]
+    }
+
+    #[inline(always)]
+    pub fn get_camera(&self) -> &Camera2D{
+        return &self.camera
+    }
+
+    pub fn update(&mut self, delta: f32) -> EditorAction{
+        self.update_camera(delta);
+
+        let mouse = self.camera.screen_to_world(mouse_position().into());
+        let hovered = self.get_hovered(mouse);
+
+        //Tools
+        let tools = [
+            (KeyCode::Key1, Tool::Obstacle(ObstacleKind::Pillar)),
+            (KeyCode::Key2, Tool::Obstacle(ObstacleKind::Barrier)),
+            (KeyCode::Key3, Tool::Obstacle(ObstacleKind::Crate)),
+            (KeyCode::Key4, Tool::SpawnRegion),
+            (KeyCode::Key5, Tool::PlayerSpawn)
+        ];
+
+        for (key, tool) in tools{
+            if is_key_pressed(key){
+                self.tool = tool;
+            }
+        }
+
+        if is_key_pressed(KeyCode::G){
+            self.is_snapping = !self.is_snapping;
+        }
+
+        if is_key_pressed(KeyCode::Left){
+            self.selected = (self.selected + self.maps.len() - 1) % self.maps.len();
+            self.focus();
+        }
+
+        if is_key_pressed(KeyCode::Right){
+            self.selected = (self.selected + 1) % self.maps.len();
+            self.focus();
+        }
+
+        if is_key_pressed(KeyCode::R){
+            if let Some(item) = hovered{
+                self.rotate(item);
+            }
+        }
+
+        //Mouse
+        if is_mouse_button_pressed(MouseButton::Left){
+            match hovered{
+                Some(item) => self.dragging = Some((item, self.get_item_pos(item) - mouse)),
+                None => self.place(mouse),
+            }
+        }
+
+        if is_mouse_button_down(MouseButton::Left){
+            if let Some((item, offset)) = self.dragging{
+                let pos = self.snap(mouse + offset);
+                self.set_item_pos(item, pos);
+            }
+        }
+        else{
+            self.dragging = None;
+        }
+
+        if is_mouse_button_pressed(MouseButton::Right){
+            if let Some(item) = hovered{
+                self.delete(item);
+            }
+        }
+
+        //Actions
+        if is_key_pressed(KeyCode::F5){
+            self.status = match self.get_map().write(){
+                Ok(_) => format!("Saved {}", self.get_map().get_path()),
+                Err(err) => format!("Failed saving: {}", err),
+            };
+        }
+
+        if is_key_pressed(KeyCode::P){
+            return EditorAction::PlayTest
+        }
+
+        if is_key_pressed(KeyCode::Escape){
+            return EditorAction::Exit
+        }
+
+        return EditorAction::None
+    }
+
+    ///Centers the camera on the map.
+    fn focus(&mut self){
+        let center = self.get_map().get_bounds() / 2.0;
+
+        self.camera.target = vec2(center, center);
+        self.dragging = None;
+    }
+
+    fn update_camera(&mut self, delta: f32){
+        let mut direction = Vec2::ZERO;
+
+        if is_key_down(KeyCode::W){ direction.y -= 1.0; }
+        if is_key_down(KeyCode::S){ direction.y += 1.0; }
+        if is_key_down(KeyCode::A){ direction.x -= 1.0; }
+        if is_key_down(KeyCode::D){ direction.x += 1.0; }
+
+        self.camera.target += direction.normalize_or_zero() * PAN_SPEED * delta;
+
+        let (_, wheel) = mouse_wheel();
+
+        if wheel != 0.0{
+            self.zoom = (self.zoom + wheel.signum() * ZOOM_STEP).clamp(MIN_ZOOM, MAX_ZOOM);
+        }
+        self.camera.zoom = vec2(self.zoom, self.zoom);
+    }
+
+    #[inline(always)]
+    fn snap(&self, pos: Vec2) -> Vec2{
+        if !self.is_snapping{
+            return pos
+        }
+
+        let step = (self.get_map().cell_size / Global::get_editor_snap_subdivisions()) as f32;
+
+        return (pos / step).round() * step
+    }
+
+    ///Items on top are hovered first. Obstacles sit above spawn regions.
+    fn get_hovered(&self, mouse: Vec2) -> Option<Item>{
+        let map = self.get_map();
+
+        if let Some(index) = map.obstacles.iter().rposition(|obstacle| obstacle.contains(mouse)){
+            return Some(Item::Obstacle(index))
+        }
+
+        return map.spawn_regions.iter()
+            .rposition(|region| region.to_rect().contains(mouse))
+            .map(Item::SpawnRegion)
+    }
+
+    ///Obstacles are placed by their center, spawn regions by their top left corner.
+    fn get_item_pos(&self, item: Item) -> Vec2{
+        let map = self.get_map();
+
+        return match item{
+            Item::Obstacle(index) => vec2(map.obstacles[index].x, map.obstacles[index].y),
+            Item::SpawnRegion(index) => vec2(map.spawn_regions[index].x, map.spawn_regions[index].y),
+        }
+    }
+
+    fn set_item_pos(&mut self, item: Item, pos: Vec2){
+        let map = &mut self.maps[self.selected];
+
+        match item{
+            Item::Obstacle(index) => {
+                map.obstacles[index].x = pos.x;
+                map.obstacles[index].y = pos.y;
+            },
+            Item::SpawnRegion(index) => {
+                map.spawn_regions[index].x = pos.x;
+                map.spawn_regions[index].y = pos.y;
+            },
+        }
+    }
+
+    fn place(&mut self, mouse: Vec2){
+        let pos = self.snap(mouse);
+        let cell_size = self.get_map().cell_size as f32;
+        let map = &mut self.maps[self.selected];
+
+        match self.tool{
+            Tool::Obstacle(kind) => {
+                let size = match kind{
+                    ObstacleKind::Pillar => vec2(240.0, 240.0),
+                    ObstacleKind::Barrier => vec2(960.0, 80.0),
+                    ObstacleKind::Crate => vec2(120.0, 120.0),
+                };
+
+                map.obstacles.push(ObstacleLayout{ kind: kind, x: pos.x, y: pos.y, w: size.x, h: size.y });
+            },
+            Tool::SpawnRegion => {
+                map.spawn_regions.push(MapArea{ x: pos.x, y: pos.y, w: cell_size, h: cell_size });
+            },
+            Tool::PlayerSpawn => {
+                map.player_spawn = MapPoint{ x: pos.x, y: pos.y };
+            },
+        }
+    }
+
+    fn delete(&mut self, item: Item){
+        let map = &mut self.maps[self.selected];
+
+        match item{
+            Item::Obstacle(index) => { map.obstacles.remove(index); },
+            Item::SpawnRegion(index) => { map.spawn_regions.remove(index); },
+        }
+        self.dragging = None;
+    }
+
+    ///Pillars are round, so only rect items rotate.
+    fn rotate(&mut self, item: Item){
+        let map = &mut self.maps[self.selected];
+
+        match item{
+            Item::Obstacle(index) => {
+                let obstacle = &mut map.obstacles[index];
+                std::mem::swap(&mut obstacle.w, &mut obstacle.h);
+            },
+            Item::SpawnRegion(index) => {
+                let region = &mut map.spawn_regions[index];
+                std::mem::swap(&mut region.w, &mut region.h);
+            },
+        }
+    }
+
+    ///World space draw calls of the edited map.
+    pub fn get_draw_calls(&self) -> Vec<(i32, DrawCall)>{
+        let map = self.get_map();
+        let bounds = map.get_bounds();
+        let (background, lines) = map.get_grid_colors();
+
+        let mut draw_calls = vec![(1, DrawCall::Rectangle(0.0, 0.0, bounds, bounds, background))];
+
+        //Cells
+        for index in 0..=map.grid_size{
+            let offset = (index * map.cell_size) as f32;
+
+            draw_calls.push((2, DrawCall::Line(offset, 0.0, offset, bounds, 4.0, lines)));
+            draw_calls.push((2, DrawCall::Line(0.0, offset, bounds, offset, 4.0, lines)));
+        }
+
+        draw_calls.push((3, DrawCall::RectangleLines(0.0, 0.0, bounds, bounds, 20.0, RED)));
+
+        //Spawn regions
+        for region in map.spawn_regions.iter(){
+            draw_calls.push((2, DrawCall::Rectangle(region.x, region.y, region.w, region.h, Color::new(1.0, 0.0, 0.0, 0.15))));
+            draw_calls.push((2, DrawCall::RectangleLines(region.x, region.y, region.w, region.h, 6.0, RED)));
+        }
+
+        //Hazards
+        for (index, layout) in map.hazards.iter().enumerate(){
+            let hazard = Hazard::new(index as u64, layout);
+            draw_calls.extend(hazard.get_draw_calls().into_iter().map(|call| (2, call)));
+        }
+
+        //Obstacles
+        for (index, layout) in map.obstacles.iter().enumerate(){
+            let obstacle = Obstacle::new(index as u64, layout);
+            draw_calls.extend(obstacle.get_draw_calls().into_iter().map(|call| (3, call)));
+        }
+
+        //Player spawn
+        draw_calls.push((10, DrawCall::Circle(map.player_spawn.x, map.player_spawn.y, 40.0, GREEN)));
+        draw_calls.push((10, DrawCall::CircleLines(map.player_spawn.x, map.player_spawn.y, 40.0, 6.0, BLACK)));
+
+        //Hovered item
+        let mouse = self.camera.screen_to_world(mouse_position().into());
+
+        if let Some(item) = self.get_hovered(mouse){
+            let outline = match item{
+                Item::Obstacle(index) => {
+                    let obstacle = &map.obstacles[index];
+                    Rect::new(obstacle.x - obstacle.w / 2.0, obstacle.y - obstacle.h / 2.0, obstacle.w, obstacle.h)
+                },
+                Item::SpawnRegion(index) => map.spawn_regions[index].to_rect(),
+            };
+
+            draw_calls.push((10, DrawCall::RectangleLines(outline.x, outline.y, outline.w, outline.h, 8.0, YELLOW)));
+        }
+
+        return draw_calls
+    }
+
+    ///Screen space overlay, drawn after the world camera is reset.
+    pub fn draw_overlay(&self){
+        let snapping = match self.is_snapping{
+            true => "on",
+            false => "off",
+        };
+
+        draw_text(&format!("Map: {}   Tool: {}   Snap: {}", self.get_map().name, self.tool.get_name(), snapping), 20.0, 40.0, 30.0, BLACK);
+        draw_text("1-5 tools, LMB place/drag, RMB delete, R rotate, G snap, WASD/Wheel camera, Left/Right map", 20.0, 75.0, 20.0, DARKGRAY);
+        draw_text("F5 save, P play-test, Escape exit", 20.0, 100.0, 20.0, DARKGRAY);
+
+        if !self.status.is_empty(){
+            draw_text(&self.status, 20.0, 135.0, 24.0, RED);
+        }
+    }
+}
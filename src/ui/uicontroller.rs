@@ -1,6 +1,5 @@
 use std::{collections::HashMap, sync::mpsc::Sender};
 
-use async_trait::async_trait;
 use macroquad::{color::{BLACK, GRAY, WHITE}, file::set_pc_assets_folder, math::Vec2, prelude::ImageFormat, text::{draw_text_ex, load_ttf_font, load_ttf_font_from_bytes, Font, TextParams}, texture::{draw_texture_ex, DrawTextureParams, Image, Texture2D}};
 
 use crate::{entity_handler::enemy_type::EnemyType, event_system::{event::{Event, EventType}, interface::{Publisher, Subscriber}}, utils::globals::Global};
@@ -98,28 +97,11 @@ impl UIController{
     }
 
 
-    fn get_new_points(&mut self, enemies: Vec<EnemyType>) -> f64{
-        let scores = Global::get_enemy_points();
-
-        let circle_score = scores.get(0).unwrap();
-        let triangle_score = scores.get(1).unwrap();
-        let rect_score = scores.get(2).unwrap();
-        let hexagon_score = scores.get(3).unwrap();
-        let boss_score = scores.get(4).unwrap();
-
-        let mut points = 0.0;
-
-        enemies.iter()
-            .for_each(|enemy_type| {
-                match enemy_type{
-                    EnemyType::Circle => points += circle_score,
-                    EnemyType::Triangle => points += triangle_score,
-                    EnemyType::Rect => points += rect_score,
-                    EnemyType::Hexagon => points += hexagon_score,
-                    EnemyType::CircleBoss => points += boss_score,
-                    EnemyType::TriangleBoss => points += boss_score,
-                }
-            }); 
+    ///Scores killed enemies, with the level they were elites of, 0 if they weren't.
+    fn get_new_points(&mut self, enemies: Vec<(EnemyType, u8)>) -> f64{
+        let points = enemies.iter()
+            .map(|(enemy_type, level)| enemy_type.get_score() * (1.0 + Global::get_elite_bonus() * *level as f64))
+            .sum::<f64>();
         
         self.score += points;
         
@@ -323,13 +305,12 @@ impl UIController{
     }
 }
 
-#[async_trait]
 impl Subscriber for UIController {
-    async fn notify(&mut self, event: &Event){
+    fn notify(&mut self, event: &Event){
         match &event.event_type{
             EventType::AddScorePoints => {
                 if let Ok(request) = event.data.lock(){
-                    if let Some(data) = request.downcast_ref::<(u64, Vec<EnemyType>)>(){
+                    if let Some(data) = request.downcast_ref::<(u64, Vec<(EnemyType, u8)>)>(){
                         let new_data = data.to_owned();
                         let kills = new_data.0;
                         let points = self.get_new_points(new_data.1);
@@ -416,9 +397,8 @@ impl Subscriber for UIController {
 }
 
 
-#[async_trait]
 impl Publisher for UIController {
-    async fn publish(&self, event: Event){
+    fn publish(&self, event: Event){
         let _ = self.sender.send(event);
     }
 }
\ No newline at end of file
@@ -1,5 +1,10 @@
 pub mod timer;
+pub mod clock;
 pub mod globals;
 pub mod machine;
 pub mod counter;
-pub mod tinkerer;
\ No newline at end of file
+pub mod tinkerer;
+pub mod knockback;
+
+#[cfg(test)]
+pub mod harness;
@@ -0,0 +1,33 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use macroquad::time::get_time;
+
+static FRAME_TIME: AtomicU64 = AtomicU64::new(0);
+
+/*
+    Frame clock.
+
+    Macroquads `get_time` can only be called from the main thread. Entities that
+    are updated on worker threads read the time of the current frame from here instead.
+*/
+pub struct Clock;
+
+impl Clock{
+    ///Stores the current time. Called on the main thread, before entities update.
+    #[inline(always)]
+    pub fn tick(){
+        FRAME_TIME.store(get_time().to_bits(), Ordering::Relaxed);
+    }
+
+    ///Stores `time` as the current frame, for frames driven without a window.
+    #[cfg(test)]
+    pub fn set(time: f64){
+        FRAME_TIME.store(time.to_bits(), Ordering::Relaxed);
+    }
+
+    ///Time of the current frame.
+    #[inline(always)]
+    pub fn now() -> f64{
+        return f64::from_bits(FRAME_TIME.load(Ordering::Relaxed))
+    }
+}
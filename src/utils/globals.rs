@@ -26,10 +26,43 @@ impl Global{
         return 8
     }
 
+    ///Cell size of the `Grid` spatial hash. Independent of the map cells.
+    pub fn get_hash_cell_size() -> f32{
+        return 240.0
+    }
+
     pub fn get_cell_capacity() -> usize{
         return 0
     }
 
+    ///Flow field nodes per `Grid` cell side.
+    pub fn get_flow_field_subdivisions() -> i32{
+        return 8
+    }
+
+    pub fn get_flow_field_refresh() -> f64{
+        return 0.25
+    }
+
+    ///Editor snapping steps per `Grid` cell side.
+    pub fn get_editor_snap_subdivisions() -> i32{
+        return 8
+    }
+
+
+    /* 
+            Entity handler
+    */
+    ///Enemy count past which enemies are updated on worker threads. Off, it was slower on a single core.
+    pub fn get_parallel_update_threshold() -> usize{
+        return usize::MAX
+    }
+
+    ///Bullets alive at once, shared by the player and the enemies.
+    pub fn get_projectile_capacity() -> usize{
+        return 1024
+    }
+
 
     /* 
             Factory
@@ -53,10 +86,6 @@ impl Global{
     /* 
         Triangle Assistant
     */
-    pub fn get_triangle_assistant_pool_size() -> usize{
-        return 128
-    }
-
     pub fn get_triangle_bullet_amount() -> usize{
         return 10
     }
@@ -88,20 +117,45 @@ impl Global{
         return 5
     }
 
+    pub fn get_laser_cooldown() -> f64{
+        return 3.0
+    }
+
+    ///How far the laser reaches when nothing stops it.
+    pub fn get_laser_length() -> f32{
+        return 2400.0
+    }
+
+
+    /* 
+        Enemy steering
+    */
+    ///Distance past the touching point, in which neighbours are taken into account.
+    pub fn get_steering_radius() -> f32{
+        return 40.0
+    }
+
+    pub fn get_separation_weight() -> f32{
+        return 1.5
+    }
+
+    pub fn get_alignment_weight() -> f32{
+        return 0.3
+    }
+
 
     
     /* 
         UIController 
     */
-    pub fn get_enemy_points() -> Vec<f64> {
-        let circle = 5.0;
-        let triangle = 8.0;
-        let rect = 15.0;
-        let hexagon = 50.0;
-
-        let boss = 200.0;
+    ///Points of either boss. Other enemies are scored by their archetype.
+    pub fn get_boss_points() -> f64 {
+        return 200.0
+    }
 
-        return vec![circle, triangle, rect, hexagon, boss]
+    ///Extra share of its points an elite is worth, per level.
+    pub fn get_elite_bonus() -> f64 {
+        return 0.5
     }
 }
 
@@ -0,0 +1,200 @@
+use std::{alloc::{GlobalAlloc, Layout}, sync::{atomic::{AtomicUsize, Ordering}, mpsc::{channel, Receiver, Sender}}, time::Instant};
+
+use macroquad::{color::WHITE, math::Vec2};
+use mimalloc::MiMalloc;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{actors::drone::Drone, entity_handler::{archetype::Archetypes, enemy_type::EnemyType, entity_handler::Handler, update_context::{Difficulty, FrameContext}}, event_system::{event::{Event, EventType}, interface::{Enemy, Subscriber}}, utils::{clock::Clock, globals::Global}};
+
+//Frames are stepped at a fixed 60 fps
+const DELTA: f32 = 1.0 / 60.0;
+//Enemies spawn in a ring around the player, between these distances
+const SPAWN_MIN: f32 = 400.0;
+const SPAWN_MAX: f32 = 2500.0;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+/*
+    Global allocator of the tests. Counts every allocation and reallocation, on any thread,
+    and hands them to `MiMalloc` like the game does. Counts are only meaningful with `--test-threads=1`.
+*/
+pub struct CountingAllocator;
+
+impl CountingAllocator{
+    ///Allocations since the start of the process.
+    #[inline(always)]
+    pub fn count() -> usize{
+        return ALLOCATIONS.load(Ordering::Relaxed)
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator{
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8{
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        return MiMalloc.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8{
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        return MiMalloc.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8{
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        return MiMalloc.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout){
+        MiMalloc.dealloc(ptr, layout);
+    }
+}
+
+/*
+    Headless harness. Drives a `Handler` frame by frame without a window, stepping the `Clock` by hand.
+
+    Enemies are drones of the given types, every archetype by default, spread around a still player
+    in the middle of the map, and topped back up as they die. Events the handler and its enemies publish are drained every frame
+    and counted, nothing routes them, so the `Grid` and the collisions aren't part of a frame.
+*/
+pub struct Harness{
+    handler: Handler,
+    sender: Sender<Event>,
+    receiver: Receiver<Event>,
+    frame: FrameContext,
+    rng: StdRng,
+    types: Vec<EnemyType>,
+    //Enemies kept alive
+    enemies: usize,
+    //Events drained so far
+    events: usize
+}
+
+impl Harness{
+    pub fn new(enemies: usize, seed: u64) -> Harness{
+        return Harness::with_types(enemies, seed, Archetypes::get_types_up_to(u8::MAX))
+    }
+
+    pub fn with_types(enemies: usize, seed: u64, types: Vec<EnemyType>) -> Harness{
+        let (sender, receiver) = channel();
+        let center = (Global::get_grid_size() * Global::get_cell_size()) as f32 / 2.0;
+
+        let mut frame = FrameContext::new(0.0, Difficulty::default());
+        frame.set_player(Vec2::splat(center), Vec2::ZERO, false);
+
+        let mut harness = Harness {
+            handler: Handler::new(sender.clone()),
+            sender: sender,
+            receiver: receiver,
+            frame: frame,
+            rng: StdRng::seed_from_u64(seed),
+            types: types,
+            enemies: enemies,
+            events: 0
+        };
+        harness.top_up();
+        harness.drain();
+
+        return harness
+    }
+
+    pub fn handler_mut(&mut self) -> &mut Handler{
+        return &mut self.handler
+    }
+
+    pub fn get_events(&self) -> usize{
+        return self.events
+    }
+
+    /*
+        Runs one frame. Returns how long the whole handler update, and the entity update within it, took,
+        along with the allocations the handler update made.
+    */
+    pub fn step(&mut self) -> (f64, f64, usize){
+        self.frame.time += DELTA as f64;
+        Clock::set(self.frame.time);
+
+        let allocations = CountingAllocator::count();
+        let started = Instant::now();
+        self.handler.update(DELTA, &self.frame);
+        let elapsed = started.elapsed().as_secs_f64();
+        let allocations = CountingAllocator::count() - allocations;
+
+        self.drain();
+        self.top_up();
+
+        return (elapsed, self.handler.get_update_time(), allocations)
+    }
+
+    pub fn run(&mut self, frames: usize) -> Timings{
+        let mut timings = Timings::with_capacity(frames);
+
+        for _ in 0..frames{
+            let (frame, update, allocations) = self.step();
+            timings.frames.push(frame);
+            timings.updates.push(update);
+            timings.allocations.push(allocations);
+        }
+
+        return timings
+    }
+
+    fn drain(&mut self){
+        self.events += self.receiver.try_iter().count();
+    }
+
+    ///Spawns drones in place of the ones that died, like the `Factory` would.
+    fn top_up(&mut self){
+        let missing = self.enemies.saturating_sub(self.handler.get_enemy_count());
+
+        let enemies = (0..missing)
+            .map(|_| {
+                let kind = self.types[self.rng.gen_range(0..self.types.len())];
+                let size = match kind{
+                    EnemyType::Drone(id) => Archetypes::get(id).size.sample(),
+                    _ => 40.0
+                };
+
+                let angle = self.rng.gen_range(0.0..std::f32::consts::TAU);
+                let distance = self.rng.gen_range(SPAWN_MIN..SPAWN_MAX);
+                let pos = self.frame.player_pos + Vec2::from_angle(angle) * distance;
+
+                let enemy: Box<dyn Enemy> = Box::new(Drone::with_archetype(0, kind, pos, size, WHITE, self.sender.clone()));
+                Some(enemy)
+            })
+            .collect::<Vec<Option<Box<dyn Enemy>>>>();
+
+        if !enemies.is_empty(){
+            self.handler.notify(&Event::new(enemies, EventType::BatchEnemySpawn));
+        }
+    }
+}
+
+///Per frame durations of a run in seconds, and allocations.
+pub struct Timings{
+    pub frames: Vec<f64>,
+    pub updates: Vec<f64>,
+    pub allocations: Vec<usize>
+}
+
+impl Timings{
+    fn with_capacity(frames: usize) -> Timings{
+        return Timings {
+            frames: Vec::with_capacity(frames),
+            updates: Vec::with_capacity(frames),
+            allocations: Vec::with_capacity(frames)
+        }
+    }
+
+    pub fn mean(samples: &[f64]) -> f64{
+        return samples.iter().sum::<f64>() / samples.len().max(1) as f64
+    }
+
+    ///Sample below which `percent` of them fall.
+    pub fn percentile(samples: &[f64], percent: f64) -> f64{
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+
+        let index = ((sorted.len() as f64 - 1.0) * percent / 100.0).round() as usize;
+        return sorted.get(index).copied().unwrap_or_default()
+    }
+}
@@ -0,0 +1,49 @@
+use macroquad::math::Vec2;
+
+//Default rate at which knockback decays per second.
+pub const DEFAULT_DAMPING: f32 = 6.0;
+//Below this speed knockback is dropped.
+const REST_THRESHOLD: f32 = 5.0;
+
+/* 
+    Knockback is the velocity an entity receives from impacts.
+    It is applied on top of the entities own movement and decays over time.
+*/
+#[derive(Clone, Copy)]
+pub struct Knockback{
+    velocity: Vec2,
+    damping: f32
+}
+
+impl Knockback{
+    pub fn new(damping: f32) -> Knockback{
+        return Knockback{
+            velocity: Vec2::ZERO,
+            damping: damping
+        }
+    }
+
+    ///Adds an impulse, already scaled by the entities mass.
+    #[inline(always)]
+    pub fn apply(&mut self, impulse: Vec2){
+        self.velocity += impulse;
+    }
+
+    ///Returns the displacement of this frame and decays the velocity.
+    #[inline(always)]
+    pub fn step(&mut self, delta: f32) -> Vec2{
+        let displacement = self.velocity * delta;
+
+        self.velocity *= (1.0 - self.damping * delta).max(0.0);
+
+        if self.velocity.length() < REST_THRESHOLD{
+            self.velocity = Vec2::ZERO;
+        }
+
+        return displacement
+    }
+
+    pub fn reset(&mut self){
+        self.velocity = Vec2::ZERO;
+    }
+}